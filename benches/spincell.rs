@@ -0,0 +1,221 @@
+// Benchmarks for the three costs that matter for a lazy cell: the first
+// initialization, reads once initialized, and initialization races between
+// threads. Each one is measured for SpinCell and for std's LazyLock as the
+// baseline.
+//
+// Run with `cargo bench`. The harness is deliberately small: it only needs
+// std, so the suite runs on any host the crate builds on.
+//
+// The scaling groups repeat the contended measurements from 2 up to 128
+// threads for each wait strategy, to show where one starts to beat another
+// on big machines. `cargo bench --features std` adds `Yield` to them, and
+// SPINCELL_BENCH_MAX_THREADS caps the thread count on smaller hosts.
+
+use std::env;
+use std::hint::black_box;
+use std::sync::{Barrier, LazyLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+use spincell::Yield;
+use spincell::{Backoff, ConfiguredSpinCell, Spin, SpinCell, WaitStrategy};
+
+const INIT_ITERS: u32 = 100_000;
+const READ_ITERS: u32 = 10_000_000;
+const RACE_ROUNDS: u32 = 1_000;
+const THREAD_COUNTS: [usize; 4] = [1, 2, 4, 8];
+const SCALING_ROUNDS: u32 = 100;
+const SCALING_THREADS: [usize; 7] = [2, 4, 8, 16, 32, 64, 128];
+const READ_ROUNDS: u32 = 10;
+const SCALING_READS: u32 = 1_000_000;
+// Spin-loop hints the initializer of the scaling cells spends, so waiters
+// actually wait and the strategy has something to do.
+const INIT_WORK: u32 = 2_000;
+
+fn report(group: &str, name: &str, elapsed: Duration, iters: u32) {
+    let per_iter = elapsed.as_nanos() as f64 / f64::from(iters);
+    println!("{group:<16} {name:<24} {per_iter:>10.2} ns/iter");
+}
+
+fn first_init() {
+    let start = Instant::now();
+    for i in 0..INIT_ITERS {
+        let cell = SpinCell::new(|| black_box(i));
+        black_box(*cell);
+    }
+    report("first-init", "SpinCell", start.elapsed(), INIT_ITERS);
+
+    let start = Instant::now();
+    for i in 0..INIT_ITERS {
+        let cell = LazyLock::new(|| black_box(i));
+        black_box(*cell);
+    }
+    report("first-init", "LazyLock", start.elapsed(), INIT_ITERS);
+}
+
+fn hot_read() {
+    static SPIN: SpinCell<u64> = SpinCell::new(|| 42);
+    static STD: LazyLock<u64> = LazyLock::new(|| 42);
+
+    black_box(*SPIN);
+    let start = Instant::now();
+    for _ in 0..READ_ITERS {
+        black_box(**black_box(&SPIN));
+    }
+    report("hot-read", "SpinCell", start.elapsed(), READ_ITERS);
+
+    black_box(*STD);
+    let start = Instant::now();
+    for _ in 0..READ_ITERS {
+        black_box(**black_box(&STD));
+    }
+    report("hot-read", "LazyLock", start.elapsed(), READ_ITERS);
+}
+
+// Releases `threads` threads at once onto a fresh cell per round and
+// measures how long it takes until all of them have read the value.
+fn race<C: Sync>(threads: usize, rounds: u32, make: fn() -> C, read: fn(&C) -> u64) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..rounds {
+        let cell = make();
+        let barrier = Barrier::new(threads + 1);
+        let mut start = None;
+        thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| {
+                    barrier.wait();
+                    black_box(read(&cell));
+                });
+            }
+            barrier.wait();
+            start = Some(Instant::now());
+        });
+        // The scope only returns once every reader has been joined.
+        total += start.unwrap().elapsed();
+    }
+    total
+}
+
+fn contended_init() {
+    for threads in THREAD_COUNTS {
+        let elapsed = race(
+            threads,
+            RACE_ROUNDS,
+            || SpinCell::<u64>::new(|| black_box(42)),
+            |c: &SpinCell<u64>| **c,
+        );
+        report(
+            "contended-init",
+            &format!("SpinCell/{threads}"),
+            elapsed,
+            RACE_ROUNDS,
+        );
+
+        let elapsed = race(
+            threads,
+            RACE_ROUNDS,
+            || LazyLock::<u64>::new(|| black_box(42)),
+            |c: &LazyLock<u64>| **c,
+        );
+        report(
+            "contended-init",
+            &format!("LazyLock/{threads}"),
+            elapsed,
+            RACE_ROUNDS,
+        );
+    }
+}
+
+fn scaling_threads() -> impl Iterator<Item = usize> {
+    let max = env::var("SPINCELL_BENCH_MAX_THREADS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(usize::MAX);
+    SCALING_THREADS.into_iter().filter(move |&n| n <= max)
+}
+
+fn slow_init() -> u64 {
+    for _ in 0..black_box(INIT_WORK) {
+        std::hint::spin_loop();
+    }
+    42
+}
+
+fn configured<S: WaitStrategy>(strategy: S) -> ConfiguredSpinCell<u64, fn() -> u64, S> {
+    SpinCell::builder(slow_init as fn() -> u64)
+        .strategy(strategy)
+        .build()
+}
+
+// Latency of an initialization raced by every thread, from the release of
+// the threads until all of them have read the value.
+fn init_scaling<S: WaitStrategy + Default + Sync>(name: &str) {
+    for threads in scaling_threads() {
+        let elapsed = race(
+            threads,
+            SCALING_ROUNDS,
+            || configured(S::default()),
+            |c| **c,
+        );
+        report(
+            "init-scaling",
+            &format!("{name}/{threads}"),
+            elapsed,
+            SCALING_ROUNDS,
+        );
+    }
+}
+
+// Read throughput of threads that all start on a cold cell, so the first
+// reads of each thread wait for the initializer under the strategy and the
+// rest hit the initialized fast path. Reported per read across all threads.
+fn read_scaling<S: WaitStrategy + Default + Sync>(name: &str) {
+    for threads in scaling_threads() {
+        let mut total = Duration::ZERO;
+        for _ in 0..READ_ROUNDS {
+            let cell = configured(S::default());
+            let barrier = Barrier::new(threads + 1);
+            let mut start = None;
+            thread::scope(|s| {
+                for _ in 0..threads {
+                    s.spawn(|| {
+                        barrier.wait();
+                        for _ in 0..SCALING_READS / threads as u32 {
+                            black_box(**black_box(&cell));
+                        }
+                    });
+                }
+                // With more threads than cores, some readers may be done
+                // before this thread runs again, so the clock starts first.
+                start = Some(Instant::now());
+                barrier.wait();
+            });
+            total += start.unwrap().elapsed();
+        }
+        report(
+            "read-scaling",
+            &format!("{name}/{threads}"),
+            total,
+            READ_ROUNDS * SCALING_READS,
+        );
+    }
+}
+
+fn scaling() {
+    init_scaling::<Backoff>("Backoff");
+    init_scaling::<Spin>("Spin");
+    #[cfg(feature = "std")]
+    init_scaling::<Yield>("Yield");
+    read_scaling::<Backoff>("Backoff");
+    read_scaling::<Spin>("Spin");
+    #[cfg(feature = "std")]
+    read_scaling::<Yield>("Yield");
+}
+
+fn main() {
+    first_init();
+    hot_read();
+    contended_init();
+    scaling();
+}
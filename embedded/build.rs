@@ -0,0 +1,18 @@
+// Puts the memory layout of the target machine on the linker search path
+// as memory.x, where the runtime crates' link.x expects it.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    let layout = match env::var("CARGO_CFG_TARGET_ARCH").as_deref() {
+        Ok("arm") => "memory-lm3s6965.x",
+        Ok("riscv32") => "memory-virt.x",
+        _ => return,
+    };
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::copy(layout, out.join("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed={layout}");
+}
@@ -0,0 +1,74 @@
+// The software interrupt the tests raise to run code in interrupt context,
+// and interrupt masking for the stub FreeRTOS port.
+
+#[cfg(target_arch = "arm")]
+mod imp {
+    use cortex_m::peripheral::SCB;
+    use cortex_m_rt::exception;
+
+    pub fn init() {}
+
+    // Pends PendSV, which runs as soon as interrupts are unmasked.
+    pub fn pend() {
+        SCB::set_pendsv();
+        cortex_m::asm::dsb();
+        cortex_m::asm::isb();
+    }
+
+    pub fn disable() {
+        cortex_m::interrupt::disable();
+    }
+
+    pub fn enable() {
+        // Safety: only called when leaving the outermost critical section.
+        unsafe { cortex_m::interrupt::enable() };
+    }
+
+    #[exception]
+    fn PendSV() {
+        crate::dispatch();
+    }
+}
+
+#[cfg(target_arch = "riscv32")]
+mod imp {
+    use core::ptr::write_volatile;
+
+    // The machine software interrupt pending bit of hart 0 in the CLINT of
+    // QEMU's virt machine.
+    const MSIP: *mut u32 = 0x0200_0000 as *mut u32;
+
+    pub fn init() {
+        // Safety: the tests run on a single hart with no other handlers.
+        unsafe {
+            riscv::register::mie::set_msoft();
+            riscv::interrupt::enable();
+        }
+    }
+
+    // Raises the machine software interrupt, which is taken as soon as
+    // interrupts are unmasked.
+    pub fn pend() {
+        // Safety: MSIP is a device register of the virt machine.
+        unsafe { write_volatile(MSIP, 1) };
+        riscv::asm::fence();
+    }
+
+    pub fn disable() {
+        riscv::interrupt::disable();
+    }
+
+    pub fn enable() {
+        // Safety: only called when leaving the outermost critical section.
+        unsafe { riscv::interrupt::enable() };
+    }
+
+    #[export_name = "MachineSoft"]
+    fn machine_soft() {
+        // Safety: as in `pend`.
+        unsafe { write_volatile(MSIP, 0) };
+        crate::dispatch();
+    }
+}
+
+pub use imp::{disable, enable, init, pend};
@@ -0,0 +1,56 @@
+// Runs the embedded tests on QEMU and reports the result through
+// semihosting. Each test is a plain function that panics on failure; the
+// semihosting panic handler then prints the message and exits QEMU with a
+// failure status.
+#![no_std]
+#![no_main]
+
+mod arch;
+mod port;
+mod tests;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use semihosting::{println, process};
+
+// The function the software interrupt calls, stored as an address.
+static HANDLER: AtomicUsize = AtomicUsize::new(0);
+
+// Sets what the next software interrupt runs.
+fn on_interrupt(f: fn()) {
+    HANDLER.store(f as usize, Ordering::SeqCst);
+}
+
+// Called by the interrupt handler in `arch`.
+fn dispatch() {
+    let f = HANDLER.load(Ordering::SeqCst);
+    if f != 0 {
+        // Safety: HANDLER only ever holds 0 or a `fn()`.
+        let f = unsafe { core::mem::transmute::<usize, fn()>(f) };
+        f();
+    }
+}
+
+fn run() -> ! {
+    arch::init();
+    for (name, test) in tests::TESTS {
+        println!("test {name} ...");
+        test();
+        on_interrupt(|| {});
+        println!("test {name} ... ok");
+    }
+    println!("{} tests passed", tests::TESTS.len());
+    process::exit(0)
+}
+
+#[cfg(target_arch = "arm")]
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    run()
+}
+
+#[cfg(target_arch = "riscv32")]
+#[riscv_rt::entry]
+fn main() -> ! {
+    run()
+}
@@ -0,0 +1,21 @@
+// Stands in for the FreeRTOS port functions behind spincell's `Critical`
+// initializers. Critical sections mask interrupts and nest, as in the real
+// Cortex-M and RISC-V ports.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+// The number of critical sections entered and not yet left.
+pub static NESTING: AtomicU32 = AtomicU32::new(0);
+
+#[no_mangle]
+extern "C" fn vPortEnterCritical() {
+    crate::arch::disable();
+    NESTING.fetch_add(1, Ordering::Relaxed);
+}
+
+#[no_mangle]
+extern "C" fn vPortExitCritical() {
+    if NESTING.fetch_sub(1, Ordering::Relaxed) == 1 {
+        crate::arch::enable();
+    }
+}
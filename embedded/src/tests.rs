@@ -0,0 +1,100 @@
+// The tests run in order on a single core. Those that touch a cell from
+// the software interrupt use statics of their own, as the handler cannot
+// borrow from the test.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use spincell::freertos::CriticalSpinCell;
+use spincell::SpinCell;
+
+use crate::port::NESTING;
+use crate::{arch, on_interrupt};
+
+pub static TESTS: &[(&str, fn())] = &[
+    ("static_initializes_once", static_initializes_once),
+    (
+        "ready_static_is_read_from_isr",
+        ready_static_is_read_from_isr,
+    ),
+    ("isr_runs_initializer", isr_runs_initializer),
+    ("isr_get_during_init", isr_get_during_init),
+    (
+        "critical_initializer_masks_isr",
+        critical_initializer_masks_isr,
+    ),
+];
+
+fn static_initializes_once() {
+    static RUNS: AtomicU32 = AtomicU32::new(0);
+    static CELL: SpinCell<u32> = SpinCell::new(|| {
+        RUNS.fetch_add(1, Ordering::Relaxed);
+        0x5eed
+    });
+    assert!(!SpinCell::is_initialized(&CELL));
+    assert_eq!(*CELL, 0x5eed);
+    assert_eq!(*CELL, 0x5eed);
+    assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+}
+
+// A cell built from a value is READY from the start, so an interrupt can
+// read it before main touches it.
+fn ready_static_is_read_from_isr() {
+    static CELL: SpinCell<[u8; 4]> = SpinCell::from_value([10, 0, 0, 1]);
+    static SEEN: AtomicU32 = AtomicU32::new(0);
+    on_interrupt(|| SEEN.store(u32::from_be_bytes(*CELL), Ordering::Relaxed));
+    arch::pend();
+    assert_eq!(SEEN.load(Ordering::Relaxed), 0x0a00_0001);
+}
+
+// The first access may come from an interrupt handler, which then runs the
+// initializer in interrupt context.
+fn isr_runs_initializer() {
+    static RUNS: AtomicU32 = AtomicU32::new(0);
+    static CELL: SpinCell<u32> = SpinCell::new(|| {
+        RUNS.fetch_add(1, Ordering::Relaxed);
+        7
+    });
+    on_interrupt(|| assert_eq!(*CELL, 7));
+    arch::pend();
+    assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+    assert_eq!(*CELL, 7);
+    assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+}
+
+// An interrupt taken while main is running the initializer must not wait
+// for it, as main cannot finish until the handler returns. `get` reports
+// the value as missing instead of spinning.
+fn isr_get_during_init() {
+    static RAN: AtomicBool = AtomicBool::new(false);
+    static CELL: SpinCell<u32> = SpinCell::new(|| {
+        arch::pend();
+        3
+    });
+    on_interrupt(|| {
+        assert_eq!(SpinCell::get(&CELL), None);
+        RAN.store(true, Ordering::Relaxed);
+    });
+    assert_eq!(*CELL, 3);
+    assert!(RAN.load(Ordering::Relaxed));
+}
+
+// An interrupt raised by a critical initializer is held off until the
+// initializer has returned, and taken as soon as the critical section is
+// left. That is before the value is published, so a handler that needs the
+// value could still not wait for it.
+fn critical_initializer_masks_isr() {
+    static TAKEN: AtomicBool = AtomicBool::new(false);
+    static CELL: CriticalSpinCell<u32> = CriticalSpinCell::new_critical(|| {
+        arch::pend();
+        assert!(
+            !TAKEN.load(Ordering::Relaxed),
+            "interrupt taken in the critical section"
+        );
+        assert_eq!(NESTING.load(Ordering::Relaxed), 1);
+        11
+    });
+    on_interrupt(|| TAKEN.store(true, Ordering::Relaxed));
+    assert_eq!(*CELL, 11);
+    assert!(TAKEN.load(Ordering::Relaxed));
+    assert_eq!(NESTING.load(Ordering::Relaxed), 0);
+}
@@ -0,0 +1,28 @@
+// libfuzzer-sys aborts from its panic hook, which would turn the panics the
+// targets provoke on purpose into crashes. `catch` lets panics inside `f`
+// unwind to it as usual, while any other panic still aborts.
+
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+use std::thread::Result;
+
+thread_local! {
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn catch<R>(f: impl FnOnce() -> R) -> Result<R> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let abort = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING.with(Cell::get) {
+                abort(info);
+            }
+        }));
+    });
+    CATCHING.with(|c| c.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|c| c.set(false));
+    result
+}
@@ -0,0 +1,79 @@
+// Drives a SpinResetCell through reads, mutations and resets encoded in the
+// input, with an initializer that panics on the runs selected by the first
+// byte. A panicking initializer leaves the cell empty rather than poisoned.
+#![no_main]
+
+mod quiet;
+
+use std::cell::Cell;
+
+use libfuzzer_sys::fuzz_target;
+use spincell::SpinResetCell;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&failing, ops)) = data.split_first() else {
+        return;
+    };
+    let runs = Cell::new(0u32);
+    let live = Cell::new(0i32);
+
+    struct Value<'a> {
+        n: u8,
+        live: &'a Cell<i32>,
+    }
+    impl Drop for Value<'_> {
+        fn drop(&mut self) {
+            self.live.set(self.live.get() - 1);
+        }
+    }
+
+    {
+        let cell = SpinResetCell::new(|| {
+            let run = runs.get();
+            runs.set(run + 1);
+            if failing >> (run % 8) & 1 == 1 {
+                panic!("initializer failed");
+            }
+            live.set(live.get() + 1);
+            Value {
+                n: run as u8,
+                live: &live,
+            }
+        });
+        let mut model: Option<u8> = None;
+        for pair in ops.chunks(2) {
+            let arg = pair.get(1).copied().unwrap_or(0);
+            let before = runs.get();
+            match pair[0] % 3 {
+                0 => match quiet::catch(|| cell.read(|v| v.n)) {
+                    Ok(n) => {
+                        let expected = *model.get_or_insert(before as u8);
+                        assert_eq!(n, expected);
+                    }
+                    Err(_) => {
+                        assert!(model.is_none());
+                        assert_eq!(runs.get(), before + 1);
+                    }
+                },
+                1 => {
+                    if quiet::catch(|| cell.mutate(|v| v.n = arg)).is_ok() {
+                        model = Some(arg);
+                    } else {
+                        assert!(model.is_none());
+                    }
+                }
+                _ => {
+                    cell.reset();
+                    model = None;
+                }
+            }
+            if model.is_some() {
+                assert!(runs.get() - before <= 1);
+            } else {
+                assert!(!cell.is_initialized());
+            }
+            assert_eq!(live.get(), i32::from(model.is_some()));
+        }
+    }
+    assert_eq!(live.get(), 0);
+});
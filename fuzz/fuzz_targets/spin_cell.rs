@@ -0,0 +1,80 @@
+// Drives a SpinCell through the operations encoded in the input and checks
+// each result against a model of the cell's state. The first byte decides
+// whether the initializer panics, which poisons the cell.
+#![no_main]
+
+mod quiet;
+
+use std::cell::Cell;
+
+use libfuzzer_sys::fuzz_target;
+use spincell::SpinCell;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Model {
+    Uninit,
+    Ready(u8),
+    Poisoned,
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&first, ops)) = data.split_first() else {
+        return;
+    };
+    let panics = first & 1 == 1;
+    let runs = Cell::new(0);
+    let cell = SpinCell::new(|| {
+        runs.set(runs.get() + 1);
+        if panics {
+            panic!("initializer failed");
+        }
+        first
+    });
+    let mut model = Model::Uninit;
+    for pair in ops.chunks(2) {
+        let arg = pair.get(1).copied().unwrap_or(0);
+        match pair[0] % 4 {
+            0 => {
+                let result = quiet::catch(|| *cell);
+                model = match (model, result) {
+                    (Model::Uninit, Ok(value)) if !panics => {
+                        assert_eq!(value, first);
+                        Model::Ready(value)
+                    }
+                    (Model::Ready(expected), Ok(value)) => {
+                        assert_eq!(value, expected);
+                        model
+                    }
+                    (Model::Uninit | Model::Poisoned, Err(_)) if panics => Model::Poisoned,
+                    (state, result) => panic!("{state:?} gave {result:?}"),
+                };
+            }
+            1 => {
+                let result = quiet::catch(|| SpinCell::initialize_with(&cell, arg));
+                model = match (model, result) {
+                    (Model::Uninit, Ok(Ok(()))) => Model::Ready(arg),
+                    (Model::Ready(_), Ok(Err(rejected))) => {
+                        assert_eq!(rejected, arg);
+                        model
+                    }
+                    (Model::Poisoned, Err(_)) => model,
+                    (state, result) => panic!("{state:?} gave {result:?}"),
+                };
+            }
+            2 => {
+                let expected = match model {
+                    Model::Ready(value) => Some(value),
+                    _ => None,
+                };
+                assert_eq!(SpinCell::get(&cell).copied(), expected);
+            }
+            _ => {
+                assert_eq!(
+                    SpinCell::is_initialized(&cell),
+                    matches!(model, Model::Ready(_))
+                );
+            }
+        }
+        assert!(runs.get() <= 1);
+    }
+});
@@ -0,0 +1,214 @@
+//! Attribute macros for spincell. Use them through the `macros` feature of
+//! the spincell crate rather than depending on this crate directly.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Turns a constructor function into a lazily initialized static with an
+/// accessor.
+///
+/// Applied to `fn make_config() -> Config`, keeps the function and adds
+/// `fn config() -> &'static Config`, which initializes a hidden
+/// `SpinCell<Config>` static by calling `make_config` on first use and
+/// returns a reference to its value. The accessor has the visibility of the
+/// constructor.
+///
+/// The accessor is named after the constructor with its `make_` prefix
+/// removed. For constructors without the prefix, or to pick another name,
+/// pass it to the attribute: `#[spincell::lazy(settings)]`.
+///
+/// The constructor must take no arguments and have no generic parameters.
+#[proc_macro_attribute]
+pub fn lazy(attr: TokenStream, item: TokenStream) -> TokenStream {
+    match expand(attr, item) {
+        Ok(tokens) => tokens,
+        Err((span, message)) => compile_error(span, message),
+    }
+}
+
+type Error = (Span, &'static str);
+
+// The parts of the constructor the expansion needs.
+struct Constructor {
+    vis: Vec<TokenTree>,
+    name: Ident,
+    ret: Vec<TokenTree>,
+}
+
+fn expand(attr: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
+    let constructor = parse_constructor(item.clone())?;
+    let accessor = accessor_name(attr, &constructor.name)?;
+
+    let mut out = item;
+    out.extend(constructor.vis.iter().cloned());
+    out.extend([ident("fn"), TokenTree::Ident(accessor)]);
+    out.extend([group(Delimiter::Parenthesis, TokenStream::new())]);
+    out.extend(punct("->"));
+    out.extend(static_ref());
+    out.extend(constructor.ret.iter().cloned());
+
+    // static CELL: ::spincell::SpinCell<Ret> = ::spincell::SpinCell::new(name);
+    // &CELL
+    let mut body = TokenStream::new();
+    body.extend([ident("static"), ident("CELL")]);
+    body.extend(punct(":"));
+    body.extend(spincell_path());
+    body.extend(punct("<"));
+    body.extend(constructor.ret.iter().cloned());
+    body.extend(punct(">"));
+    body.extend(punct("="));
+    body.extend(spincell_path());
+    body.extend(punct("::"));
+    body.extend([ident("new")]);
+    body.extend([group(
+        Delimiter::Parenthesis,
+        TokenStream::from(TokenTree::Ident(constructor.name.clone())),
+    )]);
+    body.extend(punct(";"));
+    body.extend(punct("&"));
+    body.extend([ident("CELL")]);
+    out.extend([group(Delimiter::Brace, body)]);
+    Ok(out)
+}
+
+fn parse_constructor(item: TokenStream) -> Result<Constructor, Error> {
+    let tokens: Vec<TokenTree> = item.into_iter().collect();
+    let mut i = 0;
+
+    // Outer attributes, including doc comments.
+    while matches!(&tokens[i..], [TokenTree::Punct(p), TokenTree::Group(_), ..] if p.as_char() == '#')
+    {
+        i += 2;
+    }
+
+    let mut vis = Vec::new();
+    if matches!(tokens.get(i), Some(TokenTree::Ident(id)) if id.to_string() == "pub") {
+        vis.push(tokens[i].clone());
+        i += 1;
+        if let Some(TokenTree::Group(g)) = tokens.get(i) {
+            if g.delimiter() == Delimiter::Parenthesis {
+                vis.push(tokens[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    match tokens.get(i) {
+        Some(TokenTree::Ident(id)) if id.to_string() == "fn" => i += 1,
+        Some(other) => return Err((other.span(), "#[spincell::lazy] expects a plain `fn` item")),
+        None => return Err((Span::call_site(), "#[spincell::lazy] expects a function")),
+    }
+
+    let name = match tokens.get(i) {
+        Some(TokenTree::Ident(id)) => id.clone(),
+        _ => return Err((Span::call_site(), "expected a function name")),
+    };
+    i += 1;
+
+    match tokens.get(i) {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+            if !g.stream().is_empty() {
+                return Err((g.span(), "the constructor of a lazy static takes no arguments"));
+            }
+            i += 1;
+        }
+        Some(other) => {
+            return Err((other.span(), "the constructor of a lazy static cannot be generic"));
+        }
+        None => return Err((name.span(), "expected a parameter list")),
+    }
+
+    match &tokens[i..] {
+        [TokenTree::Punct(dash), TokenTree::Punct(gt), ..]
+            if dash.as_char() == '-' && gt.as_char() == '>' =>
+        {
+            i += 2;
+        }
+        _ => return Err((name.span(), "the constructor of a lazy static must return a value")),
+    }
+
+    // Everything up to the body is the return type.
+    let body = tokens.len() - 1;
+    match tokens.get(body) {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace && body > i => {}
+        _ => return Err((name.span(), "expected a return type and a function body")),
+    }
+    let ret = tokens[i..body].to_vec();
+    if ret
+        .iter()
+        .any(|t| matches!(t, TokenTree::Ident(id) if id.to_string() == "where"))
+    {
+        return Err((name.span(), "the constructor of a lazy static cannot be generic"));
+    }
+
+    Ok(Constructor { vis, name, ret })
+}
+
+fn accessor_name(attr: TokenStream, constructor: &Ident) -> Result<Ident, Error> {
+    let mut attr = attr.into_iter();
+    match (attr.next(), attr.next()) {
+        (None, _) => {
+            let name = constructor.to_string();
+            match name.strip_prefix("make_") {
+                Some(rest) if !rest.is_empty() => Ok(Ident::new(rest, constructor.span())),
+                _ => Err((
+                    constructor.span(),
+                    "name the accessor with #[spincell::lazy(name)], or prefix the constructor with `make_`",
+                )),
+            }
+        }
+        (Some(TokenTree::Ident(name)), None) => Ok(name),
+        (Some(other), _) => Err((other.span(), "expected the name of the accessor")),
+    }
+}
+
+fn ident(name: &str) -> TokenTree {
+    TokenTree::Ident(Ident::new(name, Span::call_site()))
+}
+
+fn group(delimiter: Delimiter, stream: TokenStream) -> TokenTree {
+    TokenTree::Group(Group::new(delimiter, stream))
+}
+
+// The punctuation `op`, joined into one operator if it has several
+// characters.
+fn punct(op: &str) -> Vec<TokenTree> {
+    let last = op.len() - 1;
+    op.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let spacing = if i < last { Spacing::Joint } else { Spacing::Alone };
+            TokenTree::Punct(Punct::new(c, spacing))
+        })
+        .collect()
+}
+
+// & 'static
+fn static_ref() -> Vec<TokenTree> {
+    let mut tokens = punct("&");
+    tokens.push(TokenTree::Punct(Punct::new('\'', Spacing::Joint)));
+    tokens.push(ident("static"));
+    tokens
+}
+
+// ::spincell::SpinCell
+fn spincell_path() -> Vec<TokenTree> {
+    let mut tokens = punct("::");
+    tokens.push(ident("spincell"));
+    tokens.extend(punct("::"));
+    tokens.push(ident("SpinCell"));
+    tokens
+}
+
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+    let mut tokens = TokenStream::new();
+    tokens.extend(punct("::"));
+    tokens.extend([ident("core")]);
+    tokens.extend(punct("::"));
+    tokens.extend([ident("compile_error")]);
+    tokens.extend(punct("!"));
+    tokens.extend([group(Delimiter::Parenthesis, TokenStream::from(TokenTree::Literal(message)))]);
+    tokens.extend(punct(";"));
+    tokens
+}
@@ -0,0 +1,35 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use spincell_macros::lazy;
+
+static CALLS: AtomicU32 = AtomicU32::new(0);
+
+struct Config {
+    retries: u32,
+}
+
+/// Reads the configuration.
+#[lazy]
+fn make_config() -> Config {
+    CALLS.fetch_add(1, Ordering::Relaxed);
+    Config { retries: 3 }
+}
+
+#[lazy(limits)]
+pub(crate) fn build_limits() -> [u32; 2] {
+    [config().retries, 10]
+}
+
+#[test]
+fn test_accessor_initializes_once() {
+    assert_eq!(config().retries, 3);
+    assert!(std::ptr::eq(config(), config()));
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_named_accessor() {
+    assert_eq!(*limits(), [3, 10]);
+    // The constructor itself is left in place.
+    assert_eq!(build_limits(), [3, 10]);
+}
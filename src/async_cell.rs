@@ -0,0 +1,180 @@
+use core::future::Future;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use crate::state::LOCKED;
+use crate::wait::{Initialized, Waiter};
+use crate::{Slot, SpinCell};
+
+/// A cell whose initializer is a future, run by the first task that awaits
+/// the cell.
+///
+/// The future lives inside the cell and is polled in place, so the cell has
+/// to be pinned before it can be awaited. A `static` is pinned already and
+/// needs no allocation: use [`Pin::static_ref`]. A local can be pinned with
+/// [`core::pin::pin!`].
+///
+/// The task that locks the cell first drives the future with its own waker.
+/// Every other task awaiting the cell meanwhile sleeps until the value is
+/// published and then shares it. If the driving task stops awaiting, the
+/// partially run future stays in the cell and the next waiting task picks it
+/// up.
+///
+/// ```
+/// use core::pin::pin;
+/// use spincell::AsyncSpinCell;
+///
+/// async fn connect() -> u32 {
+///     42
+/// }
+///
+/// async fn use_cell() {
+///     let session = pin!(AsyncSpinCell::new(connect()));
+///     assert_eq!(*session.as_ref().get().await, 42);
+/// }
+/// # let _ = use_cell();
+/// ```
+///
+/// If the future panics, the cell is poisoned, and awaiting it panics too.
+pub struct AsyncSpinCell<T, F> {
+    // Holds the future while UNINIT or LOCKED and the value once READY. The
+    // lock is held by the driving `AsyncGet` across polls of the future.
+    pub(crate) inner: SpinCell<T, F>,
+}
+
+// Safety: the future is only ever polled and dropped by the task holding the
+// lock, which may be on any thread, so it has to be Send but not Sync.
+unsafe impl<T: Send + Sync, F: Send> Sync for AsyncSpinCell<T, F> {}
+
+impl<T, F: Future<Output = T>> AsyncSpinCell<T, F> {
+    #[inline(always)]
+    pub const fn new(future: F) -> AsyncSpinCell<T, F> {
+        AsyncSpinCell {
+            inner: SpinCell::with_init(future),
+        }
+    }
+
+    /// Returns a future that resolves to the value, driving the stored
+    /// future if no other task is driving it.
+    pub fn get(self: Pin<&Self>) -> AsyncGet<'_, T, F> {
+        AsyncGet {
+            cell: self.get_ref(),
+            waiter: Waiter::new(),
+            driving: false,
+        }
+    }
+}
+
+impl<T, F> AsyncSpinCell<T, F> {
+    /// Returns the value if the cell is initialized, without waiting.
+    #[inline]
+    pub fn try_get(&self) -> Option<&T> {
+        SpinCell::get(&self.inner)
+    }
+
+    /// Returns whether the value has been initialized.
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        SpinCell::is_initialized(&self.inner)
+    }
+
+    /// Returns a future that resolves to the value once another task has
+    /// driven the initializer to completion, without driving it itself.
+    pub fn initialized(&self) -> Initialized<'_, T, F> {
+        SpinCell::initialized(&self.inner)
+    }
+}
+
+/// Future returned by [`AsyncSpinCell::get`].
+#[must_use = "futures do nothing unless polled"]
+pub struct AsyncGet<'a, T, F> {
+    cell: &'a AsyncSpinCell<T, F>,
+    waiter: Waiter,
+    // Whether this future holds the lock and is the one polling the stored
+    // future.
+    driving: bool,
+}
+
+impl<'a, T, F: Future<Output = T>> Future for AsyncGet<'a, T, F> {
+    type Output = &'a T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let inner = &this.cell.inner;
+        while !this.driving {
+            match inner.state.try_lock() {
+                Some(true) => {
+                    this.waiter.release();
+                    this.driving = true;
+                }
+                Some(false) => {
+                    this.waiter.release();
+                    // Safety: `try_lock` observed READY with Acquire.
+                    return Poll::Ready(unsafe { inner.get_unchecked() });
+                }
+                None => {
+                    if !this.waiter.register(&inner.state, cx.waker()) {
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    if inner.state.load() == LOCKED {
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
+
+        // Cleared while polling so that a panic poisons the cell instead of
+        // unlocking it when this future is dropped during unwinding.
+        this.driving = false;
+        let slot = inner.slot.get();
+        let guard = PoisonOnUnwind(inner);
+        // Safety: the lock is held, so the slot holds the future and nobody
+        // else touches it. The cell is pinned, and the future is dropped in
+        // place before its storage is reused.
+        let value = match unsafe { Pin::new_unchecked(&mut *(*slot).init_func) }.poll(cx) {
+            Poll::Ready(value) => value,
+            Poll::Pending => {
+                core::mem::forget(guard);
+                this.driving = true;
+                return Poll::Pending;
+            }
+        };
+        core::mem::forget(guard);
+        #[cfg(feature = "testing")]
+        crate::testing::record(&inner.state, crate::testing::Event::Init);
+        unsafe {
+            ManuallyDrop::drop(&mut (*slot).init_func);
+            core::ptr::write(
+                slot,
+                Slot {
+                    value: ManuallyDrop::new(value),
+                },
+            );
+        }
+        inner.state.publish();
+        // Safety: the value has just been published.
+        Poll::Ready(unsafe { inner.get_unchecked() })
+    }
+}
+
+impl<T, F> Drop for AsyncGet<'_, T, F> {
+    fn drop(&mut self) {
+        // Hand the future over to the next waiting task.
+        if self.driving {
+            self.cell.inner.state.unlock();
+        }
+    }
+}
+
+// Drops the stored future and poisons the cell if polling it panics.
+struct PoisonOnUnwind<'a, T, F>(&'a SpinCell<T, F>);
+
+impl<T, F> Drop for PoisonOnUnwind<'_, T, F> {
+    fn drop(&mut self) {
+        // Safety: the lock is still held and the slot holds the future.
+        unsafe { ManuallyDrop::drop(&mut (*self.0.slot.get()).init_func) };
+        self.0.state.poison();
+    }
+}
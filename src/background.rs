@@ -0,0 +1,51 @@
+use crate::{Initializer, SpinCell};
+
+impl<T, G> SpinCell<T, G>
+where
+    T: Send + Sync + 'static,
+    G: Initializer<T> + Send + 'static,
+{
+    /// Returns a task that initializes the cell, for running it on an
+    /// executor or thread of the caller's choosing.
+    ///
+    /// Accesses made while the task runs wait for it as usual, while
+    /// [`SpinCell::get`] keeps returning `None` until it is done.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// static MODEL: SpinCell<[u8; 64]> = SpinCell::new(|| [1; 64]);
+    ///
+    /// let task = SpinCell::init_task(&MODEL);
+    /// std::thread::spawn(task).join().unwrap();
+    /// assert_eq!(SpinCell::get(&MODEL).map(|model| model[0]), Some(1));
+    /// ```
+    pub fn init_task(me: &'static SpinCell<T, G>) -> impl FnOnce() + Send + 'static {
+        move || {
+            let _ = SpinCell::try_initialize(me);
+        }
+    }
+
+    /// Starts initializing the cell on a new thread right away.
+    ///
+    /// Useful for values that are expensive to build, so the work overlaps
+    /// with whatever the program does before it first needs the value. The
+    /// returned handle can be joined to wait for the initializer, and to
+    /// observe its panic if it panicked.
+    ///
+    /// Requires the `std` feature.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// static PATTERNS: SpinCell<Vec<String>> = SpinCell::new(|| vec!["a+".into(), "b*".into()]);
+    ///
+    /// let _ = SpinCell::background_init(&PATTERNS);
+    /// // Blocks until the background thread is done, if it is not yet.
+    /// assert_eq!(PATTERNS.len(), 2);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn background_init(me: &'static SpinCell<T, G>) -> std::thread::JoinHandle<()> {
+        std::thread::spawn(SpinCell::init_task(me))
+    }
+}
@@ -0,0 +1,34 @@
+/// Exponential backoff, the [`WaitStrategy`](crate::WaitStrategy) every cell
+/// uses unless configured otherwise.
+///
+/// Each round issues twice as many spin-loop hints as the previous one, up
+/// to 64, so short initializers are noticed quickly while long ones do not
+/// hammer the cache line holding the state.
+#[derive(Clone, Copy, Default)]
+pub struct Backoff {
+    step: u32,
+}
+
+// Miri interprets every hint, and its scheduler switches threads on each
+// one anyway, so there a single hint per round is enough.
+const SPIN_LIMIT: u32 = if cfg!(miri) { 0 } else { 6 };
+
+impl Backoff {
+    #[inline(always)]
+    pub const fn new() -> Backoff {
+        Backoff { step: 0 }
+    }
+
+    #[inline]
+    pub(crate) fn spin(&mut self) {
+        for _ in 0..1u32 << self.step {
+            #[cfg(all(feature = "nightly", target_arch = "xtensa"))]
+            crate::xtensa::memw();
+            #[cfg(not(all(feature = "nightly", target_arch = "xtensa")))]
+            core::hint::spin_loop();
+        }
+        if self.step < SPIN_LIMIT {
+            self.step += 1;
+        }
+    }
+}
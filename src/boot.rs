@@ -0,0 +1,260 @@
+//! Dependency-ordered initialization at boot.
+//!
+//! Each cell that takes part is described by a [`BootEntry`] listing the
+//! entries it depends on. Entries are added to a global registry with
+//! [`register`], and [`init_all`] then initializes every registered cell
+//! after its dependencies, instead of relying on lazies happening to fire in
+//! the right order.
+//!
+//! ```
+//! use spincell::boot::{self, BootEntry};
+//! use spincell::SpinCell;
+//!
+//! static HEAP: SpinCell<usize> = SpinCell::new(|| 0x8000_0000);
+//! static ALLOCATOR: SpinCell<usize> = SpinCell::new(|| *HEAP + 64);
+//!
+//! static HEAP_ENTRY: BootEntry = BootEntry::new(&HEAP, &[]);
+//! static ALLOCATOR_ENTRY: BootEntry = BootEntry::new(&ALLOCATOR, &[&HEAP_ENTRY]);
+//!
+//! boot::register(&ALLOCATOR_ENTRY);
+//! boot::register(&HEAP_ENTRY);
+//! boot::init_all();
+//! assert!(SpinCell::is_initialized(&ALLOCATOR));
+//! ```
+//!
+//! On ELF targets, cells declared with [`register!`](crate::register) are
+//! also collected at link time, and [`init_registered`] initializes all of
+//! them at a point of the program's choosing.
+
+use core::mem;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, Ordering};
+
+use crate::{LazyInit, SpinMutCell};
+
+/// A cell and the entries that must be initialized before it.
+pub struct BootEntry {
+    cell: &'static (dyn LazyInit + Sync),
+    deps: &'static [&'static BootEntry],
+    // Next entry in the registry, once registered.
+    next: AtomicPtr<BootEntry>,
+    registered: AtomicBool,
+    // Progress of the depth-first walk, one of the marks below. Only
+    // touched with BOOT held.
+    mark: AtomicU8,
+}
+
+const UNVISITED: u8 = 0;
+const VISITING: u8 = 1;
+const DONE: u8 = 2;
+
+// Head of the intrusive list of registered entries.
+static REGISTRY: AtomicPtr<BootEntry> = AtomicPtr::new(ptr::null_mut());
+
+// Serializes the walks, so the marks are only used by one at a time.
+static BOOT: SpinMutCell<()> = SpinMutCell::new(|| ());
+
+impl BootEntry {
+    #[inline(always)]
+    pub const fn new(
+        cell: &'static (dyn LazyInit + Sync),
+        deps: &'static [&'static BootEntry],
+    ) -> BootEntry {
+        BootEntry {
+            cell,
+            deps,
+            next: AtomicPtr::new(ptr::null_mut()),
+            registered: AtomicBool::new(false),
+            mark: AtomicU8::new(UNVISITED),
+        }
+    }
+
+    /// Initializes this entry's cell, after its dependencies, without going
+    /// through the registry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the dependencies form a cycle, or if an initializer panics.
+    pub fn init(&self) {
+        BOOT.mutate(|_| self.visit());
+    }
+
+    fn visit(&self) {
+        match self.mark.load(Ordering::Relaxed) {
+            DONE => return,
+            VISITING => cycle(),
+            _ => {}
+        }
+        let unmark = Unmark(&self.mark);
+        self.mark.store(VISITING, Ordering::Relaxed);
+        for dep in self.deps {
+            dep.visit();
+        }
+        self.cell.init();
+        mem::forget(unmark);
+        self.mark.store(DONE, Ordering::Relaxed);
+    }
+}
+
+// Puts an entry back to UNVISITED if a dependency or its initializer
+// unwinds, so a later walk retries it instead of reporting a cycle.
+struct Unmark<'a>(&'a AtomicU8);
+
+impl Drop for Unmark<'_> {
+    fn drop(&mut self) {
+        self.0.store(UNVISITED, Ordering::Relaxed);
+    }
+}
+
+/// Adds `entry` to the registry walked by [`init_all`]. Registering an
+/// entry more than once has no effect.
+///
+/// Dependencies do not need to be registered themselves; they are
+/// initialized whenever an entry depending on them is.
+pub fn register(entry: &'static BootEntry) {
+    if entry.registered.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let entry_ptr = entry as *const BootEntry as *mut BootEntry;
+    let mut head = REGISTRY.load(Ordering::Relaxed);
+    loop {
+        entry.next.store(head, Ordering::Relaxed);
+        match REGISTRY.compare_exchange_weak(head, entry_ptr, Ordering::Release, Ordering::Relaxed)
+        {
+            Ok(_) => return,
+            Err(actual) => head = actual,
+        }
+    }
+}
+
+/// Initializes every registered cell, each one after the entries it depends
+/// on. Cells that are already initialized are skipped.
+///
+/// Must not be called from an initializer run by it, which deadlocks.
+///
+/// # Panics
+///
+/// Panics if the dependencies form a cycle, or if an initializer panics.
+pub fn init_all() {
+    BOOT.mutate(|_| {
+        let mut entry = REGISTRY.load(Ordering::Acquire);
+        // Safety: only `&'static BootEntry`s are ever linked in.
+        while let Some(current) = unsafe { entry.as_ref() } {
+            current.visit();
+            entry = current.next.load(Ordering::Relaxed);
+        }
+    });
+}
+
+// Cells declared with `register!` are placed in this section by reference.
+// The linker defines the bounds of sections whose names are valid C
+// identifiers.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "none"
+))]
+mod section {
+    use crate::LazyInit;
+
+    pub(super) type Entry = &'static (dyn LazyInit + Sync);
+
+    extern "C" {
+        static __start_spincell_registry: u8;
+        static __stop_spincell_registry: u8;
+    }
+
+    // Keeps the section from being empty, which would leave the bounds
+    // undefined.
+    struct Nothing;
+
+    impl LazyInit for Nothing {
+        fn init(&self) {}
+    }
+
+    #[used]
+    #[link_section = "spincell_registry"]
+    static NOTHING: Entry = &Nothing;
+
+    pub(super) fn entries() -> &'static [Entry] {
+        unsafe {
+            let start = core::ptr::addr_of!(__start_spincell_registry);
+            let stop = core::ptr::addr_of!(__stop_spincell_registry);
+            let len = (stop as usize - start as usize) / core::mem::size_of::<Entry>();
+            core::slice::from_raw_parts(start.cast::<Entry>(), len)
+        }
+    }
+}
+
+/// Initializes every cell declared with [`register!`](crate::register), in
+/// no particular order.
+///
+/// Calling this early, for example right after startup, makes initializer
+/// failures show up at a known point instead of on some later first access.
+///
+/// # Panics
+///
+/// Panics if an initializer panics.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "none"
+))]
+pub fn init_registered() {
+    for cell in section::entries() {
+        cell.init();
+    }
+}
+
+#[doc(hidden)]
+pub use crate::LazyInit as __LazyInit;
+
+#[cold]
+#[inline(never)]
+fn cycle() -> ! {
+    panic!("boot entries depend on each other in a cycle")
+}
+
+/// Declares statics and registers them for [`boot::init_registered`].
+///
+/// Each static is declared as written, and a reference to it is placed in a
+/// dedicated linker section, so all registered cells in the final binary can
+/// be found without listing them anywhere. Only available on ELF targets.
+///
+/// ```
+/// use spincell::SpinCell;
+///
+/// spincell::register! {
+///     static DEVICE_TREE: SpinCell<u32> = SpinCell::new(|| 0xd00d_feed);
+///     pub static CPU_COUNT: SpinCell<usize> = SpinCell::new(|| 4);
+/// }
+///
+/// spincell::boot::init_registered();
+/// assert!(SpinCell::is_initialized(&DEVICE_TREE));
+/// assert!(SpinCell::is_initialized(&CPU_COUNT));
+/// ```
+///
+/// [`boot::init_registered`]: crate::boot::init_registered
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "none"
+))]
+#[macro_export]
+macro_rules! register {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $ty = $init;
+
+            const _: () = {
+                #[used]
+                #[link_section = "spincell_registry"]
+                static ENTRY: &'static (dyn $crate::boot::__LazyInit + Sync) = &$name;
+            };
+        )*
+    };
+}
@@ -0,0 +1,136 @@
+use core::ops::Deref;
+
+use crate::{Initializer, SpinCell};
+
+/// A [`SpinCell`] whose accesses wait a bounded number of rounds while
+/// another thread runs the initializer.
+///
+/// A plain `SpinCell` spins for as long as the initialization it waits for
+/// takes, which leaves no worst-case execution time to state for any code
+/// that might run into it. An access to a `BoundedSpinCell` instead waits
+/// at most `MAX_SPINS` rounds, each one spin-loop hint, while it finds the
+/// cell locked by another thread, and then gives up:
+/// [`try_force`](Self::try_force) returns `None`, and `Deref` calls the
+/// overrun hook, which panics unless another one was given to
+/// [`with_overrun`](Self::with_overrun). Compare-and-swap attempts that
+/// fail spuriously on an unlocked cell are retried without counting as a
+/// round.
+///
+/// Only the wait for the lock in `try_force` and `Deref` is bounded. The
+/// thread that wins the lock runs the initializer to completion, and the
+/// other operations that wait for a lock still spin without limit: `Clone`
+/// for `SpinCell`, [`SpinCell::seal`], [`SpinCell::wait`] and the resets of
+/// [`SpinResetCell`](crate::SpinResetCell). A `BoundedSpinCell` offers none
+/// of them, but code that also uses them needs its own bound on how long
+/// they take.
+///
+/// ```
+/// use spincell::BoundedSpinCell;
+///
+/// fn read_calibration() -> [u16; 4] {
+///     [512, 498, 530, 505]
+/// }
+///
+/// static CALIBRATION: BoundedSpinCell<[u16; 4], fn() -> [u16; 4], 10_000> =
+///     BoundedSpinCell::new(read_calibration as fn() -> _);
+///
+/// match BoundedSpinCell::try_force(&CALIBRATION) {
+///     Some(table) => assert_eq!(table[1], 498),
+///     None => { /* another thread held the lock for 10 000 rounds */ }
+/// }
+/// assert_eq!(CALIBRATION[0], 512);
+/// ```
+pub struct BoundedSpinCell<T, G, const MAX_SPINS: usize> {
+    cell: SpinCell<T, G>,
+    overrun: fn() -> !,
+}
+
+impl<T, G: Initializer<T>, const MAX_SPINS: usize> BoundedSpinCell<T, G, MAX_SPINS> {
+    /// Creates a cell that runs `init_func` on first access, and whose
+    /// `Deref` panics if it gives up waiting.
+    #[inline(always)]
+    pub const fn new(init_func: G) -> BoundedSpinCell<T, G, MAX_SPINS> {
+        BoundedSpinCell::with_overrun(init_func, overran::<MAX_SPINS>)
+    }
+
+    /// Creates a cell that runs `init_func` on first access, and whose
+    /// `Deref` calls `overrun` if it gives up waiting, for example to log
+    /// the fault and reset the system.
+    #[inline(always)]
+    pub const fn with_overrun(
+        init_func: G,
+        overrun: fn() -> !,
+    ) -> BoundedSpinCell<T, G, MAX_SPINS> {
+        BoundedSpinCell {
+            cell: SpinCell::with_init(init_func),
+            overrun,
+        }
+    }
+
+    /// Initializes the cell if needed and returns the value, or returns
+    /// `None` if another thread still held the lock after `MAX_SPINS`
+    /// rounds of waiting.
+    ///
+    /// Panics if an earlier initialization attempt panicked.
+    #[inline]
+    pub fn try_force(me: &BoundedSpinCell<T, G, MAX_SPINS>) -> Option<&T> {
+        if !me.cell.state.is_ready() {
+            me.initialize_slow()?;
+        }
+        // Safety: the cell is READY.
+        Some(unsafe { me.cell.get_unchecked() })
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn initialize_slow(&self) -> Option<()> {
+        if self.cell.state.lock_bounded(MAX_SPINS)? {
+            let cell = &self.cell;
+            let value = cell.value_ptr();
+            let mut observer = None;
+            cell.initialize_locked_by(|init_func| {
+                let (value, f) = init_func.init_observed(value);
+                observer = f;
+                value
+            });
+            if let Some(observer) = observer {
+                // Safety: the value has been published.
+                observer(unsafe { value.as_ref() });
+            }
+        }
+        Some(())
+    }
+}
+
+impl<T, G, const MAX_SPINS: usize> BoundedSpinCell<T, G, MAX_SPINS> {
+    /// Returns whether the value has been initialized, without initializing
+    /// it.
+    #[inline]
+    pub fn is_initialized(me: &BoundedSpinCell<T, G, MAX_SPINS>) -> bool {
+        SpinCell::is_initialized(&me.cell)
+    }
+
+    /// Returns the value if the cell is initialized, without initializing
+    /// it or waiting for an initialization in progress.
+    #[inline]
+    pub fn get(me: &BoundedSpinCell<T, G, MAX_SPINS>) -> Option<&T> {
+        SpinCell::get(&me.cell)
+    }
+}
+
+impl<T, G: Initializer<T>, const MAX_SPINS: usize> Deref for BoundedSpinCell<T, G, MAX_SPINS> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        match BoundedSpinCell::try_force(self) {
+            Some(value) => value,
+            None => (self.overrun)(),
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn overran<const MAX_SPINS: usize>() -> ! {
+    panic!("BoundedSpinCell still locked after {MAX_SPINS} spins")
+}
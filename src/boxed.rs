@@ -0,0 +1,173 @@
+use alloc::boxed::Box;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+use crate::{Initializer, SpinCell};
+
+/// A [`SpinCell`] that keeps its value on the heap.
+///
+/// Only a pointer (plus the initializer and the state byte) lives in the
+/// cell itself; the value is allocated when the cell is first initialized.
+/// This keeps large lazily-built tables out of `.bss` on targets where
+/// static memory is scarce.
+///
+/// ```
+/// use spincell::BoxedSpinCell;
+///
+/// static TABLE: BoxedSpinCell<[u32; 1 << 16]> = BoxedSpinCell::new(|| [3; 1 << 16]);
+/// assert_eq!(core::mem::size_of_val(&TABLE), 2 * core::mem::size_of::<usize>());
+/// assert_eq!(TABLE[1234], 3);
+/// ```
+pub struct BoxedSpinCell<T, G = fn() -> T> {
+    inner: SpinCell<Box<T>, G>,
+}
+
+impl<T, G: Initializer<T>> BoxedSpinCell<T, G> {
+    #[inline(always)]
+    pub const fn new(init_func: G) -> BoxedSpinCell<T, G> {
+        Self {
+            inner: SpinCell::with_init(init_func),
+        }
+    }
+
+    /// Allocates and initializes the value unless the cell is already
+    /// initialized. Returns `Err` if it was.
+    #[allow(clippy::result_unit_err)]
+    pub fn try_initialize(me: &BoxedSpinCell<T, G>) -> Result<(), ()> {
+        if me.inner.state.is_ready() {
+            return Err(());
+        }
+        me.initialize_slow();
+        Ok(())
+    }
+
+    /// Initializes the cell if needed and leaks the value, returning a
+    /// reference that lives for the rest of the program.
+    ///
+    /// The heap block holding the value is kept as is; only the cell around
+    /// it is discarded.
+    ///
+    /// ```
+    /// use spincell::BoxedSpinCell;
+    ///
+    /// let config = BoxedSpinCell::new(|| [1u8; 64]);
+    /// let config: &'static [u8; 64] = BoxedSpinCell::leak(config);
+    /// assert_eq!(config[63], 1);
+    /// ```
+    pub fn leak(me: BoxedSpinCell<T, G>) -> &'static T
+    where
+        T: 'static,
+    {
+        let _ = BoxedSpinCell::try_initialize(&me);
+        let mut me = ManuallyDrop::new(me);
+        // Safety: the cell is READY, so the slot holds the box and the
+        // initializer is gone. The cell is never dropped, so the box is
+        // moved out exactly once.
+        let value = unsafe { ManuallyDrop::take(&mut me.inner.slot.get_mut().value) };
+        Box::leak(value)
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn initialize_slow(&self) {
+        self.inner.initialize_by(|init_func| {
+            // Allocate first so the value is written straight into the heap
+            // block instead of being built in a temporary box argument.
+            let mut slot = Box::new_uninit();
+            let value = NonNull::from(&mut *slot).cast::<T>();
+            Box::write(slot, init_func.init_at(value))
+        });
+    }
+}
+
+impl<T, G: Initializer<T>> Deref for BoxedSpinCell<T, G> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        if !self.inner.state.is_ready() {
+            self.initialize_slow();
+        }
+        unsafe { self.inner.get_unchecked() }
+    }
+}
+
+/// A [`SpinCell`] holding a boxed, possibly unsized value such as a trait
+/// object.
+///
+/// The initializer returns the `Box<T>` and the cell dereferences straight
+/// to `T`, so no wrapper is needed to get from the cell to the trait object.
+///
+/// ```
+/// use spincell::SpinDynCell;
+///
+/// trait Greeter: Send + Sync {
+///     fn greet(&self) -> &'static str;
+/// }
+///
+/// struct English;
+///
+/// impl Greeter for English {
+///     fn greet(&self) -> &'static str {
+///         "hello"
+///     }
+/// }
+///
+/// static GREETER: SpinDynCell<dyn Greeter> = SpinDynCell::new(|| Box::new(English));
+/// assert_eq!(GREETER.greet(), "hello");
+/// ```
+pub struct SpinDynCell<T: ?Sized, G = fn() -> Box<T>> {
+    inner: SpinCell<Box<T>, G>,
+}
+
+impl<T: ?Sized, G: Initializer<Box<T>>> SpinDynCell<T, G> {
+    #[inline(always)]
+    pub const fn new(init_func: G) -> SpinDynCell<T, G> {
+        Self {
+            inner: SpinCell::new(init_func),
+        }
+    }
+
+    /// Runs the initializer unless the cell is already initialized. Returns
+    /// `Err` if it was.
+    #[allow(clippy::result_unit_err)]
+    pub fn try_initialize(me: &SpinDynCell<T, G>) -> Result<(), ()> {
+        SpinCell::try_initialize(&me.inner)
+    }
+}
+
+impl<T: ?Sized, G: Initializer<Box<T>>> Deref for SpinDynCell<T, G> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T, G: Initializer<T>> SpinCell<T, G> {
+    /// Initializes a heap-allocated cell if needed and leaks it, returning a
+    /// reference to the value that lives for the rest of the program.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// let prefix = String::from("app");
+    /// let cell = Box::new(SpinCell::new(move || prefix + ".conf"));
+    /// let name: &'static String = SpinCell::leak(cell);
+    /// assert_eq!(name, "app.conf");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initializer panicked during an earlier attempt, in
+    /// which case the cell is dropped instead of leaked.
+    pub fn leak(me: Box<SpinCell<T, G>>) -> &'static T
+    where
+        T: 'static,
+    {
+        let _ = SpinCell::try_initialize(&me);
+        // Safety: the allocation is never freed, and once READY the cell
+        // holds only the value, so no borrow of `G` outlives its lifetime.
+        unsafe { SpinCell::deref_ptr(&*Box::into_raw(me)).as_ref() }
+    }
+}
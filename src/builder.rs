@@ -0,0 +1,311 @@
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+use core::ptr::read;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::state;
+use crate::{Backoff, Initializer, Slot, SpinCell, WaitStrategy};
+
+/// Collects the options of a [`ConfiguredSpinCell`]; created by
+/// [`SpinCell::builder`].
+///
+/// Every method is a `const fn`, so the finished cell can be placed in a
+/// static:
+///
+/// ```
+/// use spincell::{Backoff, ConfiguredSpinCell, SpinCell};
+///
+/// fn load() -> [u8; 4] {
+///     [10, 0, 0, 1]
+/// }
+///
+/// static NET_CFG: ConfiguredSpinCell<[u8; 4]> = SpinCell::builder(load as fn() -> _)
+///     .poisoning()
+///     .strategy(Backoff::new())
+///     .stats(true)
+///     .name("NET_CFG")
+///     .build();
+///
+/// assert_eq!(NET_CFG[0], 10);
+/// assert_eq!(ConfiguredSpinCell::name(&NET_CFG), Some("NET_CFG"));
+/// ```
+pub struct SpinCellBuilder<T, G, S = Backoff> {
+    init_func: G,
+    strategy: S,
+    retry: Option<fn(&G) -> G>,
+    stats: bool,
+    name: Option<&'static str>,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T, G: Initializer<T>> SpinCell<T, G> {
+    /// Starts configuring a cell that runs `init_func` on first access.
+    ///
+    /// Without further options, [`SpinCellBuilder::build`] returns a cell
+    /// that behaves exactly like `SpinCell::new(init_func)`.
+    #[inline(always)]
+    pub const fn builder(init_func: G) -> SpinCellBuilder<T, G> {
+        SpinCellBuilder {
+            init_func,
+            strategy: Backoff::new(),
+            retry: None,
+            stats: false,
+            name: None,
+            value: PhantomData,
+        }
+    }
+}
+
+// A const fn cannot move fields out of the builder by destructuring it, as
+// that leaves a partially moved value of a type with generic fields to be
+// dropped. The methods that take it apart wrap it in ManuallyDrop and read
+// the fields out instead.
+impl<T, G> SpinCellBuilder<T, G> {
+    /// Sets how threads wait while another thread runs the initializer.
+    /// The default is [`Backoff`].
+    #[inline(always)]
+    pub const fn strategy<S: WaitStrategy>(self, strategy: S) -> SpinCellBuilder<T, G, S> {
+        let this = ManuallyDrop::new(self);
+        let this: *const SpinCellBuilder<T, G> = (&raw const this).cast();
+        // Safety: the initializer is moved out exactly once, and the
+        // default strategy left behind needs no drop.
+        unsafe {
+            SpinCellBuilder {
+                init_func: read(&raw const (*this).init_func),
+                strategy,
+                retry: (*this).retry,
+                stats: (*this).stats,
+                name: (*this).name,
+                value: PhantomData,
+            }
+        }
+    }
+}
+
+impl<T, G: Clone, S> SpinCellBuilder<T, G, S> {
+    /// Turns poisoning off: the cell keeps a clone of the initializer while
+    /// it runs, and if the initializer panics the clone is put back and the
+    /// cell is left uninitialized, so the next access tries again.
+    #[inline(always)]
+    pub const fn no_poisoning(mut self) -> SpinCellBuilder<T, G, S> {
+        self.retry = Some(G::clone);
+        self
+    }
+}
+
+impl<T, G, S> SpinCellBuilder<T, G, S> {
+    /// Makes a panicking initializer poison the cell, which is the default,
+    /// undoing an earlier [`no_poisoning`](Self::no_poisoning).
+    #[inline(always)]
+    pub const fn poisoning(mut self) -> SpinCellBuilder<T, G, S> {
+        self.retry = None;
+        self
+    }
+
+    /// Sets whether the cell counts how often threads had to wait for it,
+    /// as reported by [`ConfiguredSpinCell::stats`]. Off by default.
+    #[inline(always)]
+    pub const fn stats(mut self, stats: bool) -> SpinCellBuilder<T, G, S> {
+        self.stats = stats;
+        self
+    }
+
+    /// Names the cell, for panic messages and [`ConfiguredSpinCell::name`].
+    #[inline(always)]
+    pub const fn name(mut self, name: &'static str) -> SpinCellBuilder<T, G, S> {
+        self.name = Some(name);
+        self
+    }
+
+    /// Creates the configured cell.
+    #[inline(always)]
+    pub const fn build(self) -> ConfiguredSpinCell<T, G, S> {
+        let this = ManuallyDrop::new(self);
+        let this: *const SpinCellBuilder<T, G, S> = (&raw const this).cast();
+        // Safety: the initializer and the strategy are each moved out
+        // exactly once, and the builder is never dropped.
+        unsafe {
+            ConfiguredSpinCell {
+                cell: SpinCell::with_init(read(&raw const (*this).init_func)),
+                strategy: read(&raw const (*this).strategy),
+                retry: (*this).retry,
+                name: (*this).name,
+                stats: if (*this).stats {
+                    Some(Counters::new())
+                } else {
+                    None
+                },
+            }
+        }
+    }
+}
+
+/// A [`SpinCell`] with the options set through a [`SpinCellBuilder`].
+///
+/// Accesses go through `Deref` like those of a plain `SpinCell`, and once
+/// the cell is initialized they cost the same single load.
+pub struct ConfiguredSpinCell<T, G = fn() -> T, S = Backoff> {
+    cell: SpinCell<T, G>,
+    strategy: S,
+    // A way to copy the initializer before running it, present when
+    // poisoning is turned off.
+    retry: Option<fn(&G) -> G>,
+    name: Option<&'static str>,
+    stats: Option<Counters>,
+}
+
+/// A snapshot of the counters of a [`ConfiguredSpinCell`] with statistics
+/// turned on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Accesses that found the cell locked by another thread and waited.
+    pub contended: usize,
+    /// Calls to [`WaitStrategy::wait`] made by those accesses.
+    pub wait_rounds: usize,
+}
+
+struct Counters {
+    contended: AtomicUsize,
+    wait_rounds: AtomicUsize,
+}
+
+impl Counters {
+    const fn new() -> Counters {
+        Counters {
+            contended: AtomicUsize::new(0),
+            wait_rounds: AtomicUsize::new(0),
+        }
+    }
+}
+
+// Unlocks the cell if cloning the initializer or the initializer itself
+// unwinds, putting the copy back in the second case.
+struct Retry<'a, T, G> {
+    cell: &'a SpinCell<T, G>,
+    init_func: Option<G>,
+}
+
+impl<T, G> Drop for Retry<'_, T, G> {
+    fn drop(&mut self) {
+        if let Some(init_func) = self.init_func.take() {
+            // Safety: the lock is held and the original initializer has
+            // been moved out of the slot.
+            unsafe {
+                self.cell.slot.get().write(Slot {
+                    init_func: ManuallyDrop::new(init_func),
+                });
+            }
+        }
+        self.cell.state.unlock();
+    }
+}
+
+impl<T, G, S> ConfiguredSpinCell<T, G, S> {
+    /// Returns whether the value has been initialized, without initializing
+    /// it.
+    #[inline]
+    pub fn is_initialized(me: &ConfiguredSpinCell<T, G, S>) -> bool {
+        SpinCell::is_initialized(&me.cell)
+    }
+
+    /// Returns the value if the cell is initialized, without initializing
+    /// it or waiting for an initialization in progress.
+    #[inline]
+    pub fn get(me: &ConfiguredSpinCell<T, G, S>) -> Option<&T> {
+        SpinCell::get(&me.cell)
+    }
+
+    /// Returns the name given with [`SpinCellBuilder::name`].
+    #[inline]
+    pub fn name(me: &ConfiguredSpinCell<T, G, S>) -> Option<&'static str> {
+        me.name
+    }
+
+    /// Returns the counters collected so far, or `None` if statistics were
+    /// not turned on with [`SpinCellBuilder::stats`].
+    pub fn stats(me: &ConfiguredSpinCell<T, G, S>) -> Option<Stats> {
+        me.stats.as_ref().map(|counters| Stats {
+            contended: counters.contended.load(Ordering::Relaxed),
+            wait_rounds: counters.wait_rounds.load(Ordering::Relaxed),
+        })
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn poisoned(&self) -> ! {
+        match self.name {
+            Some(name) => panic!("SpinCell {name} has previously been poisoned"),
+            None => state::poisoned(),
+        }
+    }
+}
+
+impl<T, G: Initializer<T>, S: WaitStrategy> ConfiguredSpinCell<T, G, S> {
+    #[cold]
+    #[inline(never)]
+    fn initialize_slow(&self) {
+        let mut strategy = self.strategy.clone();
+        let mut rounds = 0;
+        let locked = self.cell.state.lock_with(&mut || {
+            rounds += 1;
+            strategy.wait();
+        });
+        if let (Some(counters), true) = (&self.stats, rounds > 0) {
+            counters.contended.fetch_add(1, Ordering::Relaxed);
+            counters.wait_rounds.fetch_add(rounds, Ordering::Relaxed);
+        }
+        match locked {
+            Some(true) => self.initialize_locked(),
+            Some(false) => {}
+            None => self.poisoned(),
+        }
+    }
+
+    // Runs the initializer with the lock held and publishes the value.
+    fn initialize_locked(&self) {
+        let cell = &self.cell;
+        let value = cell.value_ptr();
+        let mut observer = None;
+        let mut init = Some(|init_func: G| {
+            let (value, f) = init_func.init_observed(value);
+            observer = f;
+            value
+        });
+        match self.retry {
+            None => cell
+                .state
+                .initialize_locked(&mut || cell.build_value(&mut init)),
+            Some(retry) => {
+                let mut guard = Retry {
+                    cell,
+                    init_func: None,
+                };
+                // Safety: the lock is held and the slot holds the
+                // initializer.
+                guard.init_func = Some(retry(unsafe { &(*cell.slot.get()).init_func }));
+                cell.build_value(&mut init);
+                let copy = guard.init_func.take();
+                core::mem::forget(guard);
+                cell.state.publish();
+                drop(copy);
+            }
+        }
+        if let Some(observer) = observer {
+            // Safety: the value has been published.
+            observer(unsafe { value.as_ref() });
+        }
+    }
+}
+
+impl<T, G: Initializer<T>, S: WaitStrategy> Deref for ConfiguredSpinCell<T, G, S> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        if !self.cell.state.is_ready() {
+            self.initialize_slow();
+        }
+        // Safety: the cell is READY.
+        unsafe { self.cell.get_unchecked() }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{Initializer, SpinCell};
+
+/// Types for which every sequence of bytes of the right size is a valid
+/// value, so that correctly aligned bytes can be read as one.
+///
+/// Like the `FromBytes` of zerocopy or the `AnyBitPattern` of bytemuck, but
+/// without a dependency on either crate.
+///
+/// # Safety
+///
+/// Every bit pattern of `size_of::<Self>()` bytes must be a valid `Self`,
+/// and `Self` must not contain padding, pointers or interior mutability. A
+/// `#[repr(C)]` struct satisfies this if its fields are all `FromBytes` and
+/// there is no padding between or after them.
+pub unsafe trait FromBytes {}
+
+macro_rules! from_bytes {
+    ($($ty:ty)*) => {
+        $(unsafe impl FromBytes for $ty {})*
+    };
+}
+
+from_bytes!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64);
+
+unsafe impl<T: FromBytes, const N: usize> FromBytes for [T; N] {}
+
+/// Bytes that are checked and reinterpreted as a `T` on first access.
+///
+/// For data baked into flash or included with `include_bytes!`: the first
+/// call to [`view`](SpinBytesCell::view) checks that the bytes have the size
+/// and alignment of `T` and pass the optional validation function, and
+/// every call returns the outcome of that check, a reference into the
+/// original bytes or `None`. Nothing is copied.
+///
+/// ```
+/// use spincell::SpinBytesCell;
+///
+/// #[repr(C, align(4))]
+/// struct Asset([u8; 8]);
+///
+/// static ASSET: Asset = Asset([7; 8]);
+/// static WORDS: SpinBytesCell<[u32; 2]> = SpinBytesCell::new(&ASSET.0);
+///
+/// assert_eq!(WORDS.view(), Some(&[0x0707_0707; 2]));
+/// ```
+pub struct SpinBytesCell<T: 'static> {
+    cell: SpinCell<Option<&'static T>, Validate<T>>,
+}
+
+// Checks the bytes, as the initializer of the inner cell.
+struct Validate<T: 'static> {
+    bytes: &'static [u8],
+    check: fn(&T) -> bool,
+}
+
+impl<T: FromBytes + 'static> SpinBytesCell<T> {
+    /// Creates a cell reinterpreting `bytes` as a `T`.
+    #[inline(always)]
+    pub const fn new(bytes: &'static [u8]) -> SpinBytesCell<T> {
+        SpinBytesCell::with_check(bytes, |_| true)
+    }
+
+    /// Creates a cell reinterpreting `bytes` as a `T` if `check` accepts
+    /// the value, for example after comparing a magic number or checksum.
+    #[inline(always)]
+    pub const fn with_check(bytes: &'static [u8], check: fn(&T) -> bool) -> SpinBytesCell<T> {
+        SpinBytesCell {
+            cell: SpinCell::new(Validate { bytes, check }),
+        }
+    }
+
+    /// Returns the bytes as a `T`, or `None` if they have the wrong size or
+    /// alignment or were rejected by the validation function.
+    ///
+    /// The first call does the checks; later calls return the cached
+    /// result.
+    #[inline]
+    pub fn view(&self) -> Option<&'static T> {
+        *self.cell
+    }
+}
+
+impl<T: FromBytes + 'static> Initializer<Option<&'static T>> for Validate<T> {
+    fn init(self) -> Option<&'static T> {
+        let ptr = self.bytes.as_ptr().cast::<T>();
+        if self.bytes.len() != core::mem::size_of::<T>() || !ptr.is_aligned() {
+            return None;
+        }
+        // Safety: the bytes are as large and as aligned as a `T`, any bytes
+        // are a valid `T`, and they are borrowed for 'static.
+        let value = unsafe { &*ptr };
+        (self.check)(value).then_some(value)
+    }
+}
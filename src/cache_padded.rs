@@ -0,0 +1,98 @@
+use core::ops::{Deref, DerefMut};
+
+/// Pads and aligns a value to the length of a cache line.
+///
+/// Wrapping contended cells in `CachePadded` keeps neighbouring statics off
+/// the same cache line, so spinning on one cell does not slow down readers
+/// of another. The alignment follows the destructive interference size of
+/// the target architecture:
+///
+/// - 128 bytes on x86_64, aarch64 and powerpc64, where the prefetcher pulls
+///   in pairs of 64-byte lines.
+/// - 256 bytes on s390x.
+/// - 32 bytes on arm, mips, sparc and hexagon.
+/// - 16 bytes on m68k.
+/// - 64 bytes everywhere else.
+///
+/// ```
+/// use spincell::{CachePadded, SpinCell};
+///
+/// static A: CachePadded<SpinCell<u32>> = CachePadded::new(SpinCell::new(|| 1));
+/// static B: CachePadded<SpinCell<u32>> = CachePadded::new(SpinCell::new(|| 2));
+/// assert_eq!(**A + **B, 3);
+/// ```
+#[cfg_attr(
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "powerpc64",
+    ),
+    repr(align(128))
+)]
+#[cfg_attr(target_arch = "s390x", repr(align(256)))]
+#[cfg_attr(
+    any(
+        target_arch = "arm",
+        target_arch = "mips",
+        target_arch = "mips32r6",
+        target_arch = "mips64",
+        target_arch = "mips64r6",
+        target_arch = "sparc",
+        target_arch = "hexagon",
+    ),
+    repr(align(32))
+)]
+#[cfg_attr(target_arch = "m68k", repr(align(16)))]
+#[cfg_attr(
+    not(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "powerpc64",
+        target_arch = "s390x",
+        target_arch = "arm",
+        target_arch = "mips",
+        target_arch = "mips32r6",
+        target_arch = "mips64",
+        target_arch = "mips64r6",
+        target_arch = "sparc",
+        target_arch = "hexagon",
+        target_arch = "m68k",
+    )),
+    repr(align(64))
+)]
+#[derive(Default)]
+pub struct CachePadded<T> {
+    value: T,
+}
+
+impl<T> CachePadded<T> {
+    #[inline(always)]
+    pub const fn new(value: T) -> CachePadded<T> {
+        Self { value }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> From<T> for CachePadded<T> {
+    fn from(value: T) -> CachePadded<T> {
+        Self::new(value)
+    }
+}
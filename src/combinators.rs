@@ -0,0 +1,103 @@
+use core::ops::Deref;
+
+use crate::{Initializer, SpinCell};
+
+// A lazily initialized value that another lazy is derived from. Any cell
+// type works, since they all dereference to their value.
+type Source<'a, U> = &'a (dyn Deref<Target = U> + Sync);
+
+/// A lazy value derived from another one.
+///
+/// On first access the source is initialized if needed and `f` builds the
+/// value from it. Chains of dependent singletons can be declared directly,
+/// without writing an initializer for each step by hand.
+///
+/// ```
+/// use spincell::{LazyMap, SpinCell};
+///
+/// static CONFIG: SpinCell<&str> = SpinCell::new(|| "workers=4");
+/// static WORKERS: LazyMap<&str, usize> =
+///     LazyMap::new(&CONFIG, |config| config[8..].parse().unwrap());
+/// static THREADS: LazyMap<usize, usize> = LazyMap::new(&WORKERS, |workers| workers + 1);
+/// assert_eq!(*THREADS, 5);
+/// ```
+pub struct LazyMap<'a, U: ?Sized, T, F = fn(&U) -> T> {
+    inner: SpinCell<T, Map<'a, U, F>>,
+}
+
+struct Map<'a, U: ?Sized, F> {
+    source: Source<'a, U>,
+    f: F,
+}
+
+impl<'a, U: ?Sized, T, F: FnOnce(&'a U) -> T> Initializer<T> for Map<'a, U, F> {
+    #[inline(always)]
+    fn init(self) -> T {
+        (self.f)(self.source)
+    }
+}
+
+impl<'a, U: ?Sized, T, F: FnOnce(&'a U) -> T> LazyMap<'a, U, T, F> {
+    #[inline(always)]
+    pub const fn new(source: Source<'a, U>, f: F) -> LazyMap<'a, U, T, F> {
+        Self {
+            inner: SpinCell::new(Map { source, f }),
+        }
+    }
+}
+
+impl<'a, U: ?Sized, T, F: FnOnce(&'a U) -> T> Deref for LazyMap<'a, U, T, F> {
+    type Target = T;
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+/// A lazy pair of references to the values of two other lazies.
+///
+/// The first access initializes both sources. Combined with [`LazyMap`]
+/// this derives a value from several others.
+///
+/// ```
+/// use spincell::{LazyZip, SpinCell};
+///
+/// static WIDTH: SpinCell<u32> = SpinCell::new(|| 640);
+/// static HEIGHT: SpinCell<u32> = SpinCell::new(|| 480);
+/// static SIZE: LazyZip<u32, u32> = LazyZip::new(&WIDTH, &HEIGHT);
+///
+/// let (width, height) = *SIZE;
+/// assert_eq!(width * height, 307_200);
+/// ```
+pub struct LazyZip<'a, A: ?Sized, B: ?Sized> {
+    inner: SpinCell<(&'a A, &'a B), Zip<'a, A, B>>,
+}
+
+struct Zip<'a, A: ?Sized, B: ?Sized> {
+    first: Source<'a, A>,
+    second: Source<'a, B>,
+}
+
+impl<'a, A: ?Sized, B: ?Sized> Initializer<(&'a A, &'a B)> for Zip<'a, A, B> {
+    #[inline(always)]
+    fn init(self) -> (&'a A, &'a B) {
+        (self.first, self.second)
+    }
+}
+
+impl<'a, A: ?Sized, B: ?Sized> LazyZip<'a, A, B> {
+    #[inline(always)]
+    pub const fn new(first: Source<'a, A>, second: Source<'a, B>) -> LazyZip<'a, A, B> {
+        Self {
+            inner: SpinCell::new(Zip { first, second }),
+        }
+    }
+}
+
+impl<'a, A: ?Sized, B: ?Sized> Deref for LazyZip<'a, A, B> {
+    type Target = (&'a A, &'a B);
+    #[inline(always)]
+    fn deref(&self) -> &(&'a A, &'a B) {
+        &self.inner
+    }
+}
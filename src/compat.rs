@@ -0,0 +1,4 @@
+//! Types mirroring the API of other crates, so that a project can switch to
+//! spincell by changing its `use` declarations.
+
+pub mod once_cell;
@@ -0,0 +1,165 @@
+//! Replacements for `once_cell::sync::Lazy` and `once_cell::sync::OnceCell`.
+//!
+//! The types here have the same names and method signatures as their
+//! counterparts in the once_cell crate, so switching a project over is a
+//! matter of replacing `use once_cell::sync::Lazy` with
+//! `use spincell::compat::once_cell::sync::Lazy`. Waiting threads spin
+//! instead of blocking, as with every spincell type.
+//!
+//! ```
+//! use spincell::compat::once_cell::sync::{Lazy, OnceCell};
+//!
+//! static GREETING: Lazy<&str> = Lazy::new(|| "hello");
+//! static NAME: OnceCell<&str> = OnceCell::new();
+//!
+//! assert_eq!(*GREETING, "hello");
+//! assert_eq!(NAME.get(), None);
+//! assert_eq!(NAME.set("world"), Ok(()));
+//! assert_eq!(*NAME.get_or_init(|| "unused"), "world");
+//! ```
+
+use core::ops::Deref;
+
+use crate::SpinCell;
+
+/// The types, under the module path once_cell exports them from.
+pub mod sync {
+    pub use super::{Lazy, OnceCell};
+}
+
+/// A value initialized on first access, like `once_cell::sync::Lazy`.
+///
+/// If the initializer panics, the `Lazy` is poisoned and later accesses
+/// panic as well.
+pub struct Lazy<T, F = fn() -> T> {
+    cell: SpinCell<T, F>,
+}
+
+impl<T, F> Lazy<T, F> {
+    #[inline(always)]
+    pub const fn new(init: F) -> Lazy<T, F> {
+        Lazy {
+            cell: SpinCell::with_init(init),
+        }
+    }
+
+    /// Returns the value if it has been initialized.
+    #[inline]
+    pub fn get(this: &Lazy<T, F>) -> Option<&T> {
+        SpinCell::get(&this.cell)
+    }
+
+    /// Consumes the `Lazy`, returning the value if it has been initialized
+    /// and the initializer otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Lazy` is poisoned.
+    #[inline]
+    pub fn into_value(this: Lazy<T, F>) -> Result<T, F> {
+        SpinCell::into_inner(this.cell)
+    }
+}
+
+impl<T, F: FnOnce() -> T> Lazy<T, F> {
+    /// Initializes the value if needed and returns a reference to it.
+    #[inline]
+    pub fn force(this: &Lazy<T, F>) -> &T {
+        &this.cell
+    }
+}
+
+impl<T, F: FnOnce() -> T> Deref for Lazy<T, F> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        Lazy::force(self)
+    }
+}
+
+impl<T: Default> Default for Lazy<T> {
+    fn default() -> Lazy<T> {
+        Lazy::new(T::default)
+    }
+}
+
+/// A cell that can be written to once, like `once_cell::sync::OnceCell`.
+///
+/// If the closure passed to [`get_or_init`](OnceCell::get_or_init) panics,
+/// the cell stays empty, and the next call tries again.
+pub struct OnceCell<T> {
+    // The initializer slot is unused; values are always supplied by the
+    // caller.
+    cell: SpinCell<T, ()>,
+}
+
+impl<T> OnceCell<T> {
+    /// Creates an empty cell.
+    #[inline(always)]
+    pub const fn new() -> OnceCell<T> {
+        OnceCell {
+            cell: SpinCell::empty(),
+        }
+    }
+
+    /// Creates a cell holding `value`.
+    #[inline(always)]
+    pub const fn with_value(value: T) -> OnceCell<T> {
+        OnceCell {
+            cell: SpinCell::from_value(value),
+        }
+    }
+
+    /// Returns the value, or `None` if the cell is empty.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        SpinCell::get(&self.cell)
+    }
+
+    /// Returns a mutable reference to the value, or `None` if the cell is
+    /// empty.
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        SpinCell::get_mut(&mut self.cell)
+    }
+
+    /// Stores `value` if the cell is empty, and returns it back otherwise.
+    /// If another thread is initializing the cell, waits for it first.
+    #[inline]
+    pub fn set(&self, value: T) -> Result<(), T> {
+        SpinCell::set(&self.cell, value)
+    }
+
+    /// Returns the value, initializing the cell with `f` if it is empty.
+    ///
+    /// Only one thread runs its `f`; the others wait for it and return the
+    /// value it stored.
+    #[inline]
+    pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+        SpinCell::get_or_init(&self.cell, f)
+    }
+
+    /// Consumes the cell, returning the value if there is one.
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        SpinCell::into_inner(self.cell).ok()
+    }
+
+    /// Takes the value out, leaving the cell empty.
+    #[inline]
+    pub fn take(&mut self) -> Option<T> {
+        SpinCell::take(&mut self.cell)
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> OnceCell<T> {
+        OnceCell::new()
+    }
+}
+
+impl<T> From<T> for OnceCell<T> {
+    fn from(value: T) -> OnceCell<T> {
+        OnceCell::with_value(value)
+    }
+}
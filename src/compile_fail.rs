@@ -0,0 +1,136 @@
+// Misuse that must not compile, kept as `compile_fail` doctests so that
+// `cargo test --doc` checks the type-level guarantees with each error code.
+// The module only exists for rustdoc.
+
+/// A cell is only `Sync` if its value is, so it cannot be a static holding
+/// a `Cell`:
+///
+/// ```compile_fail,E0277
+/// use core::cell::Cell;
+/// use spincell::SpinCell;
+///
+/// static COUNT: SpinCell<Cell<u32>> = SpinCell::new(|| Cell::new(0));
+/// ```
+fn value_must_be_sync() {}
+
+/// Sharing a cell also sends its value, which is built and dropped by
+/// whichever thread gets there:
+///
+/// ```compile_fail,E0277
+/// use core::marker::PhantomData;
+/// use spincell::SpinCell;
+///
+/// struct NotSend(PhantomData<*const ()>);
+/// unsafe impl Sync for NotSend {}
+///
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<SpinCell<NotSend>>();
+/// ```
+fn value_must_be_send_to_share() {}
+
+/// Whichever thread accesses the cell first runs the initializer, so it
+/// must be `Send` too:
+///
+/// ```compile_fail,E0277
+/// use std::rc::Rc;
+/// use spincell::SpinCell;
+///
+/// let shared = Rc::new(1);
+/// let cell = SpinCell::new(move || *shared);
+/// std::thread::scope(|s| {
+///     s.spawn(|| *cell);
+/// });
+/// ```
+fn initializer_must_be_send_to_share() {}
+
+/// Moving a cell to another thread moves its value:
+///
+/// ```compile_fail,E0277
+/// use std::rc::Rc;
+/// use spincell::SpinCell;
+///
+/// let cell: SpinCell<Rc<u32>> = SpinCell::new(|| Rc::new(1));
+/// std::thread::spawn(move || **cell);
+/// ```
+fn value_must_be_send_to_move() {}
+
+/// The cells that hand out mutable access need the value to be `Send`
+/// rather than `Sync`, and still reject values that are neither:
+///
+/// ```compile_fail,E0277
+/// use std::rc::Rc;
+/// use spincell::SpinMutCell;
+///
+/// static SHARED: SpinMutCell<Rc<u32>> = SpinMutCell::new(|| Rc::new(1));
+/// ```
+///
+/// ```compile_fail,E0277
+/// use std::rc::Rc;
+/// use spincell::SpinResetCell;
+///
+/// static SHARED: SpinResetCell<Rc<u32>> = SpinResetCell::new(|| Rc::new(1));
+/// ```
+fn mutable_cells_need_send_values() {}
+
+/// A cell without an initializer has no value to produce on first access,
+/// so it cannot be dereferenced, only read with `get`:
+///
+/// ```compile_fail,E0614
+/// use spincell::SpinOnceCell;
+///
+/// let cell: SpinOnceCell<u32> = SpinOnceCell::empty();
+/// let _ = *cell;
+/// ```
+fn once_cell_has_no_deref() {}
+
+/// References to the value borrow the cell:
+///
+/// ```compile_fail,E0597
+/// use spincell::SpinCell;
+///
+/// let value: &u32;
+/// {
+///     let cell: SpinCell<u32> = SpinCell::new(|| 1);
+///     value = &cell;
+/// }
+/// assert_eq!(*value, 1);
+/// ```
+///
+/// ```compile_fail,E0505
+/// use spincell::SpinCell;
+///
+/// let cell: SpinCell<u32> = SpinCell::new(|| 1);
+/// let value = SpinCell::get(&cell);
+/// let _ = SpinCell::into_inner(cell);
+/// assert_eq!(value, None);
+/// ```
+fn references_borrow_the_cell() {}
+
+/// A static cell cannot be taken apart, as others may be reading it:
+///
+/// ```compile_fail,E0507
+/// use spincell::SpinCell;
+///
+/// static CONFIG: SpinCell<u32> = SpinCell::new(|| 1);
+/// let _ = SpinCell::into_inner(CONFIG);
+/// ```
+fn statics_cannot_be_consumed() {}
+
+/// The statics of `spincell_fields!` must read from the name on its `let`
+/// line:
+///
+/// ```compile_fail,E0425
+/// struct Calibration {
+///     offset: i32,
+/// }
+///
+/// fn calibrate() -> Calibration {
+///     Calibration { offset: -3 }
+/// }
+///
+/// spincell::spincell_fields! {
+///     let calibration: Calibration = calibrate();
+///     static OFFSET: i32 = calib.offset;
+/// }
+/// ```
+fn fields_name_the_shared_result() {}
@@ -0,0 +1,71 @@
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+use crate::{Initializer, SpinCell};
+
+/// The initializer of a cell created with [`SpinCell::new_cyclic`].
+pub struct Cyclic<F>(F);
+
+/// A handle to a cell whose value is still being built, passed to the
+/// initializer of [`SpinCell::new_cyclic`].
+///
+/// The value does not exist yet, so the handle cannot be dereferenced. It
+/// only gives out the address the value will live at, which stays valid for
+/// as long as the cell is neither moved nor dropped.
+pub struct CyclicRef<'a, T> {
+    value: NonNull<T>,
+    _cell: PhantomData<&'a T>,
+}
+
+impl<T> CyclicRef<'_, T> {
+    /// The address the value will be stored at once initialized.
+    #[inline]
+    pub fn as_ptr(&self) -> NonNull<T> {
+        self.value
+    }
+}
+
+impl<T, F: FnOnce(CyclicRef<'_, T>) -> T> SpinCell<T, Cyclic<F>> {
+    /// Creates a cell whose initializer is told where the value will live.
+    ///
+    /// Like `Arc::new_cyclic`, this lets a value refer to itself, for
+    /// example so that the nodes of an intrusive structure can point back at
+    /// the singleton owning them, or so the value can register its address
+    /// somewhere during construction.
+    ///
+    /// ```
+    /// use core::ptr::NonNull;
+    /// use spincell::{Cyclic, CyclicRef, SpinCell};
+    ///
+    /// struct Node {
+    ///     owner: NonNull<Node>,
+    /// }
+    ///
+    /// unsafe impl Send for Node {}
+    /// unsafe impl Sync for Node {}
+    ///
+    /// static ROOT: SpinCell<Node, Cyclic<fn(CyclicRef<'_, Node>) -> Node>> =
+    ///     SpinCell::new_cyclic(|this| Node { owner: this.as_ptr() });
+    /// assert_eq!(ROOT.owner.as_ptr().cast_const(), &*ROOT as *const Node);
+    /// ```
+    #[inline(always)]
+    pub const fn new_cyclic(init_func: F) -> SpinCell<T, Cyclic<F>> {
+        Self::with_init(Cyclic(init_func))
+    }
+}
+
+impl<T, F: FnOnce(CyclicRef<'_, T>) -> T> Initializer<T> for Cyclic<F> {
+    // The cells always call `init_at`; without a cell there is no address
+    // to hand out.
+    fn init(self) -> T {
+        panic!("a cyclic initializer can only be run by its cell")
+    }
+
+    #[inline(always)]
+    fn init_at(self, value: NonNull<T>) -> T {
+        (self.0)(CyclicRef {
+            value,
+            _cell: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,127 @@
+/// Declares `SpinCell` statics from an initializer expression.
+///
+/// Each `static NAME: Type = expr;` becomes a `static NAME: SpinCell<Type>`
+/// whose initializer evaluates `expr` on first access. The expression is
+/// wrapped in a function, so the static's type is written out with a plain
+/// function pointer and no closure type ever has to be named:
+///
+/// ```
+/// use spincell::SpinCell;
+///
+/// struct Config {
+///     retries: u32,
+/// }
+///
+/// fn load_config() -> Config {
+///     Config { retries: 3 }
+/// }
+///
+/// spincell::spincell! {
+///     static CONFIG: Config = load_config();
+///     pub static RETRY_LIMIT: u32 = CONFIG.retries * 2;
+/// }
+///
+/// assert_eq!(*RETRY_LIMIT, 6);
+/// assert!(SpinCell::is_initialized(&CONFIG));
+/// ```
+///
+/// The function pointer is only called once, on the initialization path;
+/// initialized accesses never touch it.
+#[macro_export]
+macro_rules! spincell {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::SpinCell<$ty> = {
+                fn init() -> $ty {
+                    $init
+                }
+                $crate::SpinCell::new(init)
+            };
+        )*
+    };
+}
+
+/// Declares `SpinCell` statics that share one initializer, each holding a
+/// field of its result.
+///
+/// The `let` line names a computation and the struct it returns. The first
+/// access to any of the statics runs it once; each static then moves its
+/// own field out of the shared result the first time it is accessed:
+///
+/// ```
+/// struct Calibration {
+///     offset: i32,
+///     gain: u32,
+///     label: &'static str,
+/// }
+///
+/// fn calibrate() -> Calibration {
+///     Calibration { offset: -3, gain: 12, label: "adc0" }
+/// }
+///
+/// spincell::spincell_fields! {
+///     let calibration: Calibration = calibrate();
+///     pub static OFFSET: i32 = calibration.offset;
+///     pub static GAIN: u32 = calibration.gain;
+/// }
+///
+/// assert_eq!(*GAIN, 12);
+/// assert_eq!(*OFFSET, -3);
+/// ```
+///
+/// The struct must have named fields and must not implement `Drop`, and
+/// each field may be claimed by one static only. Fields no static claims,
+/// like `label` above, are never dropped, as with any static.
+///
+/// The name on the `let` line becomes an `unsafe fn` returning the shared
+/// result, which only the generated statics may call. Each static must read
+/// its field from that name; any other name fails to compile.
+#[macro_export]
+macro_rules! spincell_fields {
+    (
+        let $shared:ident: $parts:path = $init:expr;
+        $($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $from:ident.$field:ident;)*
+    ) => {
+        #[doc(hidden)]
+        unsafe fn $shared() -> &'static ::core::mem::ManuallyDrop<$parts> {
+            fn init() -> ::core::mem::ManuallyDrop<$parts> {
+                ::core::mem::ManuallyDrop::new($init)
+            }
+            static PARTS: $crate::SpinCell<::core::mem::ManuallyDrop<$parts>> =
+                $crate::SpinCell::new(init);
+            &PARTS
+        }
+
+        // Fails to compile if a field is claimed twice or the struct
+        // implements Drop, either of which would make moving the fields out
+        // unsound.
+        const _: () = {
+            #[allow(dead_code, unused_variables)]
+            fn claim(parts: $parts) {
+                let $parts { $($field,)* .. } = parts;
+            }
+        };
+
+        $(
+            // Fails to compile unless the static names the shared result:
+            // inside, only `$shared` resolves to the local unit struct.
+            const _: () = {
+                #[allow(non_camel_case_types, dead_code)]
+                struct $shared;
+                let _: $shared = $from;
+            };
+
+            $(#[$attr])*
+            $vis static $name: $crate::SpinCell<$ty> = {
+                fn init() -> $ty {
+                    // Safety: this runs at most once, and no other static
+                    // claims the same field, so the field is moved out of the
+                    // shared result exactly once and never dropped there.
+                    unsafe { ::core::ptr::read(&$shared().$field) }
+                }
+                $crate::SpinCell::new(init)
+            };
+        )*
+    };
+}
@@ -0,0 +1,124 @@
+use core::cell::UnsafeCell;
+use core::mem::ManuallyDrop;
+
+use crate::split::SplitSpinCell;
+use crate::state::{State, EAGER, SEALED};
+use crate::{Initializer, Slot, SpinCell};
+
+/// A cell that can be initialized ahead of its first access.
+///
+/// Implemented by every cell type so that [`init_all`] can take a mixed
+/// list of them.
+pub trait LazyInit {
+    /// Runs the initializer unless the cell is already initialized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initializer panicked during an earlier attempt.
+    fn init(&self);
+}
+
+/// Initializes each of `cells` in order.
+///
+/// This is the only way to initialize a cell created with
+/// [`SpinCell::eager`], and works just as well for lazy cells that should
+/// be warmed up at a known point, for example at the end of startup.
+///
+/// ```
+/// use spincell::SpinCell;
+///
+/// static PORT: SpinCell<u16> = SpinCell::eager(|| 8080);
+/// static NAME: SpinCell<&str> = SpinCell::new(|| "server");
+///
+/// spincell::init_all(&[&PORT, &NAME]);
+/// assert!(SpinCell::is_initialized(&PORT));
+/// assert_eq!(*PORT, 8080);
+/// ```
+pub fn init_all(cells: &[&dyn LazyInit]) {
+    for cell in cells {
+        cell.init();
+    }
+}
+
+impl<T, G: Initializer<T>> SpinCell<T, G> {
+    /// Creates a cell that is initialized by [`init_all`] rather than on
+    /// first access.
+    ///
+    /// Accessing the cell before `init_all` has initialized it panics, so a
+    /// missing entry in the startup sequence shows up on the first access
+    /// instead of silently running the initializer at an arbitrary point.
+    #[inline(always)]
+    pub const fn eager(init_func: G) -> SpinCell<T, G> {
+        Self {
+            state: State::new(EAGER),
+            slot: UnsafeCell::new(Slot {
+                init_func: ManuallyDrop::new(init_func),
+            }),
+        }
+    }
+
+    /// Forbids initializing the cell from now on, unless it is initialized
+    /// already.
+    ///
+    /// The initializer of an uninitialized cell is dropped without being
+    /// run, [`get`](SpinCell::get) keeps returning `None`, and accessing
+    /// the value panics instead of initializing it. Sealing the cells whose
+    /// values are too heavy to create lazily at the end of a boot phase
+    /// turns a late first access into an error rather than a stall at an
+    /// arbitrary point. Eager cells can be sealed too.
+    ///
+    /// Returns whether the cell is sealed, which is false if it was
+    /// initialized or poisoned first. An initialization in progress is
+    /// waited for.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// static CACHE: SpinCell<Vec<u8>> = SpinCell::new(|| vec![0; 1 << 20]);
+    /// static CONFIG: SpinCell<u32> = SpinCell::new(|| 7);
+    ///
+    /// assert_eq!(*CONFIG, 7);
+    /// assert!(SpinCell::seal(&CACHE));
+    /// assert!(!SpinCell::seal(&CONFIG));
+    /// assert!(SpinCell::is_sealed(&CACHE) && SpinCell::get(&CACHE).is_none());
+    /// ```
+    pub fn seal(me: &SpinCell<T, G>) -> bool {
+        match me.state.lock_unsealed() {
+            Ok(()) => {
+                // Safety: the lock is held and the slot holds the
+                // initializer, which is moved out exactly once.
+                let init_func = unsafe { ManuallyDrop::take(&mut (*me.slot.get()).init_func) };
+                me.state.restore(SEALED);
+                drop(init_func);
+                true
+            }
+            Err(state) => state == SEALED,
+        }
+    }
+
+    /// Returns whether the cell has been sealed with [`SpinCell::seal`].
+    #[inline]
+    pub fn is_sealed(me: &SpinCell<T, G>) -> bool {
+        me.state.is_sealed()
+    }
+}
+
+impl<T, G: Initializer<T>> LazyInit for SpinCell<T, G> {
+    fn init(&self) {
+        self.state.release_eager();
+        let _ = SpinCell::try_initialize(self);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, G: Initializer<T>> LazyInit for crate::BoxedSpinCell<T, G> {
+    fn init(&self) {
+        let _ = crate::BoxedSpinCell::try_initialize(self);
+    }
+}
+
+impl<T, G: Initializer<T>> LazyInit for SplitSpinCell<T, G> {
+    fn init(&self) {
+        let _ = SplitSpinCell::try_initialize(self);
+    }
+}
@@ -0,0 +1,62 @@
+/// Generates `extern "C"` accessors for a [`SpinCell`](crate::SpinCell)
+/// static, so C code in the same image can share the singleton.
+///
+/// For each static three unmangled functions are emitted:
+///
+/// - `get() -> *const T` initializes the value if needed and returns a
+///   pointer to it;
+/// - `is_initialized() -> bool` reports whether the value exists, without
+///   initializing it;
+/// - `force_init()` initializes the value if needed.
+///
+/// A panic in the initializer cannot unwind into C and aborts the process.
+///
+/// ```
+/// use spincell::SpinCell;
+///
+/// #[repr(C)]
+/// pub struct Config {
+///     baud: u32,
+/// }
+///
+/// static CONFIG: SpinCell<Config> = SpinCell::new(|| Config { baud: 115_200 });
+///
+/// spincell::spincell_ffi! {
+///     CONFIG: Config {
+///         get: config_get,
+///         is_initialized: config_is_initialized,
+///         force_init: config_force_init,
+///     }
+/// }
+///
+/// assert!(!config_is_initialized());
+/// assert_eq!(unsafe { (*config_get()).baud }, 115_200);
+/// assert!(config_is_initialized());
+/// ```
+#[macro_export]
+macro_rules! spincell_ffi {
+    ($(
+        $cell:ident: $ty:ty {
+            get: $get:ident,
+            is_initialized: $is_initialized:ident,
+            force_init: $force_init:ident $(,)?
+        }
+    )*) => {
+        $(
+            #[no_mangle]
+            pub extern "C" fn $get() -> *const $ty {
+                &*$cell as *const $ty
+            }
+
+            #[no_mangle]
+            pub extern "C" fn $is_initialized() -> bool {
+                $crate::SpinCell::is_initialized(&$cell)
+            }
+
+            #[no_mangle]
+            pub extern "C" fn $force_init() {
+                let _ = $crate::SpinCell::try_initialize(&$cell);
+            }
+        )*
+    };
+}
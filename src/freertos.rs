@@ -0,0 +1,116 @@
+//! Integration with the FreeRTOS kernel, which the firmware links in.
+//!
+//! [`Yield`] lets tasks waiting for a cell give way to the task that is
+//! initializing it, and [`Critical`] runs an initializer inside a FreeRTOS
+//! critical section. The kernel functions are declared here directly, so
+//! they must be provided by the FreeRTOS port at link time. `TickType_t` is
+//! assumed to be 32 bits wide, the default.
+//!
+//! Requires the `freertos` feature.
+//!
+//! ```ignore
+//! use spincell::freertos::{CriticalSpinCell, Yield};
+//! use spincell::{ConfiguredSpinCell, SpinCell};
+//!
+//! static CONFIG: ConfiguredSpinCell<u32, fn() -> u32, Yield> =
+//!     SpinCell::builder(read_config as fn() -> u32).strategy(Yield::new()).build();
+//!
+//! static CLOCK: CriticalSpinCell<u32> = CriticalSpinCell::new_critical(setup_clock);
+//! ```
+
+use core::ptr::NonNull;
+
+use crate::{Backoff, Initializer, SpinCell, WaitStrategy};
+
+extern "C" {
+    fn vTaskDelay(ticks: u32);
+    fn vPortEnterCritical();
+    fn vPortExitCritical();
+}
+
+// Rounds of backoff before the waiting task starts yielding.
+const SPIN_ROUNDS: u32 = 8;
+
+/// Spins briefly, then yields to other tasks of the same priority with
+/// `vTaskDelay(0)` every round, so a waiting task does not starve the task
+/// that runs the initializer on the same core.
+///
+/// A task waiting for a lower priority task is not helped by yielding; give
+/// initializers that may be raced at least the priority of their callers.
+#[derive(Clone, Copy, Default)]
+pub struct Yield {
+    rounds: u32,
+    backoff: Backoff,
+}
+
+impl Yield {
+    #[inline(always)]
+    pub const fn new() -> Yield {
+        Yield {
+            rounds: 0,
+            backoff: Backoff::new(),
+        }
+    }
+}
+
+impl WaitStrategy for Yield {
+    fn wait(&mut self) {
+        if self.rounds < SPIN_ROUNDS {
+            self.rounds += 1;
+            self.backoff.spin();
+        } else {
+            // Safety: called from a task, as every cell access that can
+            // wait has to be.
+            unsafe { vTaskDelay(0) };
+        }
+    }
+}
+
+/// An initializer that runs inside a FreeRTOS critical section, with
+/// interrupts masked up to `configMAX_SYSCALL_INTERRUPT_PRIORITY` and no
+/// task switches.
+///
+/// For short initializers that configure hardware also touched by
+/// interrupt handlers. The initializer must not block or call FreeRTOS APIs
+/// that are not allowed in critical sections.
+pub struct Critical<G>(G);
+
+/// A [`SpinCell`] whose initializer runs in a critical section.
+pub type CriticalSpinCell<T, G = fn() -> T> = SpinCell<T, Critical<G>>;
+
+impl<T, G: Initializer<T>> SpinCell<T, Critical<G>> {
+    /// Creates a cell that runs `init_func` inside a critical section.
+    #[inline(always)]
+    pub const fn new_critical(init_func: G) -> SpinCell<T, Critical<G>> {
+        Self::with_init(Critical(init_func))
+    }
+}
+
+// Leaves the critical section, also if the initializer unwinds.
+struct Exit;
+
+impl Drop for Exit {
+    fn drop(&mut self) {
+        unsafe { vPortExitCritical() };
+    }
+}
+
+impl<T, G: Initializer<T>> Initializer<T> for Critical<G> {
+    fn init(self) -> T {
+        unsafe { vPortEnterCritical() };
+        let _exit = Exit;
+        self.0.init()
+    }
+
+    fn init_at(self, value: NonNull<T>) -> T {
+        unsafe { vPortEnterCritical() };
+        let _exit = Exit;
+        self.0.init_at(value)
+    }
+
+    fn init_observed(self, value: NonNull<T>) -> (T, Option<fn(&T)>) {
+        unsafe { vPortEnterCritical() };
+        let _exit = Exit;
+        self.0.init_observed(value)
+    }
+}
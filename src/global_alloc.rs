@@ -0,0 +1,76 @@
+use core::alloc::{GlobalAlloc, Layout};
+
+use crate::{Initializer, SpinCell};
+
+/// Forwards to the allocator in the cell, initializing it on the first
+/// allocation.
+///
+/// This lets a heap whose configuration is only known at run time, such as
+/// an allocator over a memory region discovered during boot, be declared as
+/// the `#[global_allocator]` directly:
+///
+/// ```no_run
+/// use core::alloc::{GlobalAlloc, Layout};
+/// use core::sync::atomic::{AtomicUsize, Ordering};
+/// use spincell::SpinCell;
+///
+/// struct Bump {
+///     next: AtomicUsize,
+///     end: usize,
+/// }
+///
+/// unsafe impl GlobalAlloc for Bump {
+///     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+///         let mut next = self.next.load(Ordering::Relaxed);
+///         loop {
+///             let start = (next + layout.align() - 1) & !(layout.align() - 1);
+///             let end = start + layout.size();
+///             if end > self.end {
+///                 return core::ptr::null_mut();
+///             }
+///             match self.next.compare_exchange_weak(next, end, Ordering::Relaxed, Ordering::Relaxed) {
+///                 Ok(_) => return start as *mut u8,
+///                 Err(actual) => next = actual,
+///             }
+///         }
+///     }
+///
+///     unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+/// }
+///
+/// # fn heap_region() -> (usize, usize) {
+/// #     static mut HEAP: [u8; 1 << 20] = [0; 1 << 20];
+/// #     let start = unsafe { core::ptr::addr_of_mut!(HEAP) as usize };
+/// #     (start, start + (1 << 20))
+/// # }
+/// #[global_allocator]
+/// static HEAP: SpinCell<Bump> = SpinCell::new(|| {
+///     let (start, end) = heap_region();
+///     Bump { next: AtomicUsize::new(start), end }
+/// });
+/// # fn main() {}
+/// ```
+///
+/// The initializer must not allocate from the same cell: the allocation
+/// would wait for the initialization it is part of, and deadlock.
+unsafe impl<A: GlobalAlloc, G: Initializer<A>> GlobalAlloc for SpinCell<A, G> {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        (**self).alloc(layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        (**self).dealloc(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        (**self).alloc_zeroed(layout)
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        (**self).realloc(ptr, layout, new_size)
+    }
+}
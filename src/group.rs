@@ -0,0 +1,197 @@
+use core::mem::ManuallyDrop;
+
+use crate::{Initializer, SpinCell};
+
+/// A set of cells initialized together, all or nothing.
+///
+/// [`init`](SpinCellGroup::init) locks every member, runs their initializers
+/// with all the locks held, and only then publishes the values. No member
+/// becomes readable before every value has been built, and threads that
+/// access a member in the meantime wait for the group. If an initializer
+/// panics, the values already built are dropped, the
+/// members whose initializers had not run yet go back to being
+/// uninitialized, and the members whose initializers were consumed are
+/// poisoned.
+///
+/// ```
+/// use spincell::{SpinCell, SpinCellGroup};
+///
+/// static CLOCK: SpinCell<u32> = SpinCell::new(|| 48_000_000);
+/// static UART: SpinCell<u32> = SpinCell::new(|| 115_200);
+/// static DRIVERS: SpinCellGroup = SpinCellGroup::new(&[&CLOCK, &UART]);
+///
+/// DRIVERS.init();
+/// assert!(SpinCell::is_initialized(&CLOCK) && SpinCell::is_initialized(&UART));
+/// ```
+///
+/// Initializers must not access other members of their group, since those
+/// stay locked until the whole group is published; doing so deadlocks. The
+/// same goes for listing a cell twice. Members initialized before `init` is
+/// called are left as they are. A group has at most
+/// [`MAX_MEMBERS`](SpinCellGroup::MAX_MEMBERS) members.
+pub struct SpinCellGroup<'a> {
+    members: &'a [&'a (dyn GroupMember + Sync)],
+}
+
+/// A cell that can be part of a [`SpinCellGroup`].
+pub trait GroupMember: member::Sealed {}
+
+mod member {
+    pub enum Abort {
+        // The value was written to the slot.
+        Built,
+        // The initializer was taken out of the slot but did not finish.
+        Consumed,
+        // The initializer is still in the slot.
+        Untouched,
+    }
+
+    // The steps of a group initialization. Everything but `lock` requires
+    // the member to be locked by the caller.
+    pub trait Sealed {
+        // Locks an uninitialized member. Returns false if it is READY.
+        fn lock(&self) -> bool;
+        unsafe fn build(&self);
+        unsafe fn publish(&self);
+        unsafe fn abort(&self, how: Abort);
+    }
+}
+
+use member::Abort;
+
+impl<T, G: Initializer<T>> GroupMember for SpinCell<T, G> {}
+
+impl<T, G: Initializer<T>> member::Sealed for SpinCell<T, G> {
+    fn lock(&self) -> bool {
+        !self.state.is_ready() && self.state.lock()
+    }
+
+    unsafe fn build(&self) {
+        let value = self.value_ptr();
+        let init_func = ManuallyDrop::take(&mut (*self.slot.get()).init_func);
+        #[cfg(feature = "testing")]
+        crate::testing::record(&self.state, crate::testing::Event::Init);
+        value.write(init_func.init_at(value));
+    }
+
+    unsafe fn publish(&self) {
+        self.state.publish();
+    }
+
+    unsafe fn abort(&self, how: Abort) {
+        let slot = self.slot.get();
+        match how {
+            Abort::Untouched => self.state.unlock(),
+            Abort::Consumed => self.state.poison(),
+            Abort::Built => {
+                // Poison first so a panicking destructor cannot leave the
+                // cell locked.
+                self.state.poison();
+                ManuallyDrop::drop(&mut (*slot).value);
+                #[cfg(feature = "testing")]
+                crate::testing::record(&self.state, crate::testing::Event::Drop);
+            }
+        }
+    }
+}
+
+// Undoes a group initialization that unwound. `locked` members have been
+// through the lock step, and those whose bit is set in `owned` were locked
+// by this call. The first `built` members have their values in place, and
+// if `building` is set the next one's initializer was running.
+struct Rollback<'a> {
+    members: &'a [&'a (dyn GroupMember + Sync)],
+    owned: Owned,
+    locked: usize,
+    built: usize,
+    building: bool,
+}
+
+impl Drop for Rollback<'_> {
+    fn drop(&mut self) {
+        for (i, member) in self.members[..self.locked].iter().enumerate() {
+            // Members that were already initialized are not ours to undo.
+            if !self.owned.get(i) {
+                continue;
+            }
+            let how = if i < self.built {
+                Abort::Built
+            } else if i == self.built && self.building {
+                Abort::Consumed
+            } else {
+                Abort::Untouched
+            };
+            unsafe { member.abort(how) };
+        }
+    }
+}
+
+// One bit per member, set if this call locked it. The state byte alone
+// cannot tell: another thread may briefly lock a member that was already
+// READY, as cloning it does.
+#[derive(Clone, Copy)]
+struct Owned(u128);
+
+impl Owned {
+    fn get(self, i: usize) -> bool {
+        self.0 & (1 << i) != 0
+    }
+}
+
+impl<'a> SpinCellGroup<'a> {
+    /// The most members a group can have.
+    pub const MAX_MEMBERS: usize = 128;
+
+    /// Creates a group of `members`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than [`MAX_MEMBERS`](Self::MAX_MEMBERS)
+    /// members, which fails to compile for a group in a static.
+    #[inline(always)]
+    pub const fn new(members: &'a [&'a (dyn GroupMember + Sync)]) -> SpinCellGroup<'a> {
+        assert!(
+            members.len() <= Self::MAX_MEMBERS,
+            "a SpinCellGroup has at most 128 members"
+        );
+        Self { members }
+    }
+
+    /// Initializes every member that is not initialized yet, publishing all
+    /// of them at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a member is poisoned, or if an initializer panics, after
+    /// rolling back as described on [`SpinCellGroup`].
+    pub fn init(&self) {
+        let mut rollback = Rollback {
+            members: self.members,
+            owned: Owned(0),
+            locked: 0,
+            built: 0,
+            building: false,
+        };
+        for (i, member) in self.members.iter().enumerate() {
+            if member.lock() {
+                rollback.owned.0 |= 1 << i;
+            }
+            rollback.locked += 1;
+        }
+        let owned = rollback.owned;
+        for (i, member) in self.members.iter().enumerate() {
+            if owned.get(i) {
+                rollback.building = true;
+                unsafe { member.build() };
+                rollback.building = false;
+            }
+            rollback.built += 1;
+        }
+        core::mem::forget(rollback);
+        for (i, member) in self.members.iter().enumerate() {
+            if owned.get(i) {
+                unsafe { member.publish() };
+            }
+        }
+    }
+}
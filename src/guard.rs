@@ -0,0 +1,174 @@
+use core::mem::{ManuallyDrop, MaybeUninit};
+
+use crate::{Slot, SpinCell};
+
+/// Exclusive access to an uninitialized cell, obtained from
+/// [`SpinCell::begin_init`].
+///
+/// While the guard lives the cell is locked: other threads accessing it wait.
+/// The value is built in place through [`slot`](InitGuard::slot) and
+/// published with [`commit`](InitGuard::commit) or
+/// [`write`](InitGuard::write). Dropping the guard without committing (for
+/// example by returning early with an error) unlocks the cell and leaves it
+/// uninitialized, with its initializer restored, rather than poisoning it.
+pub struct InitGuard<'a, T, G> {
+    cell: &'a SpinCell<T, G>,
+    // The stored initializer, moved out of the slot for the duration of the
+    // transaction so the slot can hold the value under construction.
+    init_func: ManuallyDrop<G>,
+}
+
+impl<T, G> SpinCell<T, G> {
+    /// Locks an uninitialized cell for manual initialization.
+    ///
+    /// Returns `None` if the cell is already initialized. If another thread
+    /// is initializing the cell, waits for it to finish first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is poisoned.
+    pub fn begin_init(me: &SpinCell<T, G>) -> Option<InitGuard<'_, T, G>> {
+        if me.state.is_ready() || !me.state.lock() {
+            return None;
+        }
+        // Safety: we hold the lock and the cell is uninitialized, so the
+        // slot holds the initializer and nobody else is looking at it.
+        let init_func = unsafe { ManuallyDrop::take(&mut (*me.slot.get()).init_func) };
+        Some(InitGuard {
+            cell: me,
+            init_func: ManuallyDrop::new(init_func),
+        })
+    }
+
+    /// Swaps the stored initializer for `init_func` before the cell is
+    /// initialized, returning the previous one.
+    ///
+    /// Returns `init_func` back if the cell is already initialized. The
+    /// exchange happens with the lock held, so an access racing with it runs
+    /// either the old initializer or the new one, never a mix. This lets a
+    /// test inject a mock constructor into a production static:
+    ///
+    /// ```
+    /// use spincell::SpinFnCell;
+    ///
+    /// static DEVICE: SpinFnCell<&str> = SpinFnCell::new(|| "real hardware");
+    ///
+    /// assert!(SpinFnCell::replace_initializer(&DEVICE, || "mock").is_ok());
+    /// assert_eq!(*DEVICE, "mock");
+    /// assert!(SpinFnCell::replace_initializer(&DEVICE, || "too late").is_err());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is poisoned.
+    pub fn replace_initializer(me: &SpinCell<T, G>, init_func: G) -> Result<G, G> {
+        match SpinCell::begin_init(me) {
+            // Dropping the guard puts the new initializer in the slot and
+            // unlocks the cell.
+            Some(mut guard) => Ok(core::mem::replace(&mut *guard.init_func, init_func)),
+            None => Err(init_func),
+        }
+    }
+
+    /// Locks an uninitialized cell and returns a pointer to the storage for
+    /// its value, for values produced outside the CPU (for example by a DMA
+    /// engine). The stored initializer is dropped.
+    ///
+    /// Returns a null pointer if the cell is already initialized. Otherwise
+    /// the cell stays locked, and every other access to it waits, until
+    /// [`SpinCell::assume_init_commit`] publishes the value.
+    ///
+    /// # Safety
+    ///
+    /// A non-null result must eventually be followed by exactly one call to
+    /// [`SpinCell::assume_init_commit`], after the value has been fully
+    /// written through the returned pointer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is poisoned.
+    pub unsafe fn uninit_slot_ptr(me: &SpinCell<T, G>) -> *mut T {
+        match SpinCell::begin_init(me) {
+            Some(guard) => guard.into_slot_ptr(),
+            None => core::ptr::null_mut(),
+        }
+    }
+
+    /// Publishes a value written through [`SpinCell::uninit_slot_ptr`] with
+    /// release ordering, unlocking the cell.
+    ///
+    /// # Safety
+    ///
+    /// The caller must have obtained a non-null pointer from
+    /// [`SpinCell::uninit_slot_ptr`] on this cell, not yet committed, and the
+    /// value behind it must be fully initialized. Writes made by hardware
+    /// must already be visible to this CPU (after any cache maintenance the
+    /// platform requires), since the release store only orders the CPU's own
+    /// accesses.
+    pub unsafe fn assume_init_commit(me: &SpinCell<T, G>) {
+        #[cfg(feature = "testing")]
+        crate::testing::record(&me.state, crate::testing::Event::Init);
+        me.state.publish();
+    }
+}
+
+impl<'a, T, G> InitGuard<'a, T, G> {
+    /// The storage the value is to be written to.
+    pub fn slot(&mut self) -> &mut MaybeUninit<T> {
+        let value = unsafe { core::ptr::addr_of_mut!((*self.cell.slot.get()).value) };
+        unsafe { &mut *value.cast::<MaybeUninit<T>>() }
+    }
+
+    /// Writes `value` into the slot and publishes it.
+    pub fn write(mut self, value: T) -> &'a T {
+        self.slot().write(value);
+        unsafe { self.commit() }
+    }
+
+    /// Publishes the value in the slot, making it visible to all threads,
+    /// and drops the cell's original initializer.
+    ///
+    /// # Safety
+    ///
+    /// The slot must be fully initialized.
+    pub unsafe fn commit(self) -> &'a T {
+        let mut this = ManuallyDrop::new(self);
+        let cell = this.cell;
+        #[cfg(feature = "testing")]
+        crate::testing::record(&cell.state, crate::testing::Event::Init);
+        cell.state.publish();
+        // Drop the initializer only after publishing, so a panicking drop
+        // cannot leave the cell locked.
+        ManuallyDrop::drop(&mut this.init_func);
+        cell.get_unchecked()
+    }
+}
+
+impl<T, G> InitGuard<'_, T, G> {
+    // Ends the transaction without committing or unlocking: the initializer
+    // is dropped and the cell stays locked until the value is published
+    // directly on its state.
+    fn into_slot_ptr(mut self) -> *mut T {
+        let slot = self.slot().as_mut_ptr();
+        let mut this = ManuallyDrop::new(self);
+        unsafe { ManuallyDrop::drop(&mut this.init_func) };
+        slot
+    }
+}
+
+impl<T, G> Drop for InitGuard<'_, T, G> {
+    fn drop(&mut self) {
+        // Abort: whatever was written to the slot is discarded without being
+        // dropped, and the initializer goes back in its place.
+        unsafe {
+            let init_func = ManuallyDrop::take(&mut self.init_func);
+            core::ptr::write(
+                self.cell.slot.get(),
+                Slot {
+                    init_func: ManuallyDrop::new(init_func),
+                },
+            );
+        }
+        self.cell.state.unlock();
+    }
+}
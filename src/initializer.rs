@@ -0,0 +1,52 @@
+use core::ptr::NonNull;
+
+/// Something that can be consumed to build a cell's value.
+///
+/// Every `FnOnce() -> T` is an initializer. Implementing the trait for a
+/// named type, typically a unit struct, gives a cell type that can be
+/// written out in a static without a closure and without storing any bytes
+/// for the initializer:
+///
+/// ```
+/// use spincell::{Initializer, SpinCell};
+///
+/// struct Squares;
+///
+/// impl Initializer<[u32; 8]> for Squares {
+///     fn init(self) -> [u32; 8] {
+///         core::array::from_fn(|i| (i * i) as u32)
+///     }
+/// }
+///
+/// static SQUARES: SpinCell<[u32; 8], Squares> = SpinCell::new(Squares);
+/// assert_eq!(core::mem::size_of_val(&SQUARES), 1 + 8 * 4 + 3);
+/// assert_eq!(SQUARES[7], 49);
+/// ```
+pub trait Initializer<T>: Sized {
+    /// Builds the value.
+    fn init(self) -> T;
+
+    // Called by the cells instead of `init`, with the address the value is
+    // going to be stored at, for initializers that need to know it.
+    #[doc(hidden)]
+    #[inline(always)]
+    fn init_at(self, value: NonNull<T>) -> T {
+        let _ = value;
+        self.init()
+    }
+
+    // Like `init_at`, and also returns a function the cell calls with the
+    // value once it has been published.
+    #[doc(hidden)]
+    #[inline(always)]
+    fn init_observed(self, value: NonNull<T>) -> (T, Option<fn(&T)>) {
+        (self.init_at(value), None)
+    }
+}
+
+impl<T, F: FnOnce() -> T> Initializer<T> for F {
+    #[inline(always)]
+    fn init(self) -> T {
+        self()
+    }
+}
@@ -0,0 +1,75 @@
+//! Hooks at the critical points of the initialization protocol, for tests
+//! that force a particular interleaving of threads.
+//!
+//! With the `test-interleave` feature, every cell calls the hook installed
+//! with [`set_hook`] at each [`YieldPoint`] it passes, with the address of
+//! the cell's state. That is the address of the cell itself for a
+//! [`SpinCell`](crate::SpinCell), whose state is at offset 0. A hook that
+//! blocks the thread reaching a point until the test lets it go opens a
+//! window in which other threads can be run into the race under test, such
+//! as an access between taking the lock and publishing the value.
+//!
+//! ```
+//! use core::sync::atomic::{AtomicUsize, Ordering};
+//! use spincell::interleave::{set_hook, YieldPoint};
+//! use spincell::SpinCell;
+//!
+//! static PUBLISHES: AtomicUsize = AtomicUsize::new(0);
+//! static CELL: SpinCell<u32> = SpinCell::new(|| 1);
+//!
+//! set_hook(Some(|point, state| {
+//!     if point == YieldPoint::BeforePublish && state == &CELL as *const _ as usize {
+//!         PUBLISHES.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }));
+//! assert_eq!(*CELL + *CELL, 2);
+//! assert_eq!(PUBLISHES.load(Ordering::Relaxed), 1);
+//! set_hook(None);
+//! ```
+//!
+//! The hook is shared by all threads and all cells, so tests running in
+//! parallel should only act on the cells they own. It runs with the cell
+//! locked at every point; a hook that panics or accesses the same cell
+//! leaves it locked for good. Without the feature the points compile to
+//! nothing.
+
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::state::State;
+
+/// A point in the initialization protocol where cells call the hook.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum YieldPoint {
+    /// The thread has just taken the lock.
+    Locked,
+    /// The value has been written and is about to be published.
+    BeforePublish,
+    /// The lock is about to be released without publishing a value, after
+    /// an abandoned or panicking initialization or an access that leaves
+    /// the state as it was.
+    BeforeUnlock,
+}
+
+/// The signature of a hook: the point reached and the address of the
+/// cell's state.
+pub type Hook = fn(YieldPoint, usize);
+
+static HOOK: AtomicPtr<()> = AtomicPtr::new(null_mut());
+
+/// Installs `hook`, or removes the current one with `None`.
+pub fn set_hook(hook: Option<Hook>) {
+    let hook = hook.map_or(null_mut(), |hook| hook as *mut ());
+    HOOK.store(hook, Ordering::SeqCst);
+}
+
+// Calls the hook, if one is installed, for `state` reaching `point`.
+#[inline]
+pub(crate) fn test_yield(point: YieldPoint, state: &State) {
+    let hook = HOOK.load(Ordering::SeqCst);
+    if !hook.is_null() {
+        // Safety: HOOK only ever holds null or a `Hook`.
+        let hook = unsafe { core::mem::transmute::<*mut (), Hook>(hook) };
+        hook(point, state as *const State as usize);
+    }
+}
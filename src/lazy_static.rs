@@ -0,0 +1,32 @@
+/// Declares lazily initialized statics with the syntax of the lazy_static
+/// crate.
+///
+/// Every `static ref NAME: Type = expr;` becomes a
+/// `static NAME: SpinCell<Type>` declared with [`spincell!`], so code
+/// that dereferences the statics keeps working unchanged when a project
+/// swaps its `lazy_static!` for this macro. Calls to
+/// `lazy_static::initialize(&NAME)` become [`SpinCell::force`].
+///
+/// ```
+/// use spincell::{spin_lazy_static, SpinCell};
+///
+/// spin_lazy_static! {
+///     static ref PRIMES: [u32; 4] = [2, 3, 5, 7];
+///     /// The sum of [`PRIMES`].
+///     pub static ref SUM: u32 = PRIMES.iter().sum();
+/// }
+///
+/// assert_eq!(*SUM, 17);
+/// SpinCell::force(&PRIMES);
+/// ```
+///
+/// [`SpinCell::force`]: crate::SpinCell::force
+/// [`spincell!`]: crate::spincell
+#[macro_export]
+macro_rules! spin_lazy_static {
+    ($($(#[$attr:meta])* $vis:vis static ref $name:ident: $ty:ty = $init:expr;)*) => {
+        $crate::spincell! {
+            $($(#[$attr])* $vis static $name: $ty = $init;)*
+        }
+    };
+}
@@ -1,116 +1,726 @@
 #![no_std]
+#![cfg_attr(
+    feature = "nightly",
+    feature(const_trait_impl, const_convert, const_eval_select, core_intrinsics),
+    allow(internal_features)
+)]
+#![cfg_attr(all(feature = "nightly", feature = "async"), feature(cfg_sanitize))]
+#![cfg_attr(
+    all(
+        feature = "nightly",
+        target_arch = "wasm32",
+        target_feature = "atomics"
+    ),
+    feature(stdarch_wasm_atomic_wait)
+)]
+#![cfg_attr(
+    all(feature = "nightly", target_arch = "xtensa"),
+    feature(asm_experimental_arch)
+)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 use core::cell::UnsafeCell;
 use core::mem::{ManuallyDrop, MaybeUninit};
-use core::sync::atomic::{AtomicBool, Ordering};
+use core::pin::Pin;
+use core::ptr::NonNull;
+
+#[cfg(feature = "async")]
+mod async_cell;
+mod background;
+mod backoff;
+pub mod boot;
+mod bounded;
+#[cfg(feature = "alloc")]
+mod boxed;
+mod builder;
+mod bytes;
+mod cache_padded;
+mod combinators;
+pub mod compat;
+#[cfg(doctest)]
+mod compile_fail;
+mod cyclic;
+mod declare;
+mod eager;
+mod ffi;
+#[cfg(feature = "freertos")]
+pub mod freertos;
+#[cfg(feature = "global_alloc")]
+mod global_alloc;
+mod group;
+mod guard;
+mod initializer;
+#[cfg(feature = "test-interleave")]
+pub mod interleave;
+mod lazy_static;
+mod mut_cell;
+#[cfg(feature = "nightly")]
+mod nightly;
+mod observe;
+#[cfg(kani)]
+mod proofs;
+#[cfg(any(feature = "race-hunt", feature = "spurious-cas"))]
+mod race_hunt;
+mod registry;
+mod reset;
+mod result;
+#[cfg(feature = "std")]
+mod secret;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+mod shared;
+pub mod shutdown;
+mod singleton;
+mod split;
+pub mod state;
+mod std_api;
+mod strategy;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transform;
+mod ttl;
+mod two_phase;
+#[cfg(feature = "async")]
+mod wait;
+#[cfg(all(
+    feature = "nightly",
+    target_arch = "wasm32",
+    target_feature = "atomics"
+))]
+mod wasm;
+mod wipe;
+#[cfg(all(feature = "nightly", target_arch = "xtensa"))]
+mod xtensa;
+
+#[cfg(feature = "async")]
+pub use async_cell::{AsyncGet, AsyncSpinCell};
+pub use backoff::Backoff;
+pub use bounded::BoundedSpinCell;
+#[cfg(feature = "alloc")]
+pub use boxed::{BoxedSpinCell, SpinDynCell};
+pub use builder::{ConfiguredSpinCell, SpinCellBuilder, Stats};
+pub use bytes::{FromBytes, SpinBytesCell};
+pub use cache_padded::CachePadded;
+pub use combinators::{LazyMap, LazyZip};
+pub use cyclic::{Cyclic, CyclicRef};
+pub use eager::{init_all, LazyInit};
+pub use group::{GroupMember, SpinCellGroup};
+pub use guard::InitGuard;
+pub use initializer::Initializer;
+pub use mut_cell::SpinMutCell;
+pub use observe::{Observed, ObservedSpinCell};
+pub use registry::SpinRegistry;
+pub use reset::SpinResetCell;
+#[cfg(feature = "std")]
+pub use secret::SecretSpinCell;
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+pub use shared::{SharedCyclic, SharedLazy};
+pub use singleton::SpinSingleton;
+#[cfg(feature = "macros")]
+pub use spincell_macros::lazy;
+pub use split::{SplitSpinCell, UninitSlot};
+use state::{State, EAGER, LOCKED, POISONED, READY, SEALED, UNINIT};
+pub use std_api::SpinOnceCell;
+#[cfg(feature = "std")]
+pub use strategy::Yield;
+pub use strategy::{Spin, WaitStrategy};
+pub use transform::{LazyParsed, LazyTransform, Transform};
+pub use ttl::{Clock, SpinTtlCell};
+pub use two_phase::{BasicView, FullView, SpinTwoPhaseCell};
+#[cfg(feature = "async")]
+pub use wait::{GetAsync, Initialized};
+#[cfg(all(
+    feature = "nightly",
+    target_arch = "wasm32",
+    target_feature = "atomics"
+))]
+pub use wasm::AtomicWait;
+#[cfg(all(feature = "nightly", target_arch = "xtensa"))]
+pub use xtensa::WaitForInterrupt;
 
+/// A thread-safe cell that runs its initializer on first access.
+///
+/// Like those of `Arc` and `LazyLock`, the operations of the cell are
+/// associated functions, called as `SpinCell::get(&cell)`, so that they
+/// never shadow the methods of the value that `Deref` forwards to.
+///
+/// # Cost of access
+///
+/// Once the cell is initialized, every access through `Deref` or
+/// [`SpinCell::try_initialize`] performs exactly one `Acquire` load of the
+/// state byte and never writes to the cell. Reads of an initialized cell are
+/// therefore wait-free, do not contend with each other, and work even when
+/// the cell lives in memory that has since been made read-only. Only the
+/// first accesses, while the cell is still uninitialized, take the
+/// out-of-line locking path.
+///
+/// # Zero-sized values
+///
+/// The value shares its storage with the initializer, so a zero-sized `T`
+/// such as `()` costs nothing beyond the initializer itself, and dropping
+/// such a cell does no work at all. With a zero-sized initializer as well
+/// (a non-capturing closure or function item) the whole cell is a single
+/// state byte, which makes `SpinCell<(), _>` a cheap run-once flag:
+///
+/// ```
+/// use spincell::SpinCell;
+///
+/// let once = SpinCell::new(|| ());
+/// assert_eq!(core::mem::size_of_val(&once), 1);
+/// *once; // Runs the closure.
+/// *once; // Does nothing.
+/// ```
+///
+/// # Layout
+///
+/// `SpinCell` is `#[repr(C)]` and its layout is frozen, so it can be
+/// embedded in structs shared with C and inspected by debuggers:
+///
+/// - offset 0: the state byte, one of the values in [`state`];
+/// - offset [`SpinCell::VALUE_OFFSET`]: a slot sized and aligned for the
+///   larger of `T` and `G`, holding the initializer while the state is
+///   [`state::UNINIT`] and the value once it is [`state::READY`].
+///
+/// The state byte must only be accessed atomically, and the slot must only
+/// be read after observing [`state::READY`] with acquire ordering.
+///
+/// The bytes a cell costs on top of its value are [`SpinCell::OVERHEAD`].
+/// With the default `fn() -> T` initializer and a value at least as large as
+/// a pointer and aligned at most like one, that is the alignment of a
+/// pointer: 8 bytes on 64-bit targets, 4 on 32-bit and 2 on 16-bit ones.
+/// These figures are checked at compile time, so a change that grows the
+/// cell does not build.
+#[repr(C)]
 pub struct SpinCell<T, G = fn() -> T> {
-    // A simple spin lock for serialized initialization.
-    lock: AtomicBool,
-    // Whether the cell currently holds an initialized value.
-    // Readers should load this with Acquire to observe initialized data.
-    is_initialized: AtomicBool,
-    cell: MaybeUninit<UnsafeCell<T>>,
+    // Initialization state, one of UNINIT, LOCKED, READY, POISONED, EAGER
+    // or SEALED. Readers should load this with Acquire to observe initialized data.
+    state: State,
+    // Holds the initializer while UNINIT and the value once READY. Wrapped
+    // in UnsafeCell so the initializer can be swapped for the value from
+    // &self during initialization.
+    slot: UnsafeCell<Slot<T, G>>,
+}
+
+/// A [`SpinCell`] whose initializer is a plain function pointer.
+///
+/// This covers the common "call this function once" case without a closure
+/// type parameter, so the type can be written out in statics and tables.
+///
+/// ```
+/// use spincell::SpinFnCell;
+///
+/// fn load() -> u32 {
+///     7
+/// }
+///
+/// static VALUE: SpinFnCell<u32> = SpinFnCell::new(load);
+/// assert_eq!(*VALUE, 7);
+/// ```
+pub type SpinFnCell<T> = SpinCell<T, fn() -> T>;
+
+// The initializer and the value are never live at the same time, so they
+// share storage. Which field is valid is determined by the cell's state.
+#[repr(C)]
+union Slot<T, G> {
     // Stored initializer function (consumed exactly once by the first
-    // thread that successfully initializes). Wrapped in UnsafeCell so it
-    // can be taken from &self during initialization.
-    init_func: UnsafeCell<ManuallyDrop<G>>,
+    // thread that successfully initializes).
+    init_func: ManuallyDrop<G>,
+    value: ManuallyDrop<T>,
 }
 
-unsafe impl<T: Sync, G> Sync for SpinCell<T, G> {}
-unsafe impl<T: Send, G> Send for SpinCell<T, G> {}
+// The layout figures documented on `SpinCell`.
+const _: () = {
+    let word = align_of::<fn()>();
+    assert!(SpinCell::<usize>::OVERHEAD == word);
+    assert!(SpinCell::<[usize; 4]>::OVERHEAD == word);
+    assert!(align_of::<SpinCell<usize>>() == word);
+    assert!(SpinCell::<u8, ()>::OVERHEAD == 1);
+    assert!(size_of::<SpinCell<(), ()>>() == 1);
+    assert!(SpinCell::<u32, ()>::VALUE_OFFSET == 4);
+};
 
-impl<T, G: FnOnce() -> T> SpinCell<T, G> {
+// Any thread may run the initializer and drop the value, so sharing the
+// cell also sends the initializer and the value.
+unsafe impl<T: Send + Sync, G: Send> Sync for SpinCell<T, G> {}
+unsafe impl<T: Send, G: Send> Send for SpinCell<T, G> {}
+
+impl<T, G: Initializer<T>> SpinCell<T, G> {
     #[inline(always)]
     pub const fn new(init_func: G) -> SpinCell<T, G> {
-        Self {
-            lock: AtomicBool::new(false),
-            is_initialized: AtomicBool::new(false),
-            cell: MaybeUninit::uninit(),
-            init_func: UnsafeCell::new(ManuallyDrop::new(init_func)),
-        }
+        Self::with_init(init_func)
     }
 
+    /// Runs the initializer unless the cell is already initialized.
+    ///
+    /// # Safety
+    ///
+    /// Calling this is always memory safe; it stays `unsafe` for
+    /// compatibility with earlier releases.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initializer panicked during an earlier attempt.
+    #[deprecated(note = "use `SpinCell::force`, which is safe")]
     pub unsafe fn force_initialize(&self) {
-        // Acquire the lock exclusively. Use Acquire on success so that the
-        // subsequent reads/writes are properly ordered, and Relaxed on
-        // failure to avoid unnecessary barriers.
-        while self
-            .lock
-            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
-            .is_err()
-        {
-            core::hint::spin_loop();
+        SpinCell::force(self);
+    }
+
+    #[allow(clippy::result_unit_err)]
+    pub fn try_initialize(me: &SpinCell<T, G>) -> Result<(), ()> {
+        // Fast path: if already initialized, return Err.
+        if me.state.is_ready() {
+            return Err(());
         }
 
-        // If another thread initialized while we were spinning, just release
-        // the lock and return.
-        if self.is_initialized.load(Ordering::Acquire) {
-            self.lock.store(false, Ordering::Release);
-            return;
+        // Not initialized.
+        // `initialize_slow` acquires the internal lock and re-checks the
+        // state to ensure only one thread runs the initializer.
+        me.initialize_slow();
+        Ok(())
+    }
+
+    /// Initializes the cell if needed and returns a raw pointer to the value.
+    ///
+    /// The pointer is derived from the cell's interior storage rather than
+    /// from a `&T`, so its provenance covers the whole value and is not tied
+    /// to the lifetime of a shared borrow. It stays valid for as long as the
+    /// cell is neither moved nor dropped.
+    ///
+    /// The value is shared: other threads may hold `&T` to it at any time.
+    /// Reading through the pointer is always allowed, but writing through it
+    /// is only allowed to memory inside an `UnsafeCell` (or atomics) within
+    /// `T`, exactly as if the write went through a `&T`.
+    #[inline]
+    pub fn deref_ptr(me: &SpinCell<T, G>) -> NonNull<T> {
+        if !me.state.is_ready() {
+            me.initialize_slow();
+        }
+        me.value_ptr()
+    }
+
+    /// Initializes the cell if needed and returns a pinned reference to the
+    /// value.
+    ///
+    /// Pinning is structural: once a `SpinCell` is pinned, its value is
+    /// never moved until it is dropped in place with the cell. The value is
+    /// built before it is pinned, so the initializer may move it freely, but
+    /// no method that takes `&self` moves it afterwards, and nothing hands
+    /// out `&mut T`. A `static` is pinned from the start, so it can hold
+    /// address-sensitive data such as the head of an intrusive list:
+    ///
+    /// ```
+    /// use core::marker::PhantomPinned;
+    /// use core::pin::Pin;
+    /// use spincell::SpinCell;
+    ///
+    /// struct Executor {
+    ///     ready: u32,
+    ///     _pinned: PhantomPinned,
+    /// }
+    ///
+    /// static EXECUTOR: SpinCell<Executor> = SpinCell::new(|| Executor {
+    ///     ready: 0,
+    ///     _pinned: PhantomPinned,
+    /// });
+    ///
+    /// let executor: Pin<&Executor> = SpinCell::force_pinned(Pin::static_ref(&EXECUTOR));
+    /// assert_eq!(executor.ready, 0);
+    /// ```
+    #[inline]
+    pub fn force_pinned(me: Pin<&SpinCell<T, G>>) -> Pin<&T> {
+        let value: &T = me.get_ref();
+        // Safety: see above; the value stays where it is for as long as the
+        // pinned cell does.
+        unsafe { Pin::new_unchecked(value) }
+    }
+
+    /// Initializes the cell if needed and returns a pinned reference to the
+    /// value. The same as [`SpinCell::force_pinned`].
+    #[deprecated(note = "use `SpinCell::force_pinned`")]
+    #[inline]
+    pub fn deref_pinned(self: Pin<&Self>) -> Pin<&T> {
+        SpinCell::force_pinned(self)
+    }
+
+    // Everything past the READY check lives here so that the inlined access
+    // path stays a single load and branch.
+    #[cold]
+    #[inline(never)]
+    fn initialize_slow(&self) {
+        let value = self.value_ptr();
+        let mut observer = None;
+        self.initialize_by(|init_func| {
+            let (value, f) = init_func.init_observed(value);
+            observer = f;
+            value
+        });
+        // Only the thread that ran the initializer gets an observer back.
+        if let Some(observer) = observer {
+            // Safety: the value has been published.
+            observer(unsafe { value.as_ref() });
         }
+    }
+}
 
-        // Take the initializer and run it.
-        let data = &mut *self.init_func.get();
-        let init_func = ManuallyDrop::take(data);
-        let value = init_func();
+impl<T, G> SpinCell<T, G> {
+    /// Byte offset of the value (or initializer) slot within the cell.
+    pub const VALUE_OFFSET: usize = core::mem::offset_of!(SpinCell<T, G>, slot);
 
-        let ptr = self.cell.as_ptr() as *mut UnsafeCell<T>;
-        core::ptr::write(ptr, UnsafeCell::new(value));
+    /// Bytes the cell takes in addition to the value: the state byte, the
+    /// padding after it, and any room the initializer needs beyond the
+    /// value's.
+    pub const OVERHEAD: usize = size_of::<SpinCell<T, G>>() - size_of::<T>();
 
-        // Publish the initialized value. Use Release so readers that do an
-        // Acquire load on `is_initialized` see the written data.
-        self.is_initialized.store(true, Ordering::Release);
+    /// Returns whether the value has been initialized, without initializing
+    /// it.
+    #[inline]
+    pub fn is_initialized(me: &SpinCell<T, G>) -> bool {
+        me.state.is_ready()
+    }
 
-        // Release the lock.
-        self.lock.store(false, Ordering::Release);
+    /// Checks the invariants of the cell that hold whatever other threads
+    /// are doing, and describes the first one that does not.
+    ///
+    /// The state byte must hold one of the values in [`state`], and the
+    /// cell can only be poisoned if initializers can unwind. The check only
+    /// reads the byte, so it never waits. A violation means something wrote
+    /// over the cell, such as a buffer overrun in a neighbouring static or
+    /// a stray DMA transfer.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// static CONFIG: SpinCell<u32> = SpinCell::new(|| 1);
+    ///
+    /// assert_eq!(SpinCell::debug_validate(&CONFIG), Ok(()));
+    /// ```
+    pub fn debug_validate(me: &SpinCell<T, G>) -> Result<(), &'static str> {
+        me.state
+            .validate(&[UNINIT, LOCKED, READY, POISONED, EAGER, SEALED])
     }
 
-    pub fn try_initialize(me: &SpinCell<T, G>) -> Result<(), ()> {
-        // Lock SpinCell.
-        // Fast path: if already initialized, return Err.
-        if me.is_initialized.load(Ordering::Acquire) {
-            return Err(());
+    /// Returns the value if the cell is initialized, without initializing
+    /// it or waiting for an initialization in progress.
+    #[inline]
+    pub fn get(me: &SpinCell<T, G>) -> Option<&T> {
+        // Safety: the cell is READY.
+        me.state.is_ready().then(|| unsafe { me.get_unchecked() })
+    }
+
+    /// Creates a cell that is already initialized with `value`.
+    ///
+    /// This is a `const fn`, so a value computable at compile time can be
+    /// placed in a static directly. Accesses still check the state byte but
+    /// never take the lock, and no initializer is stored.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// static LIMIT: SpinCell<u32> = SpinCell::from_value(64);
+    /// assert!(SpinCell::is_initialized(&LIMIT));
+    /// assert_eq!(*LIMIT, 64);
+    /// ```
+    #[inline(always)]
+    pub const fn from_value(value: T) -> SpinCell<T, G> {
+        Self {
+            state: State::new(READY),
+            slot: UnsafeCell::new(Slot {
+                value: ManuallyDrop::new(value),
+            }),
         }
+    }
 
-        // Not initialized.
-        // `force_initialize` acquires the internal lock and re-checks the
-        // initialized flag to ensure only one thread runs the initializer.
+    /// Takes the initializer back out of a cell that was never initialized.
+    ///
+    /// Returns the cell unchanged if it holds no initializer, because it
+    /// was initialized or an initialization attempt panicked.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// let buffer = vec![0u8; 16];
+    /// let cell = SpinCell::new(move || buffer.len());
+    /// let init_func = SpinCell::into_initializer(cell).ok().unwrap();
+    /// assert_eq!(init_func(), 16);
+    /// ```
+    pub fn into_initializer(me: SpinCell<T, G>) -> Result<G, SpinCell<T, G>> {
+        let mut me = ManuallyDrop::new(me);
+        match *me.state.get_mut() {
+            // Safety: the slot holds the initializer, and the cell is never
+            // dropped, so it is moved out exactly once.
+            UNINIT | EAGER => {
+                #[cfg(feature = "testing")]
+                testing::release(&me.state);
+                Ok(unsafe { ManuallyDrop::take(&mut me.slot.get_mut().init_func) })
+            }
+            _ => Err(ManuallyDrop::into_inner(me)),
+        }
+    }
+
+    // Moves the value out of an initialized cell. Returns the cell unchanged
+    // if it holds no value.
+    fn into_value(me: SpinCell<T, G>) -> Result<T, SpinCell<T, G>> {
+        let mut me = ManuallyDrop::new(me);
+        match *me.state.get_mut() {
+            // Safety: the slot holds the value, and the cell is never
+            // dropped, so it is moved out exactly once.
+            READY => {
+                #[cfg(feature = "testing")]
+                testing::release(&me.state);
+                Ok(unsafe { ManuallyDrop::take(&mut me.slot.get_mut().value) })
+            }
+            _ => Err(ManuallyDrop::into_inner(me)),
+        }
+    }
+
+    // Like `new`, for wrappers whose stored initializer does not produce the
+    // value directly.
+    #[inline(always)]
+    const fn with_init(init_func: G) -> SpinCell<T, G> {
+        Self {
+            state: State::new(UNINIT),
+            slot: UnsafeCell::new(Slot {
+                init_func: ManuallyDrop::new(init_func),
+            }),
+        }
+    }
+
+    // Initializes the cell unless it is already initialized, building the
+    // value by handing the stored initializer to `f`.
+    fn initialize_by(&self, f: impl FnOnce(G) -> T) {
+        let mut f = Some(f);
+        self.state.initialize(&mut || self.build_value(&mut f));
+    }
+
+    // Like `initialize_by`, for a caller that already holds the lock on the
+    // uninitialized cell.
+    fn initialize_locked_by(&self, f: impl FnOnce(G) -> T) {
+        let mut f = Some(f);
+        self.state
+            .initialize_locked(&mut || self.build_value(&mut f));
+    }
+
+    // Replaces the initializer in the slot with the value `f` builds from
+    // it. The state machine runs this at most once, with the lock held, so
+    // the initializer is still in the slot and nobody else is looking at it.
+    fn build_value(&self, f: &mut Option<impl FnOnce(G) -> T>) {
+        let Some(f) = f.take() else { return };
+        let slot = self.slot.get();
         unsafe {
-            me.force_initialize();
+            let init_func = ManuallyDrop::take(&mut (*slot).init_func);
+            #[cfg(feature = "testing")]
+            testing::record(&self.state, testing::Event::Init);
+            let value = f(init_func);
+
+            // The initializer has been moved out, so its storage can be
+            // reused for the value.
+            core::ptr::write(
+                slot,
+                Slot {
+                    value: ManuallyDrop::new(value),
+                },
+            );
         }
-        Ok(())
+    }
+
+    /// Initializes the cell with `value`, dropping the stored initializer
+    /// without calling it.
+    ///
+    /// Returns `value` back if the cell was already initialized. If another
+    /// thread is initializing the cell, waits for it to finish first.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// static BOOT_ARGS: SpinCell<&str> = SpinCell::new(|| "defaults");
+    /// assert_eq!(SpinCell::initialize_with(&BOOT_ARGS, "from firmware"), Ok(()));
+    /// assert_eq!(SpinCell::initialize_with(&BOOT_ARGS, "late"), Err("late"));
+    /// assert_eq!(*BOOT_ARGS, "from firmware");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is poisoned.
+    pub fn initialize_with(me: &SpinCell<T, G>, value: T) -> Result<(), T> {
+        match SpinCell::begin_init(me) {
+            Some(guard) => {
+                guard.write(value);
+                Ok(())
+            }
+            None => Err(value),
+        }
+    }
+
+    /// Initializes the cell by writing the value directly into its storage.
+    ///
+    /// If the cell is not initialized yet, the stored initializer is dropped
+    /// without being called and `f` fills in the value instead, avoiding the
+    /// copy through the stack that returning a large `T` implies. Returns
+    /// `Err` without calling `f` if the cell was already initialized.
+    ///
+    /// # Safety
+    ///
+    /// `f` must fully initialize the slot it is given before returning. If
+    /// `f` panics instead, the cell is poisoned and the slot is never read.
+    #[allow(clippy::result_unit_err)]
+    pub unsafe fn init_in_place(
+        me: &SpinCell<T, G>,
+        f: impl FnOnce(&mut MaybeUninit<T>),
+    ) -> Result<(), ()> {
+        if me.state.is_ready() {
+            return Err(());
+        }
+        let mut f = Some(f);
+        me.state.initialize(&mut || {
+            let Some(f) = f.take() else { return };
+            let slot = me.slot.get();
+            // Move the initializer out before its storage is overwritten; it
+            // is dropped once the value is in place.
+            let _init_func = ManuallyDrop::take(&mut (*slot).init_func);
+            #[cfg(feature = "testing")]
+            testing::record(&me.state, testing::Event::Init);
+            let value = core::ptr::addr_of_mut!((*slot).value).cast::<MaybeUninit<T>>();
+            f(&mut *value);
+        });
+        // The closure only consumes `f` if this call did the initialization.
+        match f {
+            None => Ok(()),
+            Some(_) => Err(()),
+        }
+    }
+
+    // Where the value is, or will be, stored.
+    #[inline(always)]
+    fn value_ptr(&self) -> NonNull<T> {
+        let value = unsafe { core::ptr::addr_of_mut!((*self.slot.get()).value) };
+        // Safety: pointers into a live cell are never null.
+        unsafe { NonNull::new_unchecked(value.cast::<T>()) }
+    }
+
+    // Safety: the cell must be READY.
+    #[inline(always)]
+    const unsafe fn get_unchecked(&self) -> &T {
+        &*core::ptr::addr_of!((*self.slot.get()).value).cast::<T>()
     }
 }
 
-impl<T, G: FnOnce() -> T> core::ops::Deref for SpinCell<T, G> {
+// With the `nightly` feature this impl is `const`; see nightly.rs.
+#[cfg(not(feature = "nightly"))]
+impl<T, G: Initializer<T>> core::ops::Deref for SpinCell<T, G> {
     type Target = T;
+    #[inline]
     fn deref(&self) -> &T {
-        match SpinCell::try_initialize(self) {
-            Ok(()) => {}  // Called force_initialize().
-            Err(()) => {} // Cell has already been initilized.
+        if !self.state.is_ready() {
+            self.initialize_slow();
         }
-        unsafe { &*self.cell.assume_init_ref().get() }
+        unsafe { self.get_unchecked() }
+    }
+}
+
+/// Clones the value into an initialized cell if the cell is initialized, and
+/// clones the initializer into an uninitialized cell otherwise. If another
+/// thread is initializing the cell, waits for it to finish first.
+///
+/// # Panics
+///
+/// Panics if the cell is poisoned.
+impl<T: Clone, G: Clone> Clone for SpinCell<T, G> {
+    fn clone(&self) -> SpinCell<T, G> {
+        if let Some(value) = SpinCell::get(self) {
+            return SpinCell::from_value(value.clone());
+        }
+        // Hold the lock while cloning so the initializer is not consumed
+        // meanwhile.
+        let state = self.state.lock_any();
+        let restore = Restore(&self.state, state);
+        let slot = unsafe { &*self.slot.get() };
+        let clone = match state {
+            // Safety: the slot holds the value.
+            READY => SpinCell::from_value(T::clone(unsafe { &slot.value })),
+            // Safety: the slot holds the initializer.
+            _ => SpinCell {
+                state: State::new(state),
+                slot: UnsafeCell::new(Slot {
+                    init_func: ManuallyDrop::new(G::clone(unsafe { &slot.init_func })),
+                }),
+            },
+        };
+        drop(restore);
+        clone
+    }
+}
+
+// Releases a lock taken with `lock_any`, also if cloning panics.
+struct Restore<'a>(&'a State, u8);
+
+impl Drop for Restore<'_> {
+    fn drop(&mut self) {
+        self.0.restore(self.1);
+    }
+}
+
+/// Initializes the cell if needed and iterates over the value, so a lazily
+/// built collection can be used directly in a `for` loop.
+///
+/// ```
+/// use spincell::SpinCell;
+///
+/// static PRIMES: SpinCell<[u32; 4]> = SpinCell::new(|| [2, 3, 5, 7]);
+///
+/// let mut sum = 0;
+/// for p in &PRIMES {
+///     sum += p;
+/// }
+/// assert_eq!(sum, 17);
+/// ```
+impl<'a, T, G: Initializer<T>> IntoIterator for &'a SpinCell<T, G>
+where
+    &'a T: IntoIterator,
+{
+    type Item = <&'a T as IntoIterator>::Item;
+    type IntoIter = <&'a T as IntoIterator>::IntoIter;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        SpinCell::force(self).into_iter()
     }
 }
 
 impl<T, G> Drop for SpinCell<T, G> {
     fn drop(&mut self) {
-        // If the cell was initialized, drop the inner T in-place.
-        if self.is_initialized.load(Ordering::Acquire) {
-            let cell_ptr = self.cell.as_mut_ptr() as *mut UnsafeCell<T>;
-            unsafe {
+        // Nothing to drop for trivially destructible values and initializers,
+        // so skip the state check entirely. This is resolved at compile time.
+        if !core::mem::needs_drop::<T>()
+            && !core::mem::needs_drop::<G>()
+            && !cfg!(feature = "zeroize")
+            && !cfg!(feature = "testing")
+        {
+            return;
+        }
+        match *self.state.get_mut() {
+            // The cell was initialized: drop the inner T in-place.
+            READY => unsafe {
                 // Safety: we have &mut self so there are no other references
                 // to the contained T; drop it in-place.
-                core::ptr::drop_in_place((*cell_ptr).get());
-            }
-        } else {
+                ManuallyDrop::drop(&mut self.slot.get_mut().value);
+                wipe::wipe(self.value_ptr().as_ptr());
+                #[cfg(feature = "testing")]
+                testing::record(&self.state, testing::Event::Drop);
+            },
             // The cell was not initialized: the initializer is still
             // present and must be dropped. We have exclusive access via
             // &mut self, so it's safe to drop the ManuallyDrop<G>.
-            unsafe {
-                ManuallyDrop::drop(&mut *self.init_func.get());
-            }
+            UNINIT | EAGER => unsafe {
+                ManuallyDrop::drop(&mut self.slot.get_mut().init_func);
+            },
+            // The initializer was consumed by a panicking attempt, so
+            // neither a value nor an initializer is left to drop.
+            _ => {}
         }
+        #[cfg(feature = "testing")]
+        testing::release(&self.state);
     }
 }
@@ -0,0 +1,220 @@
+use core::cell::UnsafeCell;
+use core::mem::ManuallyDrop;
+
+use crate::state::{State, LOCKED, POISONED, READY, UNINIT};
+use crate::{Initializer, Slot, SpinCell};
+
+/// A lazily initialized value that can be mutated after initialization.
+///
+/// The spin lock that guards initialization is reused afterwards to hand out
+/// exclusive access, which makes this a lightweight mutex for rarely changed
+/// data such as configuration. Unlike [`SpinCell`] it does not implement
+/// `Deref`: every access, reads included, goes through the lock.
+///
+/// ```
+/// use spincell::SpinMutCell;
+///
+/// static LOG_LEVEL: SpinMutCell<u8> = SpinMutCell::new(|| 2);
+/// LOG_LEVEL.mutate(|level| *level += 1);
+/// assert_eq!(LOG_LEVEL.read(|level| *level), 3);
+/// ```
+///
+/// # Generations
+///
+/// The cell counts how often its value has changed. The first value is
+/// generation 1, and every [`mutate`](SpinMutCell::mutate),
+/// [`swap`](SpinMutCell::swap) or successful conditional update adds one,
+/// so readers can tell whether the value changed since they last looked.
+pub struct SpinMutCell<T, G = fn() -> T> {
+    pub(crate) inner: SpinCell<T, G>,
+    // Generation of the current value. Only accessed with the lock held on
+    // an initialized cell.
+    generation: UnsafeCell<u64>,
+}
+
+// Like a mutex: the value is handed to whichever thread holds the lock, so
+// it only needs to be Send, and so does the initializer.
+unsafe impl<T: Send, G: Send> Sync for SpinMutCell<T, G> {}
+
+// Releases the lock when the access ends, including by unwinding. A panic
+// can only leave behind a value that was reachable through `&mut T`, which
+// is still a valid `T`, so the cell is not poisoned.
+struct Unlock<'a>(&'a State);
+
+impl Drop for Unlock<'_> {
+    fn drop(&mut self) {
+        self.0.publish();
+    }
+}
+
+impl<T, G: Initializer<T>> SpinMutCell<T, G> {
+    #[inline(always)]
+    pub const fn new(init_func: G) -> SpinMutCell<T, G> {
+        Self {
+            inner: SpinCell::new(init_func),
+            generation: UnsafeCell::new(1),
+        }
+    }
+
+    /// Initializes the cell if needed, then runs `f` with exclusive access
+    /// to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initializer panicked during an earlier attempt.
+    /// Calling back into the same cell from `f` deadlocks.
+    pub fn mutate<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.with_lock(|value, generation| {
+            *generation += 1;
+            f(value)
+        })
+    }
+
+    /// Initializes the cell if needed, then runs `f` with the value while
+    /// holding the lock.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initializer panicked during an earlier attempt.
+    /// Calling back into the same cell from `f` deadlocks.
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        self.with_lock(|value, _| f(value))
+    }
+
+    /// Like [`read`](SpinMutCell::read), but also passes the generation of
+    /// the value.
+    ///
+    /// ```
+    /// use spincell::SpinMutCell;
+    ///
+    /// static ROUTES: SpinMutCell<[u8; 4]> = SpinMutCell::new(|| [0; 4]);
+    /// let seen = ROUTES.read_with_gen(|_, generation| generation);
+    /// ROUTES.swap([1; 4]);
+    /// assert!(ROUTES.generation() > seen);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initializer panicked during an earlier attempt.
+    /// Calling back into the same cell from `f` deadlocks.
+    pub fn read_with_gen<R>(&self, f: impl FnOnce(&T, u64) -> R) -> R {
+        self.with_lock(|value, generation| f(value, *generation))
+    }
+
+    /// Returns the generation of the current value, or 0 if the cell is not
+    /// initialized yet. Does not initialize the cell.
+    pub fn generation(&self) -> u64 {
+        if !self.inner.state.is_ready() {
+            return 0;
+        }
+        self.inner.state.lock_ready();
+        let _unlock = Unlock(&self.inner.state);
+        // Safety: we hold the lock on an initialized cell.
+        unsafe { *self.generation.get() }
+    }
+
+    /// Checks the invariants of the cell that hold whatever other threads
+    /// are doing, as [`SpinCell::debug_validate`] does. A mutable cell is
+    /// never eager.
+    pub fn debug_validate(&self) -> Result<(), &'static str> {
+        self.inner
+            .state
+            .validate(&[UNINIT, LOCKED, READY, POISONED])
+    }
+
+    /// Initializes the cell if needed, then stores `value` and returns the
+    /// previous value.
+    ///
+    /// ```
+    /// use spincell::SpinMutCell;
+    ///
+    /// static HANDLE: SpinMutCell<u32> = SpinMutCell::new(|| 1);
+    /// assert_eq!(HANDLE.swap(2), 1);
+    /// assert_eq!(HANDLE.read(|handle| *handle), 2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initializer panicked during an earlier attempt.
+    pub fn swap(&self, value: T) -> T {
+        self.mutate(|current| core::mem::replace(current, value))
+    }
+
+    /// Stores `value`, returning the previous value, or `None` if the cell
+    /// was not initialized yet. In that case the initializer is dropped
+    /// without being called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initializer panicked during an earlier attempt.
+    pub fn replace(&self, value: T) -> Option<T> {
+        let state = &self.inner.state;
+        if state.is_ready() || !state.lock() {
+            return Some(self.swap(value));
+        }
+        let slot = self.inner.slot.get();
+        // Safety: we hold the lock on an uninitialized cell, so the slot
+        // holds the initializer and nobody else is looking at it.
+        let init_func = unsafe { ManuallyDrop::take(&mut (*slot).init_func) };
+        #[cfg(feature = "testing")]
+        crate::testing::record(state, crate::testing::Event::Init);
+        unsafe {
+            slot.write(Slot {
+                value: ManuallyDrop::new(value),
+            });
+        }
+        state.publish();
+        drop(init_func);
+        None
+    }
+
+    /// Initializes the cell if needed, then stores `new` if `predicate`
+    /// accepts the current value, all under the lock.
+    ///
+    /// Returns the previous value if it was replaced, and hands `new` back
+    /// otherwise. This advances a state machine only from the state the
+    /// caller expects, even when several threads race to advance it:
+    ///
+    /// ```
+    /// use spincell::SpinMutCell;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Link {
+    ///     Down,
+    ///     Up,
+    /// }
+    ///
+    /// static LINK: SpinMutCell<Link> = SpinMutCell::new(|| Link::Down);
+    /// assert_eq!(LINK.compare_swap(|link| *link == Link::Down, Link::Up), Ok(Link::Down));
+    /// assert_eq!(LINK.compare_swap(|link| *link == Link::Down, Link::Up), Err(Link::Up));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initializer panicked during an earlier attempt.
+    pub fn compare_swap(&self, predicate: impl FnOnce(&T) -> bool, new: T) -> Result<T, T> {
+        self.with_lock(|current, generation| {
+            if predicate(current) {
+                *generation += 1;
+                Ok(core::mem::replace(current, new))
+            } else {
+                Err(new)
+            }
+        })
+    }
+
+    // Initializes the cell if needed and runs `f` with the value and its
+    // generation, holding the lock.
+    fn with_lock<R>(&self, f: impl FnOnce(&mut T, &mut u64) -> R) -> R {
+        if !self.inner.state.is_ready() {
+            self.inner.initialize_slow();
+        }
+        self.inner.state.lock_ready();
+        let _unlock = Unlock(&self.inner.state);
+        // Safety: we hold the lock on an initialized cell.
+        unsafe {
+            let value = core::ptr::addr_of_mut!((*self.inner.slot.get()).value).cast::<T>();
+            f(&mut *value, &mut *self.generation.get())
+        }
+    }
+}
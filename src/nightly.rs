@@ -0,0 +1,124 @@
+// APIs that need unstable compiler features. This module is only compiled
+// (and parsed) with the `nightly` feature.
+
+use core::ops::Deref;
+
+use crate::{Initializer, SpinCell};
+
+impl<T, G> SpinCell<T, G> {
+    /// Builds an already-initialized cell by calling `init` during constant
+    /// evaluation.
+    ///
+    /// Requires the `nightly` feature, since calling a closure or function
+    /// in a `const fn` needs `const_trait_impl`.
+    ///
+    /// ```
+    /// # #![feature(const_trait_impl)]
+    /// use spincell::SpinCell;
+    ///
+    /// const fn table() -> [u8; 4] {
+    ///     [1, 2, 4, 8]
+    /// }
+    ///
+    /// static TABLE: SpinCell<[u8; 4]> = SpinCell::new_const(table);
+    /// assert_eq!(TABLE[3], 8);
+    /// ```
+    #[inline(always)]
+    pub const fn new_const<F: [const] FnOnce() -> T>(init: F) -> SpinCell<T, G> {
+        Self::from_value(init())
+    }
+}
+
+/// With the `nightly` feature, cells can be read in `const` contexts as long
+/// as they were built already initialized, through
+/// [`SpinCell::from_value`] or [`SpinCell::new_const`]. Dereferencing an
+/// uninitialized cell during constant evaluation is a compile error, since
+/// initializers cannot run there. At run time this is the usual `Deref`.
+///
+/// Cells do not have a const destructor, so a cell used by value in a const
+/// context must not be dropped there; wrapping the const item in
+/// [`ManuallyDrop`](core::mem::ManuallyDrop) takes care of that.
+///
+/// ```
+/// # #![feature(const_trait_impl, const_convert)]
+/// use core::mem::ManuallyDrop;
+/// use spincell::SpinCell;
+///
+/// const WIDTH: ManuallyDrop<SpinCell<usize>> = ManuallyDrop::new(SpinCell::from_value(4));
+/// const BYTES: [u8; **WIDTH] = [0; **WIDTH];
+/// assert_eq!(BYTES.len(), 4);
+/// ```
+impl<T, G: Initializer<T>> const Deref for SpinCell<T, G> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        if !self.state.is_ready_const() {
+            initialize(self);
+        }
+        unsafe { self.get_unchecked() }
+    }
+}
+
+#[inline(always)]
+const fn initialize<T, G: Initializer<T>>(cell: &SpinCell<T, G>) {
+    const fn compile_time<T, G>(_: &SpinCell<T, G>) {
+        panic!("SpinCell must be initialized to be read in a const context")
+    }
+    fn run_time<T, G: Initializer<T>>(cell: &SpinCell<T, G>) {
+        cell.initialize_slow();
+    }
+    core::intrinsics::const_eval_select((cell,), compile_time, run_time)
+}
+
+/// Declares `SpinCell` statics whose initializers are closures, named
+/// through `type_alias_impl_trait`.
+///
+/// Each `static NAME: SpinCell<Type, Init> = expr;` becomes the static
+/// together with an opaque type alias `Init` for a closure evaluating
+/// `expr`. Unlike the function pointer of [`spincell!`](crate::spincell),
+/// the closure captures nothing, so it takes no space in the cell and the
+/// first access calls it directly rather than through a pointer.
+///
+/// Requires the `nightly` feature and `#![feature(type_alias_impl_trait)]`
+/// in the crate using the macro. The statics must be declared at module
+/// level, outside function bodies, where the compiler can see what defines
+/// each alias.
+///
+/// ```
+/// #![feature(type_alias_impl_trait)]
+/// use spincell::SpinCell;
+///
+/// fn load_retries() -> u32 {
+///     3
+/// }
+///
+/// spincell::spincell_tait! {
+///     static RETRIES: SpinCell<u32, RetriesInit> = load_retries();
+///     pub static RETRY_LIMIT: SpinCell<u32, LimitInit> = *RETRIES * 2;
+/// }
+///
+/// fn main() {
+///     assert_eq!(*RETRY_LIMIT, 6);
+///     assert_eq!(core::mem::size_of::<RetriesInit>(), 0);
+///     assert_eq!(core::mem::size_of_val(&RETRIES), 8);
+/// }
+/// ```
+#[macro_export]
+macro_rules! spincell_tait {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis static $name:ident: SpinCell<$ty:ty, $init_ty:ident> = $init:expr;
+    )*) => {
+        $(
+            $vis type $init_ty = impl ::core::ops::FnOnce() -> $ty;
+            $(#[$attr])*
+            $vis static $name: $crate::SpinCell<$ty, $init_ty> = {
+                #[define_opaque($init_ty)]
+                const fn init() -> $init_ty {
+                    || $init
+                }
+                $crate::SpinCell::new(init())
+            };
+        )*
+    };
+}
@@ -0,0 +1,77 @@
+use core::ptr::NonNull;
+
+use crate::{Initializer, SpinCell};
+
+/// An initializer paired with a function that is called with the value right
+/// after it has been published.
+///
+/// The observer runs exactly once, on the thread that ran the initializer,
+/// after the lock has been released. Other threads may already be reading
+/// the value by then, and the observer itself may access the cell. If the
+/// observer panics, the cell stays initialized.
+///
+/// Only [`SpinCell`] and `SecretSpinCell`, which is built on it, run the
+/// observer. Other cell types accept an `Observed` initializer but ignore
+/// the observer, and so does initializing a `SpinCell` as part of a
+/// [`SpinCellGroup`](crate::SpinCellGroup).
+///
+/// ```
+/// use core::sync::atomic::{AtomicUsize, Ordering};
+/// use spincell::ObservedSpinCell;
+///
+/// static LOADED: AtomicUsize = AtomicUsize::new(0);
+///
+/// fn record(table: &[u8; 4]) {
+///     LOADED.fetch_add(table.len(), Ordering::Relaxed);
+/// }
+///
+/// static TABLE: ObservedSpinCell<[u8; 4]> =
+///     ObservedSpinCell::new_observed(|| [1, 2, 3, 4], record);
+///
+/// assert_eq!(TABLE[0], 1);
+/// assert_eq!(TABLE[3], 4);
+/// assert_eq!(LOADED.load(Ordering::Relaxed), 4);
+/// ```
+pub struct Observed<T, G> {
+    init_func: G,
+    observer: fn(&T),
+}
+
+/// A [`SpinCell`] whose initializer is [`Observed`].
+pub type ObservedSpinCell<T, G = fn() -> T> = SpinCell<T, Observed<T, G>>;
+
+impl<T, G> Observed<T, G> {
+    #[inline(always)]
+    pub const fn new(init_func: G, observer: fn(&T)) -> Observed<T, G> {
+        Observed {
+            init_func,
+            observer,
+        }
+    }
+}
+
+impl<T, G: Initializer<T>> SpinCell<T, Observed<T, G>> {
+    /// Creates a cell that calls `observer` with the value right after
+    /// `init_func` has built and published it.
+    #[inline(always)]
+    pub const fn new_observed(init_func: G, observer: fn(&T)) -> SpinCell<T, Observed<T, G>> {
+        Self::with_init(Observed::new(init_func, observer))
+    }
+}
+
+impl<T, G: Initializer<T>> Initializer<T> for Observed<T, G> {
+    #[inline]
+    fn init(self) -> T {
+        self.init_func.init()
+    }
+
+    #[inline]
+    fn init_at(self, value: NonNull<T>) -> T {
+        self.init_func.init_at(value)
+    }
+
+    #[inline]
+    fn init_observed(self, value: NonNull<T>) -> (T, Option<fn(&T)>) {
+        (self.init_func.init_at(value), Some(self.observer))
+    }
+}
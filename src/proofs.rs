@@ -0,0 +1,120 @@
+// Proof harnesses for the Kani model checker, run with `cargo kani`. Each
+// harness checks its property for every value and every sequence of
+// operations the nondeterministic choices allow, rather than for a few
+// sampled cases. Kani also rejects any read of uninitialized memory, so
+// every harness checks that the read paths only ever see a published value.
+
+use core::cell::Cell;
+
+use crate::{SpinCell, SpinOnceCell};
+
+// Counts how often the values and initializers carrying it are dropped.
+struct Tracked<'a>(&'a Cell<u32>);
+
+impl Drop for Tracked<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+// Accesses `cell` through one of the entry points that may run its
+// initializer, or through one of those that must not.
+fn access<G: crate::Initializer<u32>>(cell: &SpinCell<u32, G>, expected: u32) {
+    match kani::any::<u8>() % 5 {
+        0 => assert_eq!(**cell, expected),
+        1 => {
+            let _ = SpinCell::try_initialize(cell);
+        }
+        2 => assert_eq!(*SpinCell::force(cell), expected),
+        3 => {
+            if let Some(value) = SpinCell::get(cell) {
+                assert_eq!(*value, expected);
+            }
+        }
+        _ => {
+            let _ = SpinCell::is_initialized(cell);
+        }
+    }
+}
+
+#[kani::proof]
+#[kani::unwind(4)]
+fn initializer_runs_exactly_once() {
+    let runs = Cell::new(0u32);
+    let seed: u32 = kani::any();
+    let cell = SpinCell::new(|| {
+        runs.set(runs.get() + 1);
+        seed
+    });
+    for _ in 0..3 {
+        access(&cell, seed);
+    }
+    assert!(runs.get() <= 1);
+    assert_eq!(SpinCell::is_initialized(&cell), runs.get() == 1);
+    assert_eq!(*cell, seed);
+    assert_eq!(runs.get(), 1);
+}
+
+#[kani::proof]
+#[kani::unwind(4)]
+fn set_cell_reads_only_published_values() {
+    let cell: SpinOnceCell<u32> = SpinOnceCell::empty();
+    let mut stored = None;
+    for _ in 0..3 {
+        let value: u32 = kani::any();
+        if kani::any() {
+            match SpinCell::set(&cell, value) {
+                Ok(()) => {
+                    assert!(stored.is_none());
+                    stored = Some(value);
+                }
+                Err(rejected) => {
+                    assert_eq!(rejected, value);
+                    assert!(stored.is_some());
+                }
+            }
+        }
+        assert_eq!(SpinCell::get(&cell).copied(), stored);
+    }
+}
+
+#[kani::proof]
+fn drop_releases_either_value_or_initializer() {
+    let value_drops = &Cell::new(0u32);
+    let init_drops = &Cell::new(0u32);
+    let initialized: bool = kani::any();
+    {
+        let guard = Tracked(init_drops);
+        let cell = SpinCell::new(move || {
+            drop(guard);
+            Tracked(value_drops)
+        });
+        if initialized {
+            let _ = SpinCell::force(&cell);
+            assert_eq!(init_drops.get(), 1);
+        }
+        assert_eq!(value_drops.get(), 0);
+    }
+    assert_eq!(init_drops.get(), 1);
+    assert_eq!(value_drops.get(), u32::from(initialized));
+}
+
+#[kani::proof]
+fn into_inner_returns_what_the_cell_holds() {
+    let seed: u32 = kani::any();
+    let cell = SpinCell::new(move || seed);
+    let initialized: bool = kani::any();
+    if initialized {
+        let _ = SpinCell::force(&cell);
+    }
+    match SpinCell::into_inner(cell) {
+        Ok(value) => {
+            assert!(initialized);
+            assert_eq!(value, seed);
+        }
+        Err(init_func) => {
+            assert!(!initialized);
+            assert_eq!(init_func(), seed);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// Random perturbations of the initialization protocol for hunting races.
+//
+// Under the `race-hunt` feature threads busy-wait at the edges of the
+// protocol. Stress tests on real hardware otherwise tend to settle into a
+// few interleavings that the timing of the machine favours; stretching the
+// windows between taking the lock, publishing and unlocking by a varying
+// amount makes other threads land in them, which matters most on weakly
+// ordered targets that loom does not model.
+//
+// Under the `spurious-cas` feature the weak exchanges of the lock fail at
+// random, as they may on LL/SC targets but never do on x86.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+// The most spin-loop hints a single delay issues.
+#[cfg(feature = "race-hunt")]
+const MAX_SPINS: u32 = 1 << 10;
+
+// The state of a xorshift generator shared by all threads. Updates that
+// race with each other lose a step, which only makes the delays less
+// predictable.
+static SEED: AtomicU32 = AtomicU32::new(0x9e37_79b9);
+
+fn next() -> u32 {
+    let mut x = SEED.load(Ordering::Relaxed);
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    SEED.store(x, Ordering::Relaxed);
+    x
+}
+
+// Spins for a random number of rounds; a quarter of the calls do not wait
+// at all, so the unstretched interleavings still come up.
+#[cfg(feature = "race-hunt")]
+#[inline(never)]
+pub(crate) fn delay() {
+    let x = next();
+    if x & 3 == 0 {
+        return;
+    }
+    for _ in 0..(x >> 2) % MAX_SPINS {
+        core::hint::spin_loop();
+    }
+}
+
+// Whether the next weak exchange should fail, which a quarter of them do.
+#[cfg(feature = "spurious-cas")]
+#[inline(never)]
+pub(crate) fn spurious_failure() -> bool {
+    next() & 3 == 0
+}
@@ -0,0 +1,99 @@
+use crate::state::{State, UNINIT};
+use crate::{SpinCell, SpinOnceCell};
+
+/// A fixed number of singletons, registered under `&'static str` keys.
+///
+/// Each key can be registered once, and its value lives for as long as the
+/// registry. Registering takes a lock shared by the whole registry, so that
+/// no key is ever registered twice; looking a key up never takes the lock
+/// and never waits. No heap memory is used, which lets firmware modules
+/// find each other's singletons by name.
+///
+/// ```
+/// use spincell::SpinRegistry;
+///
+/// static DRIVERS: SpinRegistry<u32, 4> = SpinRegistry::new();
+///
+/// assert!(DRIVERS.insert("uart0", 115_200).is_ok());
+/// assert_eq!(DRIVERS.insert("uart0", 9600), Err(9600));
+/// assert_eq!(DRIVERS.get("uart0"), Some(&115_200));
+/// assert_eq!(DRIVERS.get("spi0"), None);
+/// ```
+pub struct SpinRegistry<T, const N: usize> {
+    // Held while inserting; UNINIT when free, LOCKED when held.
+    lock: State,
+    // Filled in order, so the first empty slot ends every search.
+    slots: [SpinOnceCell<(&'static str, T)>; N],
+}
+
+// Releases the insert lock, also if comparing keys or dropping a rejected
+// value panics.
+struct Unlock<'a>(&'a State);
+
+impl Drop for Unlock<'_> {
+    fn drop(&mut self) {
+        self.0.unlock();
+    }
+}
+
+impl<T, const N: usize> SpinRegistry<T, N> {
+    /// Creates an empty registry with room for `N` entries.
+    #[inline(always)]
+    pub const fn new() -> SpinRegistry<T, N> {
+        SpinRegistry {
+            lock: State::new(UNINIT),
+            slots: [const { SpinCell::empty() }; N],
+        }
+    }
+
+    /// Registers `value` under `key` and returns a reference to it.
+    ///
+    /// Returns `value` back if `key` is already registered or the registry
+    /// is full.
+    pub fn insert(&self, key: &'static str, value: T) -> Result<&T, T> {
+        self.lock.lock();
+        let _unlock = Unlock(&self.lock);
+        for slot in &self.slots {
+            match SpinCell::get(slot) {
+                Some((existing, _)) if *existing == key => return Err(value),
+                Some(_) => {}
+                None => {
+                    // Only inserts write to the slots, and this one holds the
+                    // lock, so the slot is still empty.
+                    return Ok(&SpinCell::get_or_init(slot, || (key, value)).1);
+                }
+            }
+        }
+        Err(value)
+    }
+
+    /// Returns the value registered under `key`.
+    pub fn get(&self, key: &str) -> Option<&T> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, value)| value)
+    }
+
+    /// Iterates over the registered keys and values, in the order they
+    /// were registered.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &T)> {
+        self.slots
+            .iter()
+            .map_while(|slot| SpinCell::get(slot))
+            .map(|(key, value)| (*key, value))
+    }
+
+    /// Returns the number of registered entries.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns whether nothing has been registered yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T, const N: usize> Default for SpinRegistry<T, N> {
+    fn default() -> SpinRegistry<T, N> {
+        SpinRegistry::new()
+    }
+}
@@ -0,0 +1,217 @@
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+
+use crate::state::{State, LOCKED, READY, UNINIT};
+use crate::wipe::wipe;
+
+/// A lazily initialized value that can be discarded and rebuilt.
+///
+/// The initializer is kept for the lifetime of the cell. [`reset`] drops the
+/// current value, and the next access runs the initializer again, which
+/// suits invalidatable caches such as parsed configuration or derived
+/// tables. Because the value may go away at any time, it is only reachable
+/// while the cell's lock is held, through [`read`] and [`mutate`].
+///
+/// ```
+/// use core::sync::atomic::{AtomicU32, Ordering};
+/// use spincell::SpinResetCell;
+///
+/// static SOURCE: AtomicU32 = AtomicU32::new(1);
+/// static DOUBLED: SpinResetCell<u32> =
+///     SpinResetCell::new(|| SOURCE.load(Ordering::Relaxed) * 2);
+///
+/// assert_eq!(DOUBLED.read(|n| *n), 2);
+/// SOURCE.store(5, Ordering::Relaxed);
+/// assert_eq!(DOUBLED.read(|n| *n), 2);
+/// DOUBLED.reset();
+/// assert_eq!(DOUBLED.read(|n| *n), 10);
+/// ```
+///
+/// If the initializer panics the cell is left uninitialized rather than
+/// poisoned, and the next access tries again.
+///
+/// # Generations
+///
+/// The cell counts how often its value has changed: every run of the
+/// initializer and every [`mutate`] adds one, so the first value is
+/// generation 1. Readers can compare generations to tell whether the value
+/// changed since they last looked.
+///
+/// [`reset`]: SpinResetCell::reset
+/// [`read`]: SpinResetCell::read
+/// [`mutate`]: SpinResetCell::mutate
+pub struct SpinResetCell<T, G = fn() -> T> {
+    // UNINIT, LOCKED or READY. The lock covers both fields below.
+    pub(crate) state: State,
+    init_func: UnsafeCell<G>,
+    // Initialized exactly when the state is READY, or LOCKED by a thread
+    // that found it READY or has since run the initializer.
+    value: UnsafeCell<MaybeUninit<T>>,
+    // Generation of the current value, 0 before the first initialization.
+    // Only accessed with the lock held.
+    generation: UnsafeCell<u64>,
+}
+
+// The value and the initializer are only touched by the thread holding the
+// lock, as with a mutex.
+unsafe impl<T: Send, G: Send> Sync for SpinResetCell<T, G> {}
+
+// Releases the lock when an access ends, including by unwinding, recording
+// whether the value is present at that point.
+struct Unlock<'a> {
+    state: &'a State,
+    ready: bool,
+}
+
+impl Drop for Unlock<'_> {
+    fn drop(&mut self) {
+        if self.ready {
+            self.state.publish();
+        } else {
+            self.state.unlock();
+        }
+    }
+}
+
+impl<T, G: FnMut() -> T> SpinResetCell<T, G> {
+    #[inline(always)]
+    pub const fn new(init_func: G) -> SpinResetCell<T, G> {
+        Self {
+            state: State::new(UNINIT),
+            init_func: UnsafeCell::new(init_func),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            generation: UnsafeCell::new(0),
+        }
+    }
+
+    /// Returns whether the cell currently holds a value.
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.state.is_ready()
+    }
+
+    /// Checks the invariants of the cell that hold whatever other threads
+    /// are doing, as
+    /// [`SpinCell::debug_validate`](crate::SpinCell::debug_validate) does.
+    /// A reset cell is never poisoned or eager.
+    pub fn debug_validate(&self) -> Result<(), &'static str> {
+        self.state.validate(&[UNINIT, LOCKED, READY])
+    }
+
+    /// Initializes the cell if needed, then runs `f` with exclusive access
+    /// to the value.
+    ///
+    /// Calling back into the same cell from `f` deadlocks.
+    pub fn mutate<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.access(
+            || false,
+            || {},
+            |value, generation| {
+                *generation += 1;
+                f(value)
+            },
+        )
+    }
+
+    /// Initializes the cell if needed, then runs `f` with the value while
+    /// holding the lock.
+    ///
+    /// Calling back into the same cell from `f` deadlocks.
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        self.access(|| false, || {}, |value, _| f(value))
+    }
+
+    /// Like [`read`](SpinResetCell::read), but also passes the generation
+    /// of the value.
+    ///
+    /// Calling back into the same cell from `f` deadlocks.
+    pub fn read_with_gen<R>(&self, f: impl FnOnce(&T, u64) -> R) -> R {
+        self.access(|| false, || {}, |value, generation| f(value, *generation))
+    }
+
+    /// Returns the generation of the current value, or of the last one if
+    /// the cell has been reset since. Does not initialize the cell.
+    pub fn generation(&self) -> u64 {
+        let _unlock = Unlock {
+            ready: self.state.lock_any() == READY,
+            state: &self.state,
+        };
+        // Safety: we hold the lock.
+        unsafe { *self.generation.get() }
+    }
+
+    /// Drops the current value, if any, so that the next access runs the
+    /// initializer again.
+    pub fn reset(&self) {
+        if self.state.lock_any() != READY {
+            self.state.unlock();
+            return;
+        }
+        // Safety: the cell was READY and we hold the lock. The value is
+        // moved out before unlocking and dropped afterwards, so a panicking
+        // destructor cannot leave the cell locked.
+        let value = unsafe { (*self.value.get()).assume_init_read() };
+        unsafe { wipe(self.value.get()) };
+        self.state.unlock();
+        drop(value);
+        #[cfg(feature = "testing")]
+        {
+            crate::testing::record(&self.state, crate::testing::Event::Reset);
+            crate::testing::record(&self.state, crate::testing::Event::Drop);
+        }
+    }
+}
+
+impl<T, G: FnMut() -> T> SpinResetCell<T, G> {
+    // Locks the cell and runs `f` with the value and its generation. A
+    // present value is discarded first if `expired` returns true, and
+    // `initialized` is called after the initializer has run; both are called
+    // with the lock held.
+    pub(crate) fn access<R>(
+        &self,
+        expired: impl FnOnce() -> bool,
+        initialized: impl FnOnce(),
+        f: impl FnOnce(&mut T, &mut u64) -> R,
+    ) -> R {
+        let mut unlock = Unlock {
+            ready: self.state.lock_any() == READY,
+            state: &self.state,
+        };
+        // Safety: we hold the lock, so nobody else is looking at the fields,
+        // and `ready` tracks whether the value is initialized.
+        let value = unsafe { &mut *self.value.get() };
+        let generation = unsafe { &mut *self.generation.get() };
+        if unlock.ready && expired() {
+            let stale = unsafe { value.assume_init_read() };
+            unsafe { wipe(value) };
+            unlock.ready = false;
+            drop(stale);
+            #[cfg(feature = "testing")]
+            crate::testing::record(&self.state, crate::testing::Event::Drop);
+        }
+        if !unlock.ready {
+            #[cfg(feature = "testing")]
+            crate::testing::record(&self.state, crate::testing::Event::Init);
+            value.write(unsafe { (*self.init_func.get())() });
+            unlock.ready = true;
+            *generation += 1;
+            initialized();
+        }
+        f(unsafe { value.assume_init_mut() }, generation)
+    }
+}
+
+impl<T, G> Drop for SpinResetCell<T, G> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == READY {
+            unsafe {
+                self.value.get_mut().assume_init_drop();
+                wipe(self.value.get());
+            }
+            #[cfg(feature = "testing")]
+            crate::testing::record(&self.state, crate::testing::Event::Drop);
+        }
+        #[cfg(feature = "testing")]
+        crate::testing::release(&self.state);
+    }
+}
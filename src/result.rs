@@ -0,0 +1,40 @@
+use crate::{Initializer, SpinCell};
+
+// Accessors for cells whose initializer can fail. The initializer runs once
+// either way, so a failure is remembered like a success and every later
+// access sees the same error.
+impl<T, E, G: Initializer<Result<T, E>>> SpinCell<Result<T, E>, G> {
+    /// Initializes the cell if needed and returns the outcome by reference.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// fn probe() -> Result<u16, &'static str> {
+    ///     Err("sensor not found")
+    /// }
+    ///
+    /// static SENSOR: SpinCell<Result<u16, &str>> = SpinCell::new(probe);
+    ///
+    /// assert_eq!(SpinCell::as_result(&SENSOR), Err(&"sensor not found"));
+    /// assert_eq!(SpinCell::get_ok(&SENSOR), None);
+    /// assert_eq!(SpinCell::get_err(&SENSOR), Some(&"sensor not found"));
+    /// ```
+    #[inline]
+    pub fn as_result(me: &SpinCell<Result<T, E>, G>) -> Result<&T, &E> {
+        SpinCell::force(me).as_ref()
+    }
+
+    /// Initializes the cell if needed and returns the value if the
+    /// initializer succeeded.
+    #[inline]
+    pub fn get_ok(me: &SpinCell<Result<T, E>, G>) -> Option<&T> {
+        SpinCell::as_result(me).ok()
+    }
+
+    /// Initializes the cell if needed and returns the error if the
+    /// initializer failed.
+    #[inline]
+    pub fn get_err(me: &SpinCell<Result<T, E>, G>) -> Option<&E> {
+        SpinCell::as_result(me).err()
+    }
+}
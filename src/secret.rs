@@ -0,0 +1,236 @@
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+
+use crate::wipe::zero;
+use crate::{Initializer, SpinCell};
+
+/// A lazily derived secret, such as key material, kept in locked memory.
+///
+/// On first access the value is moved into pages of its own, which are
+/// locked into RAM with `mlock` so they are never written to swap and, on
+/// Linux, excluded from core dumps with `madvise(MADV_DONTDUMP)`. When the
+/// cell is dropped the pages are overwritten with zeros before they are
+/// unlocked and freed. Locking is best effort: if the process may not lock
+/// more memory, or the platform has no `mlock`, the value is still stored
+/// and [`is_locked`](SecretSpinCell::is_locked) returns false.
+///
+/// The value is only reachable inside [`with`](SecretSpinCell::with), so
+/// the cell never hands out the value itself or a reference that outlives
+/// the call. Copies the initializer leaves behind on its own stack are out
+/// of the cell's reach.
+///
+/// Requires the `std` feature.
+///
+/// ```
+/// use spincell::SecretSpinCell;
+///
+/// static KEY: SecretSpinCell<[u8; 32]> = SecretSpinCell::new(|| [0x5a; 32]);
+///
+/// let checksum = KEY.with(|key| key.iter().fold(0u8, |a, b| a ^ b));
+/// assert_eq!(checksum, 0);
+/// ```
+pub struct SecretSpinCell<T, G = fn() -> T> {
+    cell: SpinCell<LockedBox<T>, Secret<G>>,
+}
+
+// The initializer of the inner cell, moving the value built by `G` into
+// locked memory.
+struct Secret<G>(G);
+
+// Memory of its own for a secret value, locked if `locked`. Wiped, unlocked
+// and freed on drop.
+struct Pages {
+    memory: NonNull<u8>,
+    layout: Layout,
+    locked: bool,
+}
+
+// A value living in `Pages`, with the observer its initializer returned.
+struct LockedBox<T> {
+    pages: Pages,
+    observer: Option<fn(&T)>,
+    value: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for LockedBox<T> {}
+unsafe impl<T: Sync> Sync for LockedBox<T> {}
+
+impl<T, G: Initializer<T>> SecretSpinCell<T, G> {
+    #[inline(always)]
+    pub const fn new(init_func: G) -> SecretSpinCell<T, G> {
+        SecretSpinCell {
+            cell: SpinCell::new(Secret(init_func)),
+        }
+    }
+
+    /// Initializes the cell if needed and calls `f` with the value.
+    #[inline]
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(LockedBox::get(&self.cell))
+    }
+
+    /// Returns whether the memory holding the value is locked into RAM.
+    /// False before the cell is initialized.
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        SpinCell::get(&self.cell).is_some_and(|secret| secret.pages.locked)
+    }
+}
+
+impl<T, G> SecretSpinCell<T, G> {
+    /// Returns whether the value has been initialized, without initializing
+    /// it.
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        SpinCell::is_initialized(&self.cell)
+    }
+}
+
+// The value lives in the locked pages rather than in the cell, so the
+// wrapped initializer is given their address instead of the slot's, and its
+// observer is called with the value inside the box.
+impl<T, G: Initializer<T>> Initializer<LockedBox<T>> for Secret<G> {
+    fn init(self) -> LockedBox<T> {
+        LockedBox::build(|value| (self.0.init_at(value), None))
+    }
+
+    #[inline]
+    fn init_at(self, _slot: NonNull<LockedBox<T>>) -> LockedBox<T> {
+        self.init()
+    }
+
+    fn init_observed(
+        self,
+        _slot: NonNull<LockedBox<T>>,
+    ) -> (LockedBox<T>, Option<fn(&LockedBox<T>)>) {
+        let secret = LockedBox::build(|value| self.0.init_observed(value));
+        let observer = secret.observer.map(|_| observe::<T> as fn(&LockedBox<T>));
+        (secret, observer)
+    }
+}
+
+fn observe<T>(secret: &LockedBox<T>) {
+    if let Some(observer) = secret.observer {
+        observer(secret.get());
+    }
+}
+
+impl<T> LockedBox<T> {
+    // Allocates the pages, then lets `init` build the value for them from
+    // their address. If `init` panics the pages are freed without a value.
+    fn build(init: impl FnOnce(NonNull<T>) -> (T, Option<fn(&T)>)) -> LockedBox<T> {
+        let pages = Pages::new(Layout::new::<T>());
+        let memory = pages.memory.cast::<T>();
+        let (value, observer) = init(memory);
+        // Safety: the pages are large and aligned enough for a `T`.
+        unsafe { memory.write(value) };
+        LockedBox {
+            pages,
+            observer,
+            value: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn get(&self) -> &T {
+        // Safety: the pages hold the value for as long as the box exists.
+        unsafe { self.pages.memory.cast::<T>().as_ref() }
+    }
+}
+
+impl<T> Drop for LockedBox<T> {
+    fn drop(&mut self) {
+        // Safety: the pages hold the value; they are released afterwards.
+        unsafe { self.pages.memory.cast::<T>().drop_in_place() };
+    }
+}
+
+impl Pages {
+    // Allocates whole pages for `layout`, so that locking and unlocking them
+    // never affects another allocation, and tries to lock them.
+    fn new(layout: Layout) -> Pages {
+        let page = os::page_size();
+        let size = layout.size().max(1).next_multiple_of(page);
+        let layout =
+            Layout::from_size_align(size, layout.align().max(page)).expect("secret too large");
+        // Safety: the layout has a nonzero size.
+        let Some(memory) = NonNull::new(unsafe { alloc(layout) }) else {
+            handle_alloc_error(layout)
+        };
+        let locked = unsafe { os::lock(memory.as_ptr().cast(), size) };
+        Pages {
+            memory,
+            layout,
+            locked,
+        }
+    }
+}
+
+impl Drop for Pages {
+    fn drop(&mut self) {
+        let memory = self.memory.as_ptr();
+        let size = self.layout.size();
+        // Safety: the memory was allocated with this layout, and the value
+        // in it has been dropped.
+        unsafe {
+            zero(memory, size);
+            if self.locked {
+                os::unlock(memory.cast(), size);
+            }
+            dealloc(memory, self.layout);
+        }
+    }
+}
+
+// Miri cannot call mlock and friends, so it takes the fallback, which keeps
+// the value in ordinary memory.
+#[cfg(all(unix, not(miri)))]
+mod os {
+    use core::ffi::{c_int, c_void};
+
+    extern "C" {
+        fn mlock(addr: *const c_void, len: usize) -> c_int;
+        fn munlock(addr: *const c_void, len: usize) -> c_int;
+        #[cfg(target_os = "linux")]
+        fn madvise(addr: *mut c_void, len: usize, advice: c_int) -> c_int;
+        fn getpagesize() -> c_int;
+    }
+
+    #[cfg(target_os = "linux")]
+    const MADV_DONTDUMP: c_int = 16;
+
+    pub(super) fn page_size() -> usize {
+        // getpagesize needs no per-OS constant, unlike sysconf, whose
+        // `_SC_PAGESIZE` differs between the BSDs, Linux and macOS.
+        match unsafe { getpagesize() } {
+            size if size > 0 && (size as usize).is_power_of_two() => size as usize,
+            _ => 4096,
+        }
+    }
+
+    pub(super) unsafe fn lock(memory: *mut c_void, len: usize) -> bool {
+        #[cfg(target_os = "linux")]
+        madvise(memory, len, MADV_DONTDUMP);
+        mlock(memory, len) == 0
+    }
+
+    pub(super) unsafe fn unlock(memory: *mut c_void, len: usize) {
+        munlock(memory, len);
+    }
+}
+
+#[cfg(not(all(unix, not(miri))))]
+mod os {
+    use core::ffi::c_void;
+
+    pub(super) fn page_size() -> usize {
+        4096
+    }
+
+    pub(super) unsafe fn lock(_: *mut c_void, _: usize) -> bool {
+        false
+    }
+
+    pub(super) unsafe fn unlock(_: *mut c_void, _: usize) {}
+}
@@ -0,0 +1,80 @@
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+
+use crate::{Initializer, SpinCell};
+
+/// A lazily initialized value shared through an [`Arc`].
+///
+/// For values that are built on first use but, unlike statics, are created
+/// at run time and handed to several threads or tasks:
+///
+/// ```
+/// use spincell::{SharedLazy, SpinCell};
+///
+/// let table: SharedLazy<[u32; 4], _> = SpinCell::new_shared(|| [1, 2, 3, 4]);
+/// let worker = table.clone();
+///
+/// std::thread::spawn(move || assert_eq!(worker[3], 4))
+///     .join()
+///     .unwrap();
+/// assert_eq!(table[0], 1);
+/// ```
+pub type SharedLazy<T, G = fn() -> T> = Arc<SpinCell<T, G>>;
+
+impl<T, G: Initializer<T>> SpinCell<T, G> {
+    /// Creates a shared cell that runs `init_func` on first access.
+    #[inline]
+    pub fn new_shared(init_func: G) -> SharedLazy<T, G> {
+        Arc::new(SpinCell::new(init_func))
+    }
+}
+
+/// The initializer of a cell created with [`SpinCell::new_shared_cyclic`].
+pub struct SharedCyclic<T> {
+    this: WeakSelf<T>,
+    init_func: Box<CyclicFn<T>>,
+}
+
+// The weak reference a cyclic initializer gets to its own cell, and the
+// boxed initializer taking it.
+type WeakSelf<T> = Weak<SpinCell<T, SharedCyclic<T>>>;
+type CyclicFn<T> = dyn FnOnce(&WeakSelf<T>) -> T + Send;
+
+impl<T> SpinCell<T, SharedCyclic<T>> {
+    /// Creates a shared cell whose initializer gets a weak reference to the
+    /// cell itself, like `Arc::new_cyclic`.
+    ///
+    /// The initializer runs on first access, so the value can hand the weak
+    /// reference to callbacks or child objects that need to reach back to
+    /// it without keeping it alive.
+    ///
+    /// ```
+    /// use std::sync::Weak;
+    /// use spincell::{SharedCyclic, SpinCell};
+    ///
+    /// struct Service {
+    ///     this: Weak<SpinCell<Service, SharedCyclic<Service>>>,
+    /// }
+    ///
+    /// let service = SpinCell::new_shared_cyclic(|this| Service { this: this.clone() });
+    /// let again = service.this.upgrade().unwrap();
+    /// assert!(std::sync::Arc::ptr_eq(&service, &again));
+    /// ```
+    pub fn new_shared_cyclic(
+        init_func: impl FnOnce(&Weak<SpinCell<T, SharedCyclic<T>>>) -> T + Send + 'static,
+    ) -> SharedLazy<T, SharedCyclic<T>> {
+        Arc::new_cyclic(|this| {
+            SpinCell::new(SharedCyclic {
+                this: this.clone(),
+                init_func: Box::new(init_func),
+            })
+        })
+    }
+}
+
+impl<T> Initializer<T> for SharedCyclic<T> {
+    #[inline]
+    fn init(self) -> T {
+        (self.init_func)(&self.this)
+    }
+}
@@ -0,0 +1,180 @@
+//! Orderly teardown of statics.
+//!
+//! Statics never run `Drop`. Cells declared with
+//! [`shutdown_static!`](crate::shutdown_static) remember the order in which
+//! they were initialized, and [`run_destructors`] drops their values in
+//! reverse order, so a value is always dropped before the values its
+//! initializer used.
+//!
+//! ```
+//! use core::sync::atomic::{AtomicBool, Ordering};
+//!
+//! static FLUSHED: AtomicBool = AtomicBool::new(false);
+//!
+//! struct Log;
+//!
+//! impl Drop for Log {
+//!     fn drop(&mut self) {
+//!         FLUSHED.store(true, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! spincell::shutdown_static! {
+//!     static LOG: Log = Log;
+//! }
+//!
+//! let _ = &*LOG;
+//! // Safety: nothing accesses LOG from here on.
+//! unsafe { spincell::shutdown::run_destructors() };
+//! assert!(FLUSHED.load(Ordering::Relaxed));
+//! ```
+
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::state::{LOCKED, READY};
+use crate::{Initializer, SpinCell};
+
+/// A cell known to the shutdown registry.
+///
+/// Entries are normally created by [`shutdown_static!`](crate::shutdown_static).
+pub struct ShutdownEntry {
+    cell: &'static (dyn Teardown + Sync),
+    // Next older entry in the list of initialized cells.
+    next: AtomicPtr<ShutdownEntry>,
+}
+
+/// A cell whose value can be dropped by [`run_destructors`].
+pub trait Teardown: teardown::Sealed {}
+
+mod teardown {
+    pub trait Sealed {
+        // Drops the value if there is one, leaving the cell unusable.
+        // Nothing else may access the cell, then or later.
+        unsafe fn teardown(&self);
+    }
+}
+
+/// The initializer of a cell declared with
+/// [`shutdown_static!`](crate::shutdown_static): runs `G` and then records
+/// the cell as initialized.
+pub struct OnShutdown<G> {
+    init_func: G,
+    entry: &'static ShutdownEntry,
+}
+
+// Newest initialized entry.
+static INITIALIZED: AtomicPtr<ShutdownEntry> = AtomicPtr::new(ptr::null_mut());
+
+impl ShutdownEntry {
+    #[inline(always)]
+    pub const fn new(cell: &'static (dyn Teardown + Sync)) -> ShutdownEntry {
+        ShutdownEntry {
+            cell,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    fn push(&'static self) {
+        let entry = self as *const ShutdownEntry as *mut ShutdownEntry;
+        let mut head = INITIALIZED.load(Ordering::Relaxed);
+        loop {
+            self.next.store(head, Ordering::Relaxed);
+            match INITIALIZED.compare_exchange_weak(
+                head,
+                entry,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(actual) => head = actual,
+            }
+        }
+    }
+}
+
+impl<G> OnShutdown<G> {
+    #[inline(always)]
+    pub const fn new(init_func: G, entry: &'static ShutdownEntry) -> OnShutdown<G> {
+        OnShutdown { init_func, entry }
+    }
+}
+
+impl<T, G: Initializer<T>> Initializer<T> for OnShutdown<G> {
+    #[inline]
+    fn init(self) -> T {
+        let value = self.init_func.init();
+        self.entry.push();
+        value
+    }
+
+    #[inline]
+    fn init_at(self, slot: NonNull<T>) -> T {
+        let value = self.init_func.init_at(slot);
+        // Anything this initializer used was initialized, and recorded,
+        // before this point, so it ends up later in the list.
+        self.entry.push();
+        value
+    }
+}
+
+impl<T, G> Teardown for SpinCell<T, G> {}
+
+impl<T, G> teardown::Sealed for SpinCell<T, G> {
+    unsafe fn teardown(&self) {
+        // The entry is only recorded while the initializer runs, so the
+        // cell is READY, or still LOCKED if this runs on the thread that is
+        // initializing it. Only in the first case is there a value.
+        if self.state.load() == READY {
+            // Poison first so that a later access panics instead of reading
+            // the dropped value.
+            self.state.poison();
+            ptr::drop_in_place(self.value_ptr().as_ptr());
+            #[cfg(feature = "testing")]
+            crate::testing::record(&self.state, crate::testing::Event::Drop);
+        } else {
+            debug_assert_eq!(self.state.load(), LOCKED);
+        }
+    }
+}
+
+/// Drops the values of all initialized cells declared with
+/// [`shutdown_static!`](crate::shutdown_static), most recently initialized
+/// first.
+///
+/// Afterwards accessing one of those cells panics. Cells initialized later
+/// are dropped by a later call.
+///
+/// # Safety
+///
+/// No thread may hold a reference into any of these cells, and no thread
+/// may access them while this runs.
+pub unsafe fn run_destructors() {
+    let mut entry = INITIALIZED.swap(ptr::null_mut(), Ordering::Acquire);
+    while let Some(current) = entry.as_ref() {
+        entry = current.next.load(Ordering::Relaxed);
+        current.cell.teardown();
+    }
+}
+
+/// Declares statics whose values are dropped by
+/// [`shutdown::run_destructors`](crate::shutdown::run_destructors).
+///
+/// Each static is a lazily initialized [`SpinCell`](crate::SpinCell) like
+/// any other; initializing it also records it for teardown.
+#[macro_export]
+macro_rules! shutdown_static {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::SpinCell<$ty, $crate::shutdown::OnShutdown<fn() -> $ty>> = {
+                fn init() -> $ty {
+                    $init
+                }
+                static ENTRY: $crate::shutdown::ShutdownEntry =
+                    $crate::shutdown::ShutdownEntry::new(&$name);
+                $crate::SpinCell::new($crate::shutdown::OnShutdown::new(init, &ENTRY))
+            };
+        )*
+    };
+}
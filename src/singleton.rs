@@ -0,0 +1,127 @@
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::wipe::wipe;
+
+// No value has been put in yet.
+const EMPTY: u8 = 0;
+// A thread is writing the value passed to `put`.
+const WRITING: u8 = 1;
+// The value is waiting to be taken.
+const FULL: u8 = 2;
+// The value has been taken; the singleton stays empty for good.
+const TAKEN: u8 = 3;
+
+/// A value that can be moved out exactly once, like the `take` of the
+/// peripheral singletons generated by svd2rust.
+///
+/// The value is either placed in the singleton when it is created or
+/// supplied later with [`put`](SpinSingleton::put). The first call to
+/// [`take`](SpinSingleton::take) after that gets it, and every other call
+/// gets `None`, so at most one owner of a peripheral ever exists. Neither
+/// method waits or disables interrupts; both are a single atomic
+/// read-modify-write on the fast path, which makes them usable from
+/// interrupt handlers.
+///
+/// ```
+/// use spincell::SpinSingleton;
+///
+/// pub struct Uart {
+///     base: usize,
+/// }
+///
+/// static UART: SpinSingleton<Uart> = SpinSingleton::new(Uart { base: 0x4000_c000 });
+///
+/// let uart = UART.take().unwrap();
+/// assert_eq!(uart.base, 0x4000_c000);
+/// assert!(UART.take().is_none());
+/// ```
+pub struct SpinSingleton<T> {
+    state: AtomicU8,
+    // Initialized exactly when the state is FULL.
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Only one thread ever gets to move the value in, and only one to move it
+// out, so sharing the singleton only ever sends the value.
+unsafe impl<T: Send> Sync for SpinSingleton<T> {}
+
+impl<T> SpinSingleton<T> {
+    /// Creates a singleton holding `value`.
+    #[inline(always)]
+    pub const fn new(value: T) -> SpinSingleton<T> {
+        SpinSingleton {
+            state: AtomicU8::new(FULL),
+            value: UnsafeCell::new(MaybeUninit::new(value)),
+        }
+    }
+
+    /// Creates a singleton whose value is supplied later with
+    /// [`put`](SpinSingleton::put).
+    #[inline(always)]
+    pub const fn empty() -> SpinSingleton<T> {
+        SpinSingleton {
+            state: AtomicU8::new(EMPTY),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Stores `value` for [`take`](SpinSingleton::take) to hand out.
+    ///
+    /// Returns `value` back if the singleton already held a value, now or
+    /// at any point in the past: each singleton hands out one value only.
+    pub fn put(&self, value: T) -> Result<(), T> {
+        if self
+            .state
+            .compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(value);
+        }
+        // Safety: moving the state out of EMPTY gives this thread sole
+        // access to the storage.
+        unsafe { (*self.value.get()).write(value) };
+        self.state.store(FULL, Ordering::Release);
+        Ok(())
+    }
+
+    /// Moves the value out if it has not been taken yet.
+    ///
+    /// Returns `None` if the singleton is empty, if another thread is still
+    /// putting the value in, and on every call after the first successful
+    /// one.
+    pub fn take(&self) -> Option<T> {
+        self.state
+            .compare_exchange(FULL, TAKEN, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        // Safety: the value was published by the FULL state, and moving the
+        // state to TAKEN gives this thread sole access to it.
+        unsafe {
+            let slot = self.value.get();
+            let value = (*slot).assume_init_read();
+            wipe(slot);
+            Some(value)
+        }
+    }
+
+    /// Returns whether the value has already been taken.
+    #[inline]
+    pub fn is_taken(&self) -> bool {
+        self.state.load(Ordering::Relaxed) == TAKEN
+    }
+}
+
+impl<T> Drop for SpinSingleton<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == FULL {
+            // Safety: the singleton holds the value, and we have exclusive
+            // access.
+            unsafe {
+                let slot = self.value.get_mut();
+                slot.assume_init_drop();
+                wipe(slot);
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use core::cell::UnsafeCell;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+use crate::state::{State, LOCKED, POISONED, READY, UNINIT};
+use crate::Initializer;
+
+/// Static storage for the value of a [`SplitSpinCell`].
+///
+/// The slot is plain uninitialized memory, so it can be placed in a section
+/// that is neither copied nor zeroed at boot. It is normally declared through
+/// [`spin_static!`](crate::spin_static).
+pub struct UninitSlot<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Safety: the slot is only ever accessed through the single cell that owns
+// it, which does its own synchronization.
+unsafe impl<T> Sync for UninitSlot<T> {}
+
+impl<T> UninitSlot<T> {
+    #[inline(always)]
+    pub const fn new() -> UninitSlot<T> {
+        Self {
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+impl<T> Default for UninitSlot<T> {
+    fn default() -> UninitSlot<T> {
+        Self::new()
+    }
+}
+
+/// A cell whose value lives in a separate [`UninitSlot`].
+///
+/// The state byte and the initializer stay with the cell, while the value is
+/// written into the slot on first access. This lets the (possibly large)
+/// value be placed in a NOLOAD or `.uninit` section while the cell itself
+/// lives in `.data`/`.bss`. See [`spin_static!`](crate::spin_static).
+pub struct SplitSpinCell<T: 'static, G = fn() -> T> {
+    state: State,
+    init_func: UnsafeCell<ManuallyDrop<G>>,
+    slot: &'static UninitSlot<T>,
+}
+
+// Any thread may run the initializer and drop the value, so sharing the
+// cell also sends the initializer and the value.
+unsafe impl<T: Send + Sync, G: Send> Sync for SplitSpinCell<T, G> {}
+unsafe impl<T: Send, G: Send> Send for SplitSpinCell<T, G> {}
+
+impl<T, G: Initializer<T>> SplitSpinCell<T, G> {
+    /// # Safety
+    ///
+    /// `slot` must not be used by any other cell.
+    #[inline(always)]
+    pub const unsafe fn new(slot: &'static UninitSlot<T>, init_func: G) -> SplitSpinCell<T, G> {
+        Self {
+            state: State::new(UNINIT),
+            init_func: UnsafeCell::new(ManuallyDrop::new(init_func)),
+            slot,
+        }
+    }
+
+    #[allow(clippy::result_unit_err)]
+    pub fn try_initialize(me: &SplitSpinCell<T, G>) -> Result<(), ()> {
+        if me.state.is_ready() {
+            return Err(());
+        }
+        me.initialize_slow();
+        Ok(())
+    }
+
+    /// Checks the invariants of the cell that hold whatever other threads
+    /// are doing, as [`SpinCell::debug_validate`](crate::SpinCell::debug_validate)
+    /// does. A split cell is never eager.
+    pub fn debug_validate(me: &SplitSpinCell<T, G>) -> Result<(), &'static str> {
+        me.state.validate(&[UNINIT, LOCKED, READY, POISONED])
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn initialize_slow(&self) {
+        self.state.initialize(&mut || unsafe {
+            // Run at most once, with the lock held; the slot belongs to this
+            // cell alone.
+            let init_func = ManuallyDrop::take(&mut *self.init_func.get());
+            let slot = &mut *self.slot.value.get();
+            let value = NonNull::from(&mut *slot).cast::<T>();
+            slot.write(init_func.init_at(value));
+        });
+    }
+}
+
+impl<T, G: Initializer<T>> Deref for SplitSpinCell<T, G> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        if !self.state.is_ready() {
+            self.initialize_slow();
+        }
+        unsafe { (*self.slot.value.get()).assume_init_ref() }
+    }
+}
+
+impl<T, G> Drop for SplitSpinCell<T, G> {
+    fn drop(&mut self) {
+        match *self.state.get_mut() {
+            // We own the slot, so the value written there is ours to drop.
+            READY => unsafe { (*self.slot.value.get()).assume_init_drop() },
+            UNINIT => unsafe { ManuallyDrop::drop(self.init_func.get_mut()) },
+            _ => {}
+        }
+    }
+}
+
+/// Declares statics whose values live in a separate, attributed slot.
+///
+/// A `#[link_section]` written on the static applies to the hidden
+/// [`UninitSlot`] that holds the value, not to the cell, so it moves only
+/// the value while the state byte and initializer stay in the default
+/// sections. Doc comments and all other attributes apply to the static
+/// itself:
+///
+/// ```
+/// spincell::spin_static! {
+///     /// Lookup table kept out of the zeroed `.bss`.
+///     #[link_section = ".bss.uninit_table"]
+///     static TABLE: [u32; 256] = [5; 256];
+/// }
+///
+/// assert_eq!(TABLE[255], 5);
+/// ```
+#[macro_export]
+macro_rules! spin_static {
+    () => {};
+    // Sorts the attributes of one static into those for the slot and those
+    // for the static, then declares it.
+    (@attrs [$($slot:tt)*] [$($attr:tt)*] #[link_section = $section:literal] $($rest:tt)*) => {
+        $crate::spin_static!(@attrs [$($slot)* #[link_section = $section]] [$($attr)*] $($rest)*);
+    };
+    (@attrs [$($slot:tt)*] [$($attr:tt)*] #[$meta:meta] $($rest:tt)*) => {
+        $crate::spin_static!(@attrs [$($slot)*] [$($attr)* #[$meta]] $($rest)*);
+    };
+    (
+        @attrs [$($slot:tt)*] [$($attr:tt)*]
+        $vis:vis static $name:ident: $ty:ty = $init:expr;
+        $($rest:tt)*
+    ) => {
+        $($attr)*
+        $vis static $name: $crate::SplitSpinCell<$ty> = {
+            $($slot)*
+            static SLOT: $crate::UninitSlot<$ty> = $crate::UninitSlot::new();
+            fn init() -> $ty {
+                $init
+            }
+            // Safety: SLOT is private to this expansion.
+            unsafe { $crate::SplitSpinCell::new(&SLOT, init) }
+        };
+        $crate::spin_static!($($rest)*);
+    };
+    ($($rest:tt)+) => {
+        $crate::spin_static!(@attrs [] [] $($rest)+);
+    };
+}
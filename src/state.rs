@@ -0,0 +1,474 @@
+//! Values of the state byte at the start of every [`SpinCell`].
+//!
+//! A cell starts out [`UNINIT`], moves to [`LOCKED`] while one thread runs
+//! the initializer, and ends up [`READY`] once the value has been published.
+//! If the initializer panics the cell is left [`POISONED`]. Cells created
+//! with [`SpinCell::eager`] start out [`EAGER`] instead of [`UNINIT`] and
+//! only become [`UNINIT`] when [`init_all`] gets to them. A cell that is
+//! [`seal`](crate::SpinCell::seal)ed before being initialized ends up
+//! [`SEALED`] for good. These values are
+//! part of the stable layout of [`SpinCell`] and will not change, so C code
+//! and debuggers may compare against them.
+//!
+//! [`SpinCell`]: crate::SpinCell
+//! [`SpinCell::eager`]: crate::SpinCell::eager
+//! [`init_all`]: crate::init_all
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::backoff::Backoff;
+
+/// The initializer has not run yet; the slot holds the initializer.
+pub const UNINIT: u8 = 0;
+/// A thread is running the initializer.
+pub const LOCKED: u8 = 1;
+/// The value has been published; the slot holds the value.
+pub const READY: u8 = 2;
+/// The initializer panicked; the slot holds neither.
+pub const POISONED: u8 = 3;
+/// Like [`UNINIT`], but the cell may only be initialized through
+/// [`init_all`](crate::init_all); accessing it panics.
+pub const EAGER: u8 = 4;
+/// The cell was sealed before being initialized; the slot holds neither the
+/// initializer nor a value, and accessing it panics.
+pub const SEALED: u8 = 5;
+
+// Marks a point of the protocol where the order of threads matters: where
+// the `test-interleave` hook runs and `race-hunt` delays the thread.
+macro_rules! edge {
+    ($state:expr, $point:ident) => {
+        #[cfg(feature = "test-interleave")]
+        crate::interleave::test_yield(crate::interleave::YieldPoint::$point, $state);
+        #[cfg(feature = "race-hunt")]
+        crate::race_hunt::delay();
+    };
+}
+
+// The initialization protocol shared by every cell type. None of this is
+// generic over the stored value, so the locking and publishing machinery is
+// compiled once no matter how many distinct cells a program declares; the
+// cells only contribute the closure that moves their initializer into place.
+#[repr(transparent)]
+pub(crate) struct State(AtomicU8);
+
+// Poisons the state if the initializer unwinds before the value is published.
+struct PoisonOnUnwind<'a>(&'a State);
+
+impl Drop for PoisonOnUnwind<'_> {
+    fn drop(&mut self) {
+        self.0.poison();
+    }
+}
+
+impl State {
+    #[inline(always)]
+    pub(crate) const fn new(state: u8) -> State {
+        State(AtomicU8::new(state))
+    }
+
+    // A single Acquire load: observing READY makes the published value
+    // visible to the caller.
+    #[inline(always)]
+    pub(crate) fn is_ready(&self) -> bool {
+        self.0.load(Ordering::Acquire) == READY
+    }
+
+    // Checks that the state byte holds one of `states`. It is only read, so
+    // a cell whose memory has been overwritten can be checked without
+    // spinning on a lock that may never be released.
+    pub(crate) fn validate(&self, states: &[u8]) -> Result<(), &'static str> {
+        match self.0.load(Ordering::Acquire) {
+            state if state > SEALED => Err("the state byte holds no known state"),
+            // Only an unwinding initializer poisons a cell, apart from the
+            // poisoned cells the testing module builds.
+            POISONED if cfg!(panic = "abort") && !cfg!(feature = "testing") => {
+                Err("the cell is poisoned, but initializers cannot unwind")
+            }
+            state if states.contains(&state) => Ok(()),
+            _ => Err("the state byte holds a state this cell type never enters"),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_sealed(&self) -> bool {
+        self.0.load(Ordering::Relaxed) == SEALED
+    }
+
+    // Same as `is_ready`, but also callable during constant evaluation,
+    // where there are no other threads and a plain read is enough.
+    #[cfg(feature = "nightly")]
+    #[inline(always)]
+    pub(crate) const fn is_ready_const(&self) -> bool {
+        const fn compile_time(state: &AtomicU8) -> bool {
+            unsafe { *state.as_ptr() == READY }
+        }
+        #[inline(always)]
+        fn run_time(state: &AtomicU8) -> bool {
+            state.load(Ordering::Acquire) == READY
+        }
+        core::intrinsics::const_eval_select((&self.0,), compile_time, run_time)
+    }
+
+    #[inline(always)]
+    pub(crate) fn load(&self) -> u8 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_mut(&mut self) -> &mut u8 {
+        self.0.get_mut()
+    }
+
+    // Runs `init` exactly once across all threads, with the lock held, and
+    // publishes the result. Returns once the state is READY, whether this
+    // call or another one did the work.
+    //
+    // Panics if an earlier initialization attempt panicked.
+    #[inline(never)]
+    pub(crate) fn initialize(&self, init: &mut dyn FnMut()) {
+        if self.lock() {
+            self.initialize_locked(init);
+        }
+    }
+
+    // Runs `init` and publishes the result, with the lock already held by
+    // the caller.
+    pub(crate) fn initialize_locked(&self, init: &mut dyn FnMut()) {
+        // If the initializer panics the guard poisons the state so that
+        // waiting threads do not spin forever.
+        let guard = PoisonOnUnwind(self);
+        init();
+        core::mem::forget(guard);
+
+        self.publish();
+    }
+
+    // Waits until either this thread holds the lock, returning true, or the
+    // value has been published, returning false.
+    //
+    // Panics if an earlier initialization attempt panicked.
+    #[inline]
+    pub(crate) fn lock(&self) -> bool {
+        // Move the state from UNINIT to LOCKED. Use Acquire so that a READY
+        // observed here also makes the published value visible to us. The
+        // uncontended case falls straight through; everything else,
+        // including spurious failures of the weak exchange, is handled out
+        // of line.
+        match self.exchange_weak(UNINIT, LOCKED, Ordering::Acquire, Ordering::Acquire) {
+            Ok(_) => {
+                edge!(self, Locked);
+                true
+            }
+            Err(state) => self.lock_contended(state),
+        }
+    }
+
+    // The weak exchange every lock attempt goes through. With the
+    // `spurious-cas` feature it now and then fails although the state
+    // matched, as it may on LL/SC targets, so the retry paths also run on
+    // machines whose exchanges never fail spuriously. Strong exchanges are
+    // left alone, since callers rely on them not failing that way.
+    #[inline(always)]
+    fn exchange_weak(
+        &self,
+        current: u8,
+        new: u8,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<u8, u8> {
+        #[cfg(feature = "spurious-cas")]
+        if crate::race_hunt::spurious_failure() {
+            return Err(self.0.load(failure));
+        }
+        self.0.compare_exchange_weak(current, new, success, failure)
+    }
+
+    // Allows an EAGER cell to be initialized like any other.
+    #[inline]
+    pub(crate) fn release_eager(&self) {
+        let _ = self
+            .0
+            .compare_exchange(EAGER, UNINIT, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    // Locks an UNINIT or EAGER cell to seal it, waiting for a thread that
+    // holds the lock. Returns the state found instead if it is READY,
+    // POISONED or SEALED. The lock is released with `restore(SEALED)`.
+    pub(crate) fn lock_unsealed(&self) -> Result<(), u8> {
+        let mut backoff = Backoff::new();
+        let mut state = self.0.load(Ordering::Acquire);
+        loop {
+            match state {
+                UNINIT | EAGER => {
+                    match self.exchange_weak(state, LOCKED, Ordering::Acquire, Ordering::Acquire) {
+                        Ok(_) => {
+                            edge!(self, Locked);
+                            return Ok(());
+                        }
+                        Err(actual) => state = actual,
+                    }
+                }
+                LOCKED => {
+                    backoff.spin();
+                    state = self.0.load(Ordering::Acquire);
+                }
+                _ => return Err(state),
+            }
+        }
+    }
+
+    // Locks a cell whose value has already been published, for exclusive
+    // access to it. Released again with `publish`.
+    #[inline]
+    pub(crate) fn lock_ready(&self) {
+        let mut backoff = Backoff::new();
+        while self
+            .exchange_weak(READY, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            backoff.spin();
+        }
+        edge!(self, Locked);
+    }
+
+    // Locks the cell whether or not it holds a value, for cells whose value
+    // can be discarded again. Returns the state the lock was taken from,
+    // UNINIT, READY or EAGER; the lock is released with `publish`, `unlock`
+    // or `restore`. Panics if the cell is poisoned or sealed.
+    pub(crate) fn lock_any(&self) -> u8 {
+        let mut backoff = Backoff::new();
+        let mut state = self.0.load(Ordering::Relaxed);
+        loop {
+            match state {
+                POISONED => poisoned(),
+                SEALED => accessed_after_seal(),
+                UNINIT | READY | EAGER => {
+                    match self.exchange_weak(state, LOCKED, Ordering::Acquire, Ordering::Relaxed) {
+                        Ok(_) => {
+                            edge!(self, Locked);
+                            return state;
+                        }
+                        Err(actual) => state = actual,
+                    }
+                }
+                _ => {
+                    backoff.spin();
+                    state = self.0.load(Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    // Publishes the initialized value and releases the lock. Use Release so
+    // readers that do an Acquire load on the state see the written data.
+    #[inline]
+    pub(crate) fn publish(&self) {
+        edge!(self, BeforePublish);
+        self.0.store(READY, Ordering::Release);
+        #[cfg(feature = "async")]
+        crate::wait::wake(self);
+        #[cfg(all(
+            feature = "nightly",
+            target_arch = "wasm32",
+            target_feature = "atomics"
+        ))]
+        crate::wasm::wake();
+    }
+
+    // Releases the lock after the initializer has been lost, leaving the
+    // cell POISONED.
+    #[inline]
+    pub(crate) fn poison(&self) {
+        edge!(self, BeforeUnlock);
+        self.0.store(POISONED, Ordering::Release);
+        #[cfg(feature = "async")]
+        crate::wait::wake(self);
+        #[cfg(all(
+            feature = "nightly",
+            target_arch = "wasm32",
+            target_feature = "atomics"
+        ))]
+        crate::wasm::wake();
+    }
+
+    // Releases the lock without publishing, returning the cell to UNINIT.
+    #[inline]
+    pub(crate) fn unlock(&self) {
+        edge!(self, BeforeUnlock);
+        self.0.store(UNINIT, Ordering::Release);
+        #[cfg(feature = "async")]
+        crate::wait::wake(self);
+        #[cfg(all(
+            feature = "nightly",
+            target_arch = "wasm32",
+            target_feature = "atomics"
+        ))]
+        crate::wasm::wake();
+    }
+
+    // Like `is_ready`, but panics if the cell is poisoned, for waiters that
+    // never take the lock themselves.
+    #[cfg(feature = "async")]
+    pub(crate) fn is_ready_checked(&self) -> bool {
+        match self.0.load(Ordering::Acquire) {
+            READY => true,
+            POISONED => poisoned(),
+            SEALED => accessed_after_seal(),
+            _ => false,
+        }
+    }
+
+    // Releases a lock taken with `lock_any`, putting back the state it was
+    // taken from.
+    #[inline]
+    pub(crate) fn restore(&self, state: u8) {
+        edge!(self, BeforeUnlock);
+        self.0.store(state, Ordering::Release);
+        #[cfg(feature = "async")]
+        crate::wait::wake(self);
+        #[cfg(all(
+            feature = "nightly",
+            target_arch = "wasm32",
+            target_feature = "atomics"
+        ))]
+        crate::wasm::wake();
+    }
+
+    // Waits until the value has been published, without ever taking the
+    // lock.
+    //
+    // Panics if the cell is or becomes poisoned.
+    pub(crate) fn wait(&self) {
+        let mut backoff = Backoff::new();
+        loop {
+            match self.0.load(Ordering::Acquire) {
+                READY => return,
+                POISONED => poisoned(),
+                SEALED => accessed_after_seal(),
+                _ => backoff.spin(),
+            }
+        }
+    }
+
+    // Like `lock`, but returns None instead of waiting if another thread
+    // holds the lock.
+    #[cfg(feature = "async")]
+    pub(crate) fn try_lock(&self) -> Option<bool> {
+        let mut state = self.0.load(Ordering::Acquire);
+        loop {
+            match state {
+                READY => return Some(false),
+                POISONED => poisoned(),
+                EAGER => accessed_before_init_all(),
+                SEALED => accessed_after_seal(),
+                UNINIT => {
+                    match self.exchange_weak(UNINIT, LOCKED, Ordering::Acquire, Ordering::Acquire) {
+                        Ok(_) => {
+                            edge!(self, Locked);
+                            return Some(true);
+                        }
+                        Err(actual) => state = actual,
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    // Like `lock`, but spins at most `max_spins` times while another thread
+    // holds the lock and returns None once it has. Only rounds spent with
+    // the cell LOCKED count: a spurious failure of the weak exchange on an
+    // UNINIT cell retries right away, as in `lock_waiting`, so a cell nobody
+    // else is touching is always taken.
+    pub(crate) fn lock_bounded(&self, max_spins: usize) -> Option<bool> {
+        let mut state = self.0.load(Ordering::Acquire);
+        let mut spins = 0;
+        loop {
+            match state {
+                READY => return Some(false),
+                POISONED => poisoned(),
+                EAGER => accessed_before_init_all(),
+                SEALED => accessed_after_seal(),
+                UNINIT => {
+                    match self.exchange_weak(UNINIT, LOCKED, Ordering::Acquire, Ordering::Acquire) {
+                        Ok(_) => {
+                            edge!(self, Locked);
+                            return Some(true);
+                        }
+                        Err(actual) => state = actual,
+                    }
+                }
+                _ if spins == max_spins => return None,
+                _ => {
+                    spins += 1;
+                    core::hint::spin_loop();
+                    state = self.0.load(Ordering::Acquire);
+                }
+            }
+        }
+    }
+
+    // Waits for a cell that was observed in `state` to either become READY,
+    // returning false, or to be locked by this thread, returning true.
+    #[cold]
+    fn lock_contended(&self, state: u8) -> bool {
+        let mut backoff = Backoff::new();
+        match self.lock_waiting(state, &mut || backoff.spin()) {
+            Some(locked) => locked,
+            None => poisoned(),
+        }
+    }
+
+    // Like `lock`, but calls `wait` between attempts instead of backing off,
+    // and returns None instead of panicking if the cell is poisoned.
+    pub(crate) fn lock_with(&self, wait: &mut dyn FnMut()) -> Option<bool> {
+        self.lock_waiting(self.0.load(Ordering::Acquire), wait)
+    }
+
+    fn lock_waiting(&self, mut state: u8, wait: &mut dyn FnMut()) -> Option<bool> {
+        loop {
+            match state {
+                // Another thread initialized while we were spinning.
+                READY => return Some(false),
+                POISONED => return None,
+                EAGER => accessed_before_init_all(),
+                SEALED => accessed_after_seal(),
+                // Either the lock is free or the weak exchange failed
+                // spuriously; retry right away. On LL/SC targets this loop is
+                // the only retry loop, instead of one nested inside a strong
+                // compare_exchange.
+                UNINIT => {
+                    match self.exchange_weak(UNINIT, LOCKED, Ordering::Acquire, Ordering::Acquire) {
+                        Ok(_) => {
+                            edge!(self, Locked);
+                            return Some(true);
+                        }
+                        Err(actual) => state = actual,
+                    }
+                }
+                _ => {
+                    wait();
+                    state = self.0.load(Ordering::Acquire);
+                }
+            }
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+pub(crate) fn poisoned() -> ! {
+    panic!("SpinCell instance has previously been poisoned")
+}
+
+#[cold]
+#[inline(never)]
+fn accessed_before_init_all() -> ! {
+    panic!("eager SpinCell accessed before init_all() initialized it")
+}
+
+#[cold]
+#[inline(never)]
+pub(crate) fn accessed_after_seal() -> ! {
+    panic!("SpinCell accessed after it was sealed uninitialized")
+}
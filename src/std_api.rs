@@ -0,0 +1,185 @@
+// Methods named after, and behaving like, their counterparts on
+// `std::sync::OnceLock` and `std::sync::LazyLock`.
+
+use core::mem::ManuallyDrop;
+
+use crate::state::{self, READY, UNINIT};
+use crate::wipe::wipe;
+use crate::{Initializer, Slot, SpinCell};
+
+/// A [`SpinCell`] without an initializer, filled in by [`SpinCell::set`] or
+/// [`SpinCell::get_or_init`], like `OnceLock`.
+///
+/// ```
+/// use spincell::{SpinCell, SpinOnceCell};
+///
+/// static SERIAL: SpinOnceCell<u64> = SpinOnceCell::empty();
+///
+/// assert_eq!(SpinCell::get(&SERIAL), None);
+/// assert_eq!(SpinCell::set(&SERIAL, 0x1234), Ok(()));
+/// assert_eq!(*SpinCell::wait(&SERIAL), 0x1234);
+/// ```
+pub type SpinOnceCell<T> = SpinCell<T, ()>;
+
+impl<T> SpinCell<T, ()> {
+    /// Creates an uninitialized cell with no initializer, like
+    /// `OnceLock::new`.
+    #[inline(always)]
+    pub const fn empty() -> SpinCell<T, ()> {
+        SpinCell::with_init(())
+    }
+}
+
+impl<T, G: Initializer<T>> SpinCell<T, G> {
+    /// Initializes the cell if needed and returns a reference to the value,
+    /// like `LazyLock::force`.
+    #[inline]
+    pub fn force(me: &SpinCell<T, G>) -> &T {
+        me
+    }
+
+    /// Initializes the cell if needed and returns a mutable reference to the
+    /// value, like `LazyLock::force_mut`.
+    #[inline]
+    pub fn force_mut(me: &mut SpinCell<T, G>) -> &mut T {
+        let _ = SpinCell::try_initialize(me);
+        // Safety: the cell is READY and we have exclusive access.
+        unsafe { &mut me.slot.get_mut().value }
+    }
+}
+
+impl<T, G> SpinCell<T, G> {
+    /// Initializes the cell with `value` unless it is already initialized,
+    /// like `OnceLock::set`. The same as [`SpinCell::initialize_with`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is poisoned.
+    #[inline]
+    pub fn set(me: &SpinCell<T, G>, value: T) -> Result<(), T> {
+        SpinCell::initialize_with(me, value)
+    }
+
+    /// Initializes the cell with `value` unless it is already initialized,
+    /// like `OnceCell::try_insert`, and returns a reference to the value.
+    ///
+    /// A caller that loses the race gets the winning value along with its
+    /// own back, so it can recycle the one it built:
+    ///
+    /// ```
+    /// use spincell::SpinOnceCell;
+    ///
+    /// static BUFFER: SpinOnceCell<Vec<u8>> = SpinOnceCell::empty();
+    ///
+    /// assert_eq!(SpinOnceCell::try_insert(&BUFFER, vec![1]), Ok(&vec![1]));
+    /// let (current, mut spare) = SpinOnceCell::try_insert(&BUFFER, vec![2]).unwrap_err();
+    /// assert_eq!(current, &[1]);
+    /// spare.clear();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is poisoned.
+    pub fn try_insert(me: &SpinCell<T, G>, value: T) -> Result<&T, (&T, T)> {
+        match SpinCell::begin_init(me) {
+            Some(guard) => Ok(guard.write(value)),
+            // Safety: `begin_init` only returns `None` once the cell is
+            // initialized.
+            None => Err((unsafe { me.get_unchecked() }, value)),
+        }
+    }
+
+    /// Returns the value, initializing the cell with `f` instead of the
+    /// stored initializer if it is not initialized yet, like
+    /// `OnceLock::get_or_init`.
+    ///
+    /// If `f` panics the cell stays uninitialized, with its initializer, and
+    /// the panic propagates.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// static PORT: SpinCell<u16> = SpinCell::new(|| 80);
+    /// assert_eq!(*SpinCell::get_or_init(&PORT, || 8080), 8080);
+    /// assert_eq!(*SpinCell::get_or_init(&PORT, || 443), 8080);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is poisoned.
+    pub fn get_or_init(me: &SpinCell<T, G>, f: impl FnOnce() -> T) -> &T {
+        match SpinCell::begin_init(me) {
+            Some(guard) => guard.write(f()),
+            // Safety: `begin_init` only returns `None` once the cell is
+            // initialized.
+            None => unsafe { me.get_unchecked() },
+        }
+    }
+
+    /// Returns a mutable reference to the value if the cell is initialized,
+    /// like `OnceLock::get_mut`.
+    #[inline]
+    pub fn get_mut(me: &mut SpinCell<T, G>) -> Option<&mut T> {
+        if *me.state.get_mut() != READY {
+            return None;
+        }
+        // Safety: the slot holds the value, and we have exclusive access.
+        Some(unsafe { &mut me.slot.get_mut().value })
+    }
+
+    /// Waits until another thread has initialized the cell and returns the
+    /// value, like `OnceLock::wait`. Never runs the initializer.
+    ///
+    /// Spins for as long as nobody initializes the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is or becomes poisoned.
+    pub fn wait(me: &SpinCell<T, G>) -> &T {
+        me.state.wait();
+        // Safety: the cell is READY.
+        unsafe { me.get_unchecked() }
+    }
+
+    /// Consumes the cell, returning the value if it was initialized and the
+    /// initializer otherwise, like `LazyLock::into_inner`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is poisoned or sealed.
+    pub fn into_inner(me: SpinCell<T, G>) -> Result<T, G> {
+        match SpinCell::into_value(me) {
+            Ok(value) => Ok(value),
+            Err(me) => match SpinCell::into_initializer(me) {
+                Ok(init_func) => Err(init_func),
+                Err(me) if me.state.load() == state::SEALED => state::accessed_after_seal(),
+                Err(_) => state::poisoned(),
+            },
+        }
+    }
+
+    /// Takes the value out of the cell, leaving it uninitialized with a
+    /// default initializer, like `OnceLock::take`.
+    ///
+    /// Available when the initializer type implements `Default`, as `()`
+    /// does for cells that are only ever filled with [`SpinCell::set`].
+    pub fn take(me: &mut SpinCell<T, G>) -> Option<T>
+    where
+        G: Default,
+    {
+        if *me.state.get_mut() != READY {
+            return None;
+        }
+        let slot = me.slot.get_mut();
+        // Safety: the slot holds the value, and we have exclusive access.
+        let value = unsafe { ManuallyDrop::take(&mut slot.value) };
+        unsafe { wipe(slot) };
+        *slot = Slot {
+            init_func: ManuallyDrop::new(G::default()),
+        };
+        *me.state.get_mut() = UNINIT;
+        #[cfg(feature = "testing")]
+        crate::testing::record(&me.state, crate::testing::Event::Reset);
+        Some(value)
+    }
+}
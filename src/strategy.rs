@@ -0,0 +1,45 @@
+use crate::Backoff;
+
+/// How a thread passes the time while another thread initializes a cell.
+///
+/// A waiting thread clones the strategy it was configured with and calls
+/// [`wait`](WaitStrategy::wait) on the copy each time it finds the cell
+/// still locked, so stateful strategies such as [`Backoff`] start over for
+/// every wait. Pick one per cell with [`SpinCell::builder`](crate::SpinCell::builder).
+pub trait WaitStrategy: Clone {
+    /// Waits for a while before the state is checked again.
+    fn wait(&mut self);
+}
+
+impl WaitStrategy for Backoff {
+    #[inline]
+    fn wait(&mut self) {
+        self.spin();
+    }
+}
+
+/// Issues a single spin-loop hint per round, for the lowest latency when
+/// initializers are known to be short.
+#[derive(Clone, Copy, Default)]
+pub struct Spin;
+
+impl WaitStrategy for Spin {
+    #[inline]
+    fn wait(&mut self) {
+        core::hint::spin_loop();
+    }
+}
+
+/// Gives up the rest of the time slice to the OS scheduler every round, for
+/// initializers that may take long enough to be worth descheduling for.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Default)]
+pub struct Yield;
+
+#[cfg(feature = "std")]
+impl WaitStrategy for Yield {
+    #[inline]
+    fn wait(&mut self) {
+        std::thread::yield_now();
+    }
+}
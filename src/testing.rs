@@ -0,0 +1,45 @@
+//! Helpers for testing code that uses spincell.
+//!
+//! [`ManualClock`] is a [`Clock`](crate::Clock) that only moves when told
+//! to, so tests of [`SpinTtlCell`](crate::SpinTtlCell) expiry run
+//! deterministically instead of depending on the system clock. [`CellId`]
+//! reads how often a cell has run its initializer, dropped a value and been
+//! reset, counted for every [`SpinCell`](crate::SpinCell),
+//! [`SpinMutCell`](crate::SpinMutCell), `AsyncSpinCell`,
+//! [`SpinResetCell`](crate::SpinResetCell) and
+//! [`SpinTtlCell`](crate::SpinTtlCell) while the feature is on.
+//!
+//! [`Mock`] hands out initializers that return, fail or panic as told and
+//! counts their calls, [`DropCounter`] counts the drops of values wrapped in
+//! a [`DropTracker`], and [`poisoned`] builds a cell that is already
+//! poisoned.
+//!
+//! Requires the `testing` feature. The clock also needs 64-bit atomics.
+//!
+//! ```
+//! use spincell::testing::ManualClock;
+//! use spincell::SpinTtlCell;
+//!
+//! static CLOCK: ManualClock = ManualClock::new(0);
+//! static TOKEN: SpinTtlCell<u64, &ManualClock> =
+//!     SpinTtlCell::new(60, &CLOCK, || CLOCK.now());
+//!
+//! assert_eq!(TOKEN.read(|t| *t), 0);
+//! CLOCK.advance(59);
+//! assert_eq!(TOKEN.read(|t| *t), 0);
+//! CLOCK.advance(1);
+//! assert_eq!(TOKEN.read(|t| *t), 60);
+//! ```
+
+#[cfg(target_has_atomic = "64")]
+mod clock;
+
+#[cfg(target_has_atomic = "64")]
+pub use clock::ManualClock;
+
+mod counters;
+mod mock;
+
+pub(crate) use counters::{record, release, Event};
+pub use counters::{CellId, Counts, Instrumented};
+pub use mock::{poisoned, DropCounter, DropTracker, Mock, MockInit};
@@ -0,0 +1,61 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::Clock;
+
+/// A clock whose time is set by hand.
+///
+/// Pass it to a cell by reference, so the test keeps a handle to move it.
+/// Readings may be set to any value, including ones that wrap around, which
+/// the cells handle like any other elapsed time.
+pub struct ManualClock {
+    now: AtomicU64,
+}
+
+impl ManualClock {
+    /// Creates a clock reading `start`.
+    #[inline(always)]
+    pub const fn new(start: u64) -> ManualClock {
+        ManualClock {
+            now: AtomicU64::new(start),
+        }
+    }
+
+    /// Returns the current reading.
+    #[inline]
+    pub fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+
+    /// Sets the reading to `now`.
+    #[inline]
+    pub fn set(&self, now: u64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    /// Moves the clock forward by `by`, wrapping around at `u64::MAX`, and
+    /// returns the new reading.
+    #[inline]
+    pub fn advance(&self, by: u64) -> u64 {
+        self.now.fetch_add(by, Ordering::SeqCst).wrapping_add(by)
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> ManualClock {
+        ManualClock::new(0)
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now(&self) -> u64 {
+        ManualClock::now(self)
+    }
+}
+
+impl Clock for &ManualClock {
+    #[inline]
+    fn now(&self) -> u64 {
+        ManualClock::now(self)
+    }
+}
@@ -0,0 +1,240 @@
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::state::State;
+#[cfg(feature = "async")]
+use crate::AsyncSpinCell;
+use crate::{SpinCell, SpinMutCell, SpinResetCell, SpinTtlCell};
+
+// Counts are kept in a fixed table keyed by the address of each cell's
+// state, so cells need no extra room for them and the layout stays the same
+// with the feature on. A cell claims an entry on its first event and gives
+// it back when it is dropped, unless a `CellId` was taken for it: those
+// entries are kept so the counts can still be read once the cell is gone.
+const ENTRY_COUNT: usize = 256;
+
+static ENTRIES: [Entry; ENTRY_COUNT] = [const { Entry::new() }; ENTRY_COUNT];
+
+// Held while claiming an entry, so that two events of the same cell on
+// different threads cannot claim two.
+static CLAIMING: AtomicBool = AtomicBool::new(false);
+
+// Set once a cell found no free entry; from then on some counts may be
+// missing events.
+static OVERFLOWED: AtomicBool = AtomicBool::new(false);
+
+struct Entry {
+    // Address of the state counted here, or 0 if the entry is free.
+    key: AtomicUsize,
+    // Whether a `CellId` was taken for the cell, so the entry outlives it.
+    kept: AtomicBool,
+    inits: AtomicUsize,
+    drops: AtomicUsize,
+    resets: AtomicUsize,
+}
+
+impl Entry {
+    const fn new() -> Entry {
+        Entry {
+            key: AtomicUsize::new(0),
+            kept: AtomicBool::new(false),
+            inits: AtomicUsize::new(0),
+            drops: AtomicUsize::new(0),
+            resets: AtomicUsize::new(0),
+        }
+    }
+
+    fn clear(&self) {
+        self.inits.store(0, Ordering::Relaxed);
+        self.drops.store(0, Ordering::Relaxed);
+        self.resets.store(0, Ordering::Relaxed);
+    }
+}
+
+fn key(state: &State) -> usize {
+    state as *const State as usize
+}
+
+// Returns the entry for `key`, if it has one.
+fn find(key: usize) -> Option<&'static Entry> {
+    ENTRIES
+        .iter()
+        .find(|entry| entry.key.load(Ordering::Acquire) == key)
+}
+
+// Returns the entry for `key`, claiming a free one if there is none yet.
+fn claim(key: usize) -> Option<&'static Entry> {
+    if let Some(entry) = find(key) {
+        return Some(entry);
+    }
+    while CLAIMING
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        core::hint::spin_loop();
+    }
+    let claimed = find(key).or_else(|| {
+        let entry = find(0)?;
+        entry.key.store(key, Ordering::Release);
+        Some(entry)
+    });
+    CLAIMING.store(false, Ordering::Release);
+    if claimed.is_none() {
+        OVERFLOWED.store(true, Ordering::Relaxed);
+    }
+    claimed
+}
+
+// Gives back the entry of the cell owning `state`, which is being dropped,
+// unless a `CellId` was taken for it.
+pub(crate) fn release(state: &State) {
+    if let Some(entry) = find(key(state)) {
+        if !entry.kept.load(Ordering::Relaxed) {
+            entry.clear();
+            // Release, so whoever claims the entry next sees it cleared.
+            entry.key.store(0, Ordering::Release);
+        }
+    }
+}
+
+pub(crate) enum Event {
+    Init,
+    Drop,
+    Reset,
+}
+
+// Counts `event` for the cell owning `state`.
+pub(crate) fn record(state: &State, event: Event) {
+    let Some(entry) = claim(key(state)) else {
+        return;
+    };
+    let counter = match event {
+        Event::Init => &entry.inits,
+        Event::Drop => &entry.drops,
+        Event::Reset => &entry.resets,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// What has happened to a cell so far, as returned by [`CellId::counts`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// Initializations of the cell: calls of its initializer, including
+    /// ones that panicked, and values written in by
+    /// [`SpinCell::init_in_place`] or through an
+    /// [`InitGuard`](crate::InitGuard).
+    pub inits: usize,
+    /// Values the cell dropped: when it was reset, when a value expired and
+    /// when the cell itself was dropped.
+    pub drops: usize,
+    /// Values taken out or discarded by [`SpinCell::take`] and
+    /// [`SpinResetCell::reset`].
+    pub resets: usize,
+}
+
+/// Names a cell whose [`Counts`] are wanted, and stays usable after the
+/// cell is gone.
+///
+/// ```
+/// use spincell::testing::{CellId, Counts};
+/// use spincell::SpinCell;
+///
+/// let id = {
+///     let cell = SpinCell::new(|| [1u8; 4].to_vec());
+///     assert_eq!(cell.len(), 4);
+///     CellId::of(&cell)
+/// };
+/// assert_eq!(id.counts(), Counts { inits: 1, drops: 1, resets: 0 });
+/// ```
+///
+/// Counts are kept per address. Moving a cell gives it a new id, and a cell
+/// created where a dropped one used to live continues its counts, so tests
+/// creating several cells in the same place should
+/// [`clear`](CellId::clear) them in between. The counts of a cell without
+/// an id are discarded when it is dropped. Room is kept for 256 cells at a
+/// time, counting live cells with events and cells an id was taken for; once
+/// more than that have needed one, reading counts panics, since some events
+/// were not counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellId(usize);
+
+impl CellId {
+    /// Returns the id of `cell`, keeping its counts readable after it is
+    /// dropped.
+    pub fn of<C: Instrumented + ?Sized>(cell: &C) -> CellId {
+        let id = CellId(cell.state_addr());
+        if let Some(entry) = claim(id.0) {
+            entry.kept.store(true, Ordering::Relaxed);
+        }
+        id
+    }
+
+    /// Returns the counts recorded for the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more cells needed an entry at once than there is room for,
+    /// rather than returning counts that may be missing events.
+    pub fn counts(self) -> Counts {
+        if OVERFLOWED.load(Ordering::Relaxed) {
+            panic!("more than 256 cells were counted at once, so some events were lost");
+        }
+        match find(self.0) {
+            Some(entry) => Counts {
+                inits: entry.inits.load(Ordering::Relaxed),
+                drops: entry.drops.load(Ordering::Relaxed),
+                resets: entry.resets.load(Ordering::Relaxed),
+            },
+            None => Counts::default(),
+        }
+    }
+
+    /// Sets the counts of the cell back to zero.
+    pub fn clear(self) {
+        if let Some(entry) = find(self.0) {
+            entry.clear();
+        }
+    }
+}
+
+/// Cell types whose events are counted with the `testing` feature.
+pub trait Instrumented {
+    // The address of the cell's state, which keys its counts.
+    #[doc(hidden)]
+    fn state_addr(&self) -> usize;
+}
+
+impl<T, G> Instrumented for SpinCell<T, G> {
+    #[inline]
+    fn state_addr(&self) -> usize {
+        key(&self.state)
+    }
+}
+
+impl<T, G> Instrumented for SpinMutCell<T, G> {
+    #[inline]
+    fn state_addr(&self) -> usize {
+        self.inner.state_addr()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T, F> Instrumented for AsyncSpinCell<T, F> {
+    #[inline]
+    fn state_addr(&self) -> usize {
+        self.inner.state_addr()
+    }
+}
+
+impl<T, G> Instrumented for SpinResetCell<T, G> {
+    #[inline]
+    fn state_addr(&self) -> usize {
+        key(&self.state)
+    }
+}
+
+impl<T, C, G> Instrumented for SpinTtlCell<T, C, G> {
+    #[inline]
+    fn state_addr(&self) -> usize {
+        self.cell.state_addr()
+    }
+}
@@ -0,0 +1,199 @@
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::state::POISONED;
+use crate::{Initializer, SpinCell};
+
+/// Hands out initializers with a programmed outcome and counts how many of
+/// them have been called.
+///
+/// ```
+/// use spincell::testing::{Mock, MockInit};
+/// use spincell::SpinCell;
+///
+/// static MOCK: Mock = Mock::new();
+/// static PORT: SpinCell<u16, MockInit<u16>> = SpinCell::new(MOCK.returning(8080));
+///
+/// assert_eq!(MOCK.calls(), 0);
+/// assert_eq!(*PORT + *PORT, 16160);
+/// assert_eq!(MOCK.calls(), 1);
+/// ```
+pub struct Mock {
+    calls: AtomicUsize,
+}
+
+/// An initializer handed out by a [`Mock`].
+pub struct MockInit<'a, T> {
+    mock: &'a Mock,
+    // None if the initializer is to panic.
+    outcome: Option<T>,
+}
+
+impl Mock {
+    #[inline(always)]
+    pub const fn new() -> Mock {
+        Mock {
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns how many initializers of this mock have been called,
+    /// including ones that panicked.
+    #[inline]
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Returns an initializer that builds `value`.
+    #[inline(always)]
+    pub const fn returning<T>(&self, value: T) -> MockInit<'_, T> {
+        MockInit {
+            mock: self,
+            outcome: Some(value),
+        }
+    }
+
+    /// Returns an initializer that builds `Err(error)`, for cells holding a
+    /// `Result`.
+    #[inline(always)]
+    pub const fn failing<T, E>(&self, error: E) -> MockInit<'_, Result<T, E>> {
+        MockInit {
+            mock: self,
+            outcome: Some(Err(error)),
+        }
+    }
+
+    /// Returns an initializer that panics, which poisons the cell running
+    /// it.
+    #[inline(always)]
+    pub const fn panicking<T>(&self) -> MockInit<'_, T> {
+        MockInit {
+            mock: self,
+            outcome: None,
+        }
+    }
+}
+
+impl Default for Mock {
+    fn default() -> Mock {
+        Mock::new()
+    }
+}
+
+impl<T> Initializer<T> for MockInit<'_, T> {
+    fn init(self) -> T {
+        self.mock.calls.fetch_add(1, Ordering::SeqCst);
+        match self.outcome {
+            Some(value) => value,
+            None => panic!("MockInit programmed to panic"),
+        }
+    }
+}
+
+/// Counts the values wrapped in its [`DropTracker`]s and how many of them
+/// have been dropped.
+///
+/// ```
+/// use spincell::testing::DropCounter;
+/// use spincell::SpinCell;
+///
+/// let counter = DropCounter::new();
+/// {
+///     let cell = SpinCell::new(|| counter.track(vec![1, 2, 3]));
+///     assert_eq!(cell.len(), 3);
+///     assert_eq!(counter.live(), 1);
+/// }
+/// assert_eq!((counter.created(), counter.dropped()), (1, 1));
+/// ```
+pub struct DropCounter {
+    created: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+impl DropCounter {
+    #[inline(always)]
+    pub const fn new() -> DropCounter {
+        DropCounter {
+            created: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Wraps `value` so that its drop is counted.
+    #[inline]
+    pub fn track<T>(&self, value: T) -> DropTracker<'_, T> {
+        self.created.fetch_add(1, Ordering::SeqCst);
+        DropTracker {
+            value,
+            counter: self,
+        }
+    }
+
+    /// Returns how many values have been wrapped.
+    #[inline]
+    pub fn created(&self) -> usize {
+        self.created.load(Ordering::SeqCst)
+    }
+
+    /// Returns how many wrapped values have been dropped.
+    #[inline]
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::SeqCst)
+    }
+
+    /// Returns how many wrapped values are still alive.
+    #[inline]
+    pub fn live(&self) -> usize {
+        self.created() - self.dropped()
+    }
+}
+
+impl Default for DropCounter {
+    fn default() -> DropCounter {
+        DropCounter::new()
+    }
+}
+
+/// A value whose drop is counted by a [`DropCounter`]. Derefs to the
+/// value.
+pub struct DropTracker<'a, T> {
+    value: T,
+    counter: &'a DropCounter,
+}
+
+impl<T> Deref for DropTracker<'_, T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for DropTracker<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Drop for DropTracker<'_, T> {
+    fn drop(&mut self) {
+        self.counter.dropped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Returns a cell in the state a panicking initializer leaves behind, for
+/// testing how code copes with poisoned cells without provoking a panic.
+///
+/// `init_func` is dropped. Accessing the value panics, as with any poisoned
+/// cell. Uninitialized and initialized cells come from [`SpinCell::new`]
+/// and [`SpinCell::from_value`].
+pub fn poisoned<T, G>(init_func: G) -> SpinCell<T, G> {
+    let mut cell = SpinCell::with_init(init_func);
+    // Safety: the slot holds the initializer, and a POISONED cell holds
+    // neither an initializer nor a value.
+    unsafe { ManuallyDrop::drop(&mut cell.slot.get_mut().init_func) };
+    *cell.state.get_mut() = POISONED;
+    cell
+}
@@ -0,0 +1,73 @@
+use crate::{Initializer, SpinCell};
+
+/// The initializer of a [`LazyTransform`]: an input value and the function
+/// that turns it into the cell's value.
+pub struct Transform<I, F> {
+    input: I,
+    transform: F,
+}
+
+/// A [`SpinCell`] that stores an input value until first access and then
+/// consumes it to build the value.
+///
+/// Compared to a closure capturing the input, the input type is spelled out,
+/// so the cell can be named in a static with a plain function pointer and
+/// its layout is explicit: the input and the value share storage, and the
+/// cell is as large as the larger of the two (plus the function pointer and
+/// the state byte).
+///
+/// ```
+/// use spincell::LazyTransform;
+///
+/// fn parse(text: &'static str) -> u32 {
+///     text.parse().unwrap()
+/// }
+///
+/// static PORT: LazyTransform<&str, u32> = LazyTransform::new_transform("8080", parse);
+/// assert_eq!(*PORT, 8080);
+/// ```
+pub type LazyTransform<I, T, F = fn(I) -> T> = SpinCell<T, Transform<I, F>>;
+
+impl<I, T, F: FnOnce(I) -> T> SpinCell<T, Transform<I, F>> {
+    /// Creates a cell that builds its value by calling `transform` on
+    /// `input` on first access.
+    #[inline(always)]
+    pub const fn new_transform(input: I, transform: F) -> SpinCell<T, Transform<I, F>> {
+        Self::with_init(Transform { input, transform })
+    }
+}
+
+impl<I, T, F: FnOnce(I) -> T> Initializer<T> for Transform<I, F> {
+    #[inline(always)]
+    fn init(self) -> T {
+        (self.transform)(self.input)
+    }
+}
+
+/// A [`LazyTransform`] parsing bytes, such as a serialized table placed in
+/// flash, on first access.
+///
+/// Only the reference to the input and the parse function are stored until
+/// then; the bytes themselves stay where they are.
+///
+/// ```
+/// use spincell::LazyParsed;
+///
+/// static RAW: [u8; 4] = [3, 1, 4, 1];
+///
+/// fn checksum(bytes: &'static [u8]) -> u32 {
+///     bytes.iter().map(|&b| u32::from(b)).sum()
+/// }
+///
+/// static CHECKSUM: LazyParsed<u32> = LazyParsed::new_parsed(&RAW, checksum);
+/// assert_eq!(*CHECKSUM, 9);
+/// ```
+pub type LazyParsed<T> = LazyTransform<&'static [u8], T>;
+
+impl<T> SpinCell<T, Transform<&'static [u8], fn(&'static [u8]) -> T>> {
+    /// Creates a cell that runs `parse` on `input` on first access.
+    #[inline(always)]
+    pub const fn new_parsed(input: &'static [u8], parse: fn(&'static [u8]) -> T) -> LazyParsed<T> {
+        Self::new_transform(input, parse)
+    }
+}
@@ -0,0 +1,126 @@
+use core::cell::UnsafeCell;
+
+use crate::SpinResetCell;
+
+/// A monotonic time source for [`SpinTtlCell`].
+///
+/// The unit is up to the implementation (milliseconds, timer ticks, ...);
+/// the time-to-live of a cell is given in the same unit. Readings must never
+/// go backwards, but they may wrap around.
+pub trait Clock {
+    /// Returns the current time.
+    fn now(&self) -> u64;
+}
+
+impl<F: Fn() -> u64> Clock for F {
+    #[inline]
+    fn now(&self) -> u64 {
+        self()
+    }
+}
+
+/// A [`SpinResetCell`] whose value expires after a fixed time-to-live.
+///
+/// Every access reads the clock and, once `ttl` has elapsed since the value
+/// was built, drops it and runs the initializer again before handing it
+/// out. This suits cached data that goes stale, such as sensor calibrations
+/// or resolved addresses.
+///
+/// ```
+/// use core::sync::atomic::{AtomicU64, Ordering};
+/// use spincell::SpinTtlCell;
+///
+/// static NOW: AtomicU64 = AtomicU64::new(0);
+/// static BUILT: AtomicU64 = AtomicU64::new(0);
+///
+/// fn now() -> u64 {
+///     NOW.load(Ordering::Relaxed)
+/// }
+///
+/// static CALIBRATION: SpinTtlCell<u64, fn() -> u64> =
+///     SpinTtlCell::new(100, now, || BUILT.fetch_add(1, Ordering::Relaxed));
+///
+/// assert_eq!(CALIBRATION.read(|c| *c), 0);
+/// NOW.store(99, Ordering::Relaxed);
+/// assert_eq!(CALIBRATION.read(|c| *c), 0);
+/// NOW.store(100, Ordering::Relaxed);
+/// assert_eq!(CALIBRATION.read(|c| *c), 1);
+/// ```
+pub struct SpinTtlCell<T, C, G = fn() -> T> {
+    pub(crate) cell: SpinResetCell<T, G>,
+    clock: C,
+    ttl: u64,
+    // Clock reading taken when the current value was built. Only accessed
+    // with the cell's lock held.
+    built_at: UnsafeCell<u64>,
+}
+
+// `built_at` is covered by the inner cell's lock, and the clock is only
+// read through `&C`.
+unsafe impl<T: Send, C: Sync, G: Send> Sync for SpinTtlCell<T, C, G> {}
+
+impl<T, C: Clock, G: FnMut() -> T> SpinTtlCell<T, C, G> {
+    #[inline(always)]
+    pub const fn new(ttl: u64, clock: C, init_func: G) -> SpinTtlCell<T, C, G> {
+        Self {
+            cell: SpinResetCell::new(init_func),
+            clock,
+            ttl,
+            built_at: UnsafeCell::new(0),
+        }
+    }
+
+    /// Re-initializes the value if it is missing or has expired, then runs
+    /// `f` with exclusive access to it.
+    ///
+    /// Calling back into the same cell from `f` deadlocks.
+    pub fn mutate<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.access(|value, generation| {
+            *generation += 1;
+            f(value)
+        })
+    }
+
+    /// Re-initializes the value if it is missing or has expired, then runs
+    /// `f` with it while holding the lock.
+    ///
+    /// Calling back into the same cell from `f` deadlocks.
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        self.access(|value, _| f(value))
+    }
+
+    /// Like [`read`](SpinTtlCell::read), but also passes the generation of
+    /// the value, which goes up every time the value is rebuilt or mutated.
+    ///
+    /// Calling back into the same cell from `f` deadlocks.
+    pub fn read_with_gen<R>(&self, f: impl FnOnce(&T, u64) -> R) -> R {
+        self.access(|value, generation| f(value, *generation))
+    }
+
+    /// Returns the generation of the current value, without rebuilding an
+    /// expired one.
+    pub fn generation(&self) -> u64 {
+        self.cell.generation()
+    }
+
+    /// Drops the current value, if any, regardless of its age.
+    pub fn reset(&self) {
+        self.cell.reset();
+    }
+
+    /// Checks the invariants of the underlying [`SpinResetCell`], as
+    /// [`SpinResetCell::debug_validate`] does.
+    pub fn debug_validate(&self) -> Result<(), &'static str> {
+        self.cell.debug_validate()
+    }
+
+    fn access<R>(&self, f: impl FnOnce(&mut T, &mut u64) -> R) -> R {
+        let built_at = self.built_at.get();
+        self.cell.access(
+            // Safety: both closures run with the lock held.
+            || self.clock.now().wrapping_sub(unsafe { *built_at }) >= self.ttl,
+            || unsafe { *built_at = self.clock.now() },
+            f,
+        )
+    }
+}
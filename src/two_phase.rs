@@ -0,0 +1,164 @@
+use core::ops::Deref;
+
+use crate::SpinCell;
+
+/// A cell initialized in two steps: a minimal value early on, and a full
+/// value built from it later.
+///
+/// Some resources are needed before everything required to set them up
+/// completely is available, for example a console that must work before
+/// interrupts or the heap are up. [`init_stage1`] stores the basic value of
+/// type `B`, and [`BasicView::finalize`] later builds the full value of type
+/// `F` from it. The views handed out say which stage has been reached, so
+/// code that needs the full value cannot be given a cell that only holds the
+/// basic one.
+///
+/// ```
+/// use spincell::SpinTwoPhaseCell;
+///
+/// struct Uart {
+///     base: usize,
+/// }
+///
+/// struct Console {
+///     buffered: bool,
+/// }
+///
+/// static CONSOLE: SpinTwoPhaseCell<Uart, Console> = SpinTwoPhaseCell::new();
+///
+/// // Early boot: only the UART is usable.
+/// let early = CONSOLE.init_stage1(Uart { base: 0x1000 }).ok().unwrap();
+/// assert_eq!(early.base, 0x1000);
+/// assert!(CONSOLE.full().is_none());
+///
+/// // Once the heap is up.
+/// let full = early.finalize(|_uart| Console { buffered: true });
+/// assert!(full.buffered);
+/// assert_eq!(CONSOLE.full().unwrap().basic().base, 0x1000);
+/// ```
+///
+/// [`init_stage1`]: SpinTwoPhaseCell::init_stage1
+pub struct SpinTwoPhaseCell<B, F> {
+    // Neither cell has an initializer; both are filled in through
+    // `begin_init`. `full` is only ever initialized after `basic`.
+    basic: SpinCell<B, ()>,
+    full: SpinCell<F, ()>,
+}
+
+/// Access to a [`SpinTwoPhaseCell`] that has completed the first stage.
+///
+/// Dereferences to the basic value.
+pub struct BasicView<'a, B, F> {
+    cell: &'a SpinTwoPhaseCell<B, F>,
+}
+
+/// Access to a [`SpinTwoPhaseCell`] that has completed both stages.
+///
+/// Dereferences to the full value; the basic value is available through
+/// [`basic`](FullView::basic).
+pub struct FullView<'a, B, F> {
+    basic: &'a B,
+    full: &'a F,
+}
+
+impl<B, F> SpinTwoPhaseCell<B, F> {
+    #[inline(always)]
+    pub const fn new() -> SpinTwoPhaseCell<B, F> {
+        Self {
+            basic: SpinCell::with_init(()),
+            full: SpinCell::with_init(()),
+        }
+    }
+
+    /// Completes the first stage by storing the basic value.
+    ///
+    /// Returns `Err` with `value` if the first stage was already completed.
+    pub fn init_stage1(&self, value: B) -> Result<BasicView<'_, B, F>, B> {
+        match SpinCell::begin_init(&self.basic) {
+            Some(guard) => {
+                guard.write(value);
+                Ok(BasicView { cell: self })
+            }
+            None => Err(value),
+        }
+    }
+
+    /// Returns the basic view if the first stage has been completed.
+    #[inline]
+    pub fn basic(&self) -> Option<BasicView<'_, B, F>> {
+        SpinCell::is_initialized(&self.basic).then_some(BasicView { cell: self })
+    }
+
+    /// Returns the full view if both stages have been completed.
+    #[inline]
+    pub fn full(&self) -> Option<FullView<'_, B, F>> {
+        self.basic()?.try_full()
+    }
+}
+
+impl<B, F> Default for SpinTwoPhaseCell<B, F> {
+    fn default() -> SpinTwoPhaseCell<B, F> {
+        Self::new()
+    }
+}
+
+impl<'a, B, F> BasicView<'a, B, F> {
+    /// Completes the second stage by building the full value from the basic
+    /// one, unless that has already happened.
+    ///
+    /// Concurrent calls run `f` at most once; the others wait for it and
+    /// return the same value. If `f` panics the second stage is left
+    /// incomplete and may be attempted again.
+    pub fn finalize(self, f: impl FnOnce(&'a B) -> F) -> FullView<'a, B, F> {
+        if let Some(guard) = SpinCell::begin_init(&self.cell.full) {
+            guard.write(f(self.get()));
+        }
+        // Either this call published the value or begin_init waited for
+        // the one that did.
+        self.try_full().unwrap()
+    }
+
+    /// Returns the full view if the second stage has been completed.
+    #[inline]
+    pub fn try_full(&self) -> Option<FullView<'a, B, F>> {
+        let full = &self.cell.full;
+        if !SpinCell::is_initialized(full) {
+            return None;
+        }
+        // Safety: the cell is READY.
+        Some(FullView {
+            basic: self.get(),
+            full: unsafe { full.get_unchecked() },
+        })
+    }
+
+    #[inline]
+    fn get(&self) -> &'a B {
+        // Safety: a basic view is only created once `basic` is READY.
+        unsafe { self.cell.basic.get_unchecked() }
+    }
+}
+
+impl<B, F> Deref for BasicView<'_, B, F> {
+    type Target = B;
+    #[inline]
+    fn deref(&self) -> &B {
+        self.get()
+    }
+}
+
+impl<'a, B, F> FullView<'a, B, F> {
+    /// The value stored by the first stage.
+    #[inline]
+    pub fn basic(&self) -> &'a B {
+        self.basic
+    }
+}
+
+impl<B, F> Deref for FullView<'_, B, F> {
+    type Target = F;
+    #[inline]
+    fn deref(&self) -> &F {
+        self.full
+    }
+}
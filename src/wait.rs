@@ -0,0 +1,311 @@
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use crate::backoff::Backoff;
+use crate::state::{State, LOCKED};
+use crate::{Initializer, SpinCell};
+
+// Wakers of tasks waiting for a locked cell. Cells carry no room for a
+// waiter list, so waiters park their waker in one of a fixed number of
+// global slots, keyed by the address of the cell's state. Releasing the lock
+// wakes every slot with a matching key.
+//
+// When all slots are taken a waiter falls back to waking itself right away,
+// which turns it into a task that yields on every poll instead of one that
+// blocks the executor.
+const SLOT_COUNT: usize = 32;
+
+static SLOTS: [WakerSlot; SLOT_COUNT] = [const { WakerSlot::new() }; SLOT_COUNT];
+
+// Number of claimed slots, so that releasing a lock nobody waits for does not
+// have to scan them.
+static CLAIMED: AtomicUsize = AtomicUsize::new(0);
+
+// ThreadSanitizer does not model fences, and would miss the ordering the
+// two below provide. Under it they become SeqCst read-modify-writes of this
+// one word instead, which it does understand and which order at least as
+// strongly.
+#[cfg(feature = "nightly")]
+#[cfg_attr(feature = "nightly", cfg(sanitize = "thread"))]
+static FENCE: AtomicUsize = AtomicUsize::new(0);
+
+#[inline(always)]
+fn seq_cst_fence() {
+    #[cfg_attr(feature = "nightly", cfg(not(sanitize = "thread")))]
+    core::sync::atomic::fence(Ordering::SeqCst);
+    #[cfg(feature = "nightly")]
+    #[cfg_attr(feature = "nightly", cfg(sanitize = "thread"))]
+    FENCE.fetch_add(0, Ordering::SeqCst);
+}
+
+struct WakerSlot {
+    // Address of the state being waited for, or 0 if the slot is free.
+    key: AtomicUsize,
+    // Protects `waker`.
+    lock: AtomicBool,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+// Safety: `waker` is only accessed with `lock` held.
+unsafe impl Sync for WakerSlot {}
+
+impl WakerSlot {
+    const fn new() -> WakerSlot {
+        WakerSlot {
+            key: AtomicUsize::new(0),
+            lock: AtomicBool::new(false),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    fn with_waker<R>(&self, f: impl FnOnce(&mut Option<Waker>) -> R) -> R {
+        let mut backoff = Backoff::new();
+        while self
+            .lock
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            backoff.spin();
+        }
+        // Safety: the lock is held.
+        let result = f(unsafe { &mut *self.waker.get() });
+        self.lock.store(false, Ordering::Release);
+        result
+    }
+}
+
+fn key(state: &State) -> usize {
+    state as *const State as usize
+}
+
+// Wakes every task waiting for `state`. Called after each change of the state
+// that ends a lock.
+pub(crate) fn wake(state: &State) {
+    // Pairs with the fence in `Waiter::register`: either the waiter sees the
+    // new state, or this sees its claimed slot.
+    seq_cst_fence();
+    if CLAIMED.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let key = key(state);
+    for slot in &SLOTS {
+        if slot.key.load(Ordering::Relaxed) == key {
+            if let Some(waker) = slot.with_waker(Option::take) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+// A task's claim on a slot, released on drop.
+pub(crate) struct Waiter {
+    slot: Option<&'static WakerSlot>,
+}
+
+impl Waiter {
+    pub(crate) const fn new() -> Waiter {
+        Waiter { slot: None }
+    }
+
+    // Arranges for `waker` to be woken the next time the lock on `state` is
+    // released. Returns false if no slot was free, in which case the caller
+    // has to poll again on its own.
+    //
+    // The caller must re-check the state afterwards; a release that happened
+    // before the waker was stored is not reported.
+    pub(crate) fn register(&mut self, state: &State, waker: &Waker) -> bool {
+        let slot = match self.slot {
+            Some(slot) => slot,
+            None => match claim(key(state)) {
+                Some(slot) => *self.slot.insert(slot),
+                None => return false,
+            },
+        };
+        slot.with_waker(|stored| match stored {
+            Some(stored) if stored.will_wake(waker) => {}
+            _ => *stored = Some(waker.clone()),
+        });
+        seq_cst_fence();
+        true
+    }
+
+    // Gives the slot back, if one is claimed.
+    pub(crate) fn release(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            drop(slot.with_waker(Option::take));
+            slot.key.store(0, Ordering::Relaxed);
+            CLAIMED.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+fn claim(key: usize) -> Option<&'static WakerSlot> {
+    let slot = SLOTS.iter().find(|slot| {
+        slot.key
+            .compare_exchange(0, key, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    })?;
+    CLAIMED.fetch_add(1, Ordering::Relaxed);
+    Some(slot)
+}
+
+/// Future returned by [`SpinCell::get_async`].
+#[must_use = "futures do nothing unless polled"]
+pub struct GetAsync<'a, T, G = fn() -> T> {
+    cell: &'a SpinCell<T, G>,
+    waiter: Waiter,
+}
+
+impl<'a, T, G: Initializer<T>> Future for GetAsync<'a, T, G> {
+    type Output = &'a T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let cell = this.cell;
+        loop {
+            match cell.state.try_lock() {
+                Some(true) => {
+                    this.waiter.release();
+                    let value_ptr = cell.value_ptr();
+                    let mut observer = None;
+                    cell.initialize_locked_by(|init_func| {
+                        let (value, f) = init_func.init_observed(value_ptr);
+                        observer = f;
+                        value
+                    });
+                    // Safety: the value has just been published.
+                    let value = unsafe { cell.get_unchecked() };
+                    if let Some(observer) = observer {
+                        observer(value);
+                    }
+                    return Poll::Ready(value);
+                }
+                Some(false) => {
+                    this.waiter.release();
+                    // Safety: `try_lock` observed READY with Acquire.
+                    return Poll::Ready(unsafe { cell.get_unchecked() });
+                }
+                None => {}
+            }
+            if !this.waiter.register(&cell.state, cx.waker()) {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            if cell.state.load() == LOCKED {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+/// Future returned by [`SpinCell::initialized`].
+#[must_use = "futures do nothing unless polled"]
+pub struct Initialized<'a, T, G = fn() -> T> {
+    cell: &'a SpinCell<T, G>,
+    waiter: Waiter,
+}
+
+impl<'a, T, G> Future for Initialized<'a, T, G> {
+    type Output = &'a T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let cell = this.cell;
+        if !cell.state.is_ready_checked() {
+            if !this.waiter.register(&cell.state, cx.waker()) {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            if !cell.state.is_ready_checked() {
+                return Poll::Pending;
+            }
+        }
+        this.waiter.release();
+        // Safety: the cell was observed READY with Acquire.
+        Poll::Ready(unsafe { cell.get_unchecked() })
+    }
+}
+
+impl<T, G> SpinCell<T, G> {
+    /// Returns a future that resolves to the value once someone else has
+    /// initialized the cell.
+    ///
+    /// Unlike [`SpinCell::get_async`] this never runs the initializer, so a
+    /// supervisor can wait for a subsystem to come up without bringing it up
+    /// itself. The future panics if the initializer panics.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// static NETWORK: SpinCell<&str> = SpinCell::new(|| "up");
+    ///
+    /// async fn supervisor() {
+    ///     let status = SpinCell::initialized(&NETWORK).await;
+    ///     assert_eq!(*status, "up");
+    /// }
+    /// # let _ = supervisor();
+    /// ```
+    pub fn initialized(me: &SpinCell<T, G>) -> Initialized<'_, T, G> {
+        Initialized {
+            cell: me,
+            waiter: Waiter::new(),
+        }
+    }
+
+    /// Returns [`Poll::Ready`] with the value if the cell is initialized
+    /// and [`Poll::Pending`] otherwise.
+    ///
+    /// This is a non-blocking check for hand-written poll loops that do not
+    /// have a waker to register; it never initializes the cell. Panics if
+    /// the initializer panicked.
+    #[inline]
+    pub fn poll_ready(me: &SpinCell<T, G>) -> Poll<&T> {
+        if me.state.is_ready_checked() {
+            // Safety: the cell was observed READY with Acquire.
+            Poll::Ready(unsafe { me.get_unchecked() })
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<T, G: Initializer<T>> SpinCell<T, G> {
+    /// Returns a future that initializes the cell if needed and resolves to
+    /// the value.
+    ///
+    /// Behaves like dereferencing the cell, except that while another thread
+    /// or task is running the initializer the future returns
+    /// [`Poll::Pending`] and is woken once the initializer finishes, instead
+    /// of spinning. If nobody is initializing the cell, the initializer runs
+    /// synchronously inside `poll`.
+    ///
+    /// At most 32 tasks across the program can be waiting for cells at any
+    /// one time. Beyond that, a waiting future wakes itself immediately and
+    /// is polled again by the executor, which still does not block it.
+    ///
+    /// ```
+    /// use spincell::SpinCell;
+    ///
+    /// static CONFIG: SpinCell<u32> = SpinCell::new(|| 8);
+    ///
+    /// async fn workers() -> u32 {
+    ///     *SpinCell::get_async(&CONFIG).await
+    /// }
+    /// # let _ = workers();
+    /// ```
+    pub fn get_async(me: &SpinCell<T, G>) -> GetAsync<'_, T, G> {
+        GetAsync {
+            cell: me,
+            waiter: Waiter::new(),
+        }
+    }
+}
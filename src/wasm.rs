@@ -0,0 +1,68 @@
+use core::arch::wasm32::{memory_atomic_notify, memory_atomic_wait32};
+use core::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+
+use crate::WaitStrategy;
+
+// Every cell wakes parked threads through this one word: unlocking a cell
+// while threads are parked bumps it and notifies everyone waiting on it.
+static GENERATION: AtomicI32 = AtomicI32::new(0);
+// The number of threads parked on GENERATION, so that unlocking skips the
+// notification when nobody waits.
+static PARKED: AtomicU32 = AtomicU32::new(0);
+
+// Rounds spent spinning before the first park.
+const SPIN_ROUNDS: u32 = 64;
+// Parked threads check the cell again after this long even without a
+// notification, for the rare case that the cell was unlocked between
+// their last check and parking.
+const TIMEOUT_NS: i64 = 1_000_000;
+
+/// Waits with `memory.atomic.wait32` on WebAssembly with shared memory, so
+/// workers waiting for an expensive initializer sleep instead of burning
+/// CPU.
+///
+/// Spins briefly first, then parks until a cell is unlocked. Use it with
+/// [`SpinCell::builder`](crate::SpinCell::builder) for cells accessed from
+/// web workers. Browsers do not allow the main thread to wait, so cells
+/// the main thread may have to wait for must keep a spinning strategy.
+///
+/// Requires the `nightly` feature and a target with the `atomics` target
+/// feature.
+#[derive(Clone, Copy, Default)]
+pub struct AtomicWait {
+    rounds: u32,
+}
+
+impl AtomicWait {
+    #[inline(always)]
+    pub const fn new() -> AtomicWait {
+        AtomicWait { rounds: 0 }
+    }
+}
+
+impl WaitStrategy for AtomicWait {
+    fn wait(&mut self) {
+        if self.rounds < SPIN_ROUNDS {
+            self.rounds += 1;
+            core::hint::spin_loop();
+            return;
+        }
+        PARKED.fetch_add(1, Ordering::SeqCst);
+        let generation = GENERATION.load(Ordering::SeqCst);
+        // Safety: GENERATION is an aligned i32 that lives for the whole
+        // program.
+        unsafe { memory_atomic_wait32(GENERATION.as_ptr(), generation, TIMEOUT_NS) };
+        PARKED.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Wakes the threads parked by `AtomicWait`, after a cell's state left
+// LOCKED.
+#[inline]
+pub(crate) fn wake() {
+    if PARKED.load(Ordering::SeqCst) != 0 {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+        // Safety: as above.
+        unsafe { memory_atomic_notify(GENERATION.as_ptr(), u32::MAX) };
+    }
+}
@@ -0,0 +1,22 @@
+// With the `zeroize` feature, overwrites the storage of a value that has
+// just been dropped or moved out with zeros, so that secrets do not linger
+// in memory the cell keeps. Without the feature this does nothing.
+//
+// The writes are volatile, so they are not removed as dead stores even
+// though nothing reads the storage afterwards.
+#[inline(always)]
+pub(crate) unsafe fn wipe<T>(value: *mut T) {
+    #[cfg(feature = "zeroize")]
+    zero(value.cast(), core::mem::size_of::<T>());
+    let _ = value;
+}
+
+// Overwrites `len` bytes at `bytes` with zeros, regardless of the feature.
+#[cfg_attr(not(any(feature = "zeroize", feature = "std")), allow(dead_code))]
+#[inline]
+pub(crate) unsafe fn zero(bytes: *mut u8, len: usize) {
+    for i in 0..len {
+        bytes.add(i).write_volatile(0);
+    }
+    core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+}
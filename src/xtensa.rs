@@ -0,0 +1,63 @@
+use core::arch::asm;
+
+use crate::WaitStrategy;
+
+// Issued by `Backoff` instead of `spin_loop`, which emits nothing on
+// Xtensa. `memw` waits for this core's pending loads and stores, so the
+// next load of the state sees what the other core published as soon as
+// possible instead of spinning on a stale value.
+#[inline(always)]
+pub(crate) fn memw() {
+    unsafe { asm!("memw", options(nostack, preserves_flags)) };
+}
+
+// Rounds spent spinning before the first `waiti`.
+const SPIN_ROUNDS: u32 = 16;
+
+/// Waits in the low-power `waiti` state on Xtensa cores such as the ESP32,
+/// instead of busy looping.
+///
+/// After spinning briefly, each round sleeps until the next interrupt, so a
+/// core waiting for the other core's initializer draws much less power but
+/// only notices the value at its next interrupt, typically the scheduler
+/// tick. It suits long initializers on systems with a periodic interrupt;
+/// without one the core may sleep indefinitely. Interrupts are enabled
+/// while the core sleeps, and the interrupt level of the caller is restored
+/// afterwards.
+///
+/// Use it with [`SpinCell::builder`](crate::SpinCell::builder). Requires
+/// the `nightly` feature.
+#[derive(Clone, Copy, Default)]
+pub struct WaitForInterrupt {
+    rounds: u32,
+}
+
+impl WaitForInterrupt {
+    #[inline(always)]
+    pub const fn new() -> WaitForInterrupt {
+        WaitForInterrupt { rounds: 0 }
+    }
+}
+
+impl WaitStrategy for WaitForInterrupt {
+    fn wait(&mut self) {
+        if self.rounds < SPIN_ROUNDS {
+            self.rounds += 1;
+            memw();
+            return;
+        }
+        // Save PS, sleep with all interrupts enabled, then put back the
+        // interrupt level the caller had.
+        unsafe {
+            asm!(
+                "memw",
+                "rsr.ps {ps}",
+                "waiti 0",
+                "wsr.ps {ps}",
+                "rsync",
+                ps = out(reg) _,
+                options(nostack),
+            )
+        };
+    }
+}
@@ -0,0 +1,170 @@
+// Codegen regression tests for the initialized access path.
+//
+// The library and a small shim crate that dereferences a `SpinCell<u32>` are
+// compiled with optimizations, and the emitted assembly is checked: up to
+// the first return, the function must be a load of the state byte, a
+// compare, a conditional branch and a load of the value. No atomic
+// read-modify-write, store or call may appear on that path.
+//
+// Targets whose standard library is not installed are skipped.
+// Not built under Miri, which cannot run the compiler.
+#![cfg(not(miri))]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const SHIM: &str = r#"
+#![no_std]
+use spincell::SpinCell;
+
+#[no_mangle]
+pub fn spincell_codegen_deref(cell: &SpinCell<u32>) -> u32 {
+    **cell
+}
+"#;
+
+struct Arch {
+    target: &'static str,
+    extra_args: &'static [&'static str],
+    is_branch: fn(&str) -> bool,
+    is_return: fn(&str) -> bool,
+    forbidden: &'static [&'static str],
+}
+
+const X86_64: Arch = Arch {
+    target: "x86_64-unknown-linux-gnu",
+    extra_args: &["-C", "llvm-args=-x86-asm-syntax=intel"],
+    is_branch: |insn| insn.starts_with('j') && !insn.starts_with("jmp"),
+    is_return: |insn| insn == "ret",
+    forbidden: &["lock", "xchg", "call", "mfence"],
+};
+
+const AARCH64: Arch = Arch {
+    target: "aarch64-unknown-linux-gnu",
+    extra_args: &[],
+    is_branch: |insn| insn.starts_with("b.") || insn.starts_with("cb") || insn.starts_with("tb"),
+    is_return: |insn| insn == "ret",
+    forbidden: &[
+        "ldaxr", "stlxr", "ldxr", "stxr", "cas", "swp", "ldadd", "bl", "dmb", "st",
+    ],
+};
+
+// Arguments enabling the same library features this test was built with.
+fn feature_args() -> Vec<String> {
+    let mut features = Vec::new();
+    if cfg!(feature = "nightly") {
+        features.push("nightly");
+    }
+    features
+        .into_iter()
+        .flat_map(|feature| ["--cfg".to_string(), format!("feature=\"{feature}\"")])
+        .collect()
+}
+
+fn rustc(args: &[&str], extra: &[String]) {
+    let status = Command::new("rustc")
+        .args(["--edition", "2021", "-C", "opt-level=3"])
+        .args(args)
+        .args(extra)
+        .status()
+        .unwrap();
+    assert!(status.success(), "rustc {args:?} failed");
+}
+
+fn target_installed(target: &str) -> bool {
+    let output = Command::new("rustc")
+        .args(["--print", "target-libdir", "--target", target])
+        .output()
+        .unwrap();
+    let libdir = String::from_utf8(output.stdout).unwrap();
+    output.status.success() && Path::new(libdir.trim()).exists()
+}
+
+// Compiles the shim for `arch` and returns the instructions of the shim
+// function, one per entry, without directives or labels.
+fn shim_instructions(arch: &Arch) -> Vec<String> {
+    let out_dir = std::env::temp_dir().join(format!(
+        "spincell-codegen-{}-{}",
+        std::process::id(),
+        arch.target
+    ));
+    fs::create_dir_all(&out_dir).unwrap();
+    let src = out_dir.join("shim.rs");
+    let asm = out_dir.join("shim.s");
+    fs::write(&src, SHIM).unwrap();
+
+    // Build the library from source for the target, so the check works
+    // for any target and does not depend on what happens to be in target/.
+    let lib = concat!(env!("CARGO_MANIFEST_DIR"), "/src/lib.rs");
+    let out = out_dir.to_str().unwrap();
+    let target = ["--target", arch.target];
+    let mut lib_args = vec!["--crate-type", "rlib", "--crate-name", "spincell"];
+    lib_args.extend(target);
+    lib_args.extend(["--out-dir", out, lib]);
+    rustc(&lib_args, &feature_args());
+
+    let rlib = out_dir.join("libspincell.rlib");
+    let extern_arg = format!("spincell={}", rlib.display());
+    let mut shim_args = vec!["--crate-type", "rlib", "--crate-name", "shim"];
+    shim_args.extend(target);
+    shim_args.extend(arch.extra_args);
+    shim_args.extend(["--emit", "asm", "--extern", &extern_arg]);
+    shim_args.extend(["-o", asm.to_str().unwrap(), src.to_str().unwrap()]);
+    rustc(&shim_args, &[]);
+
+    let text = fs::read_to_string(&asm).unwrap();
+    let _ = fs::remove_dir_all(&out_dir);
+    text.lines()
+        .skip_while(|line| !line.starts_with("spincell_codegen_deref:"))
+        .skip(1)
+        .map(str::trim)
+        .take_while(|line| !line.starts_with(".Lfunc_end") && !line.starts_with(".size"))
+        .filter(|line| !line.is_empty() && !line.starts_with('.') && !line.ends_with(':'))
+        .map(|line| line.split("//").next().unwrap().trim().to_string())
+        .collect()
+}
+
+fn check_fast_path(arch: &Arch) {
+    if !target_installed(arch.target) {
+        eprintln!("skipping codegen check: {} is not installed", arch.target);
+        return;
+    }
+
+    let insns = shim_instructions(arch);
+    let ret = insns
+        .iter()
+        .position(|insn| (arch.is_return)(insn))
+        .expect("shim has no return");
+    let hot = &insns[..=ret];
+    let mnemonics: Vec<&str> = hot
+        .iter()
+        .map(|insn| insn.split_whitespace().next().unwrap())
+        .collect();
+
+    // load, compare, branch, load, return; x86 may fuse the first two.
+    assert!(hot.len() <= 5, "fast path grew: {hot:#?}");
+    let branches = mnemonics.iter().filter(|m| (arch.is_branch)(m)).count();
+    assert_eq!(
+        branches, 1,
+        "fast path should branch exactly once: {hot:#?}"
+    );
+    for mnemonic in &mnemonics {
+        for forbidden in arch.forbidden {
+            assert!(
+                !mnemonic.starts_with(forbidden),
+                "fast path contains `{mnemonic}`: {hot:#?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_deref_fast_path_x86_64() {
+    check_fast_path(&X86_64);
+}
+
+#[test]
+fn test_deref_fast_path_aarch64() {
+    check_fast_path(&AARCH64);
+}
@@ -0,0 +1,164 @@
+// Threaded stress tests, ignored by default. Run them with
+//
+//     cargo test --release --test stress -- --ignored
+//
+// Each test hammers shared cells from many threads until a deadline and
+// checks exactly-once semantics with atomic counters. SPINCELL_STRESS_SECS
+// sets how long each test runs (default 2) and SPINCELL_STRESS_THREADS how
+// many threads it uses (default twice the available parallelism, at least
+// 4), so CI machines and many-core ARM boxes can soak for longer.
+
+use std::env;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Barrier;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use spincell::{SpinCell, SpinMutCell, SpinResetCell};
+
+fn duration() -> Duration {
+    let secs = env::var("SPINCELL_STRESS_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(2);
+    Duration::from_secs(secs)
+}
+
+fn threads() -> usize {
+    env::var("SPINCELL_STRESS_THREADS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| {
+            let cores = thread::available_parallelism().map_or(1, |n| n.get());
+            (cores * 2).max(4)
+        })
+}
+
+// Runs `f` on every thread with its index until the deadline passes.
+fn storm(f: impl Fn(usize) + Sync) {
+    let deadline = Instant::now() + duration();
+    thread::scope(|s| {
+        for index in 0..threads() {
+            let f = &f;
+            s.spawn(move || {
+                while Instant::now() < deadline {
+                    f(index);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+#[ignore]
+fn stress_init_races() {
+    let threads = threads();
+    let deadline = Instant::now() + duration();
+    let mut rounds = 0u64;
+    while Instant::now() < deadline {
+        let round = rounds;
+        let runs = AtomicUsize::new(0);
+        let cell = SpinCell::new(|| {
+            runs.fetch_add(1, Ordering::Relaxed);
+            round
+        });
+        let barrier = Barrier::new(threads);
+        thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| {
+                    barrier.wait();
+                    assert_eq!(*cell, round);
+                });
+            }
+        });
+        assert_eq!(runs.load(Ordering::Relaxed), 1, "round {rounds}");
+        rounds += 1;
+    }
+}
+
+#[test]
+#[ignore]
+fn stress_read_storm() {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    static TABLE: SpinCell<[u64; 64]> = SpinCell::new(|| {
+        RUNS.fetch_add(1, Ordering::Relaxed);
+        core::array::from_fn(|i| (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    });
+    let counter = SpinMutCell::new(|| 0u64);
+    let increments = AtomicU64::new(0);
+    storm(|index| {
+        for (i, &entry) in TABLE.iter().enumerate() {
+            assert_eq!(entry, (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        }
+        if index % 4 == 0 {
+            counter.mutate(|n| {
+                *n += 1;
+                increments.fetch_add(1, Ordering::Relaxed);
+            });
+        } else {
+            counter.read(|n| assert_eq!(*n, increments.load(Ordering::Relaxed)));
+        }
+    });
+    assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+    assert_eq!(counter.read(|n| *n), increments.load(Ordering::Relaxed));
+}
+
+// A value whose halves must always agree, to catch torn or stale reads.
+struct Pair {
+    value: u64,
+    check: u64,
+    live: &'static AtomicUsize,
+}
+
+impl Drop for Pair {
+    fn drop(&mut self) {
+        self.live.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+#[ignore]
+fn stress_reset_storm() {
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    static RUNS: AtomicU64 = AtomicU64::new(0);
+    let cell = SpinResetCell::new(|| {
+        let run = RUNS.fetch_add(1, Ordering::Relaxed);
+        LIVE.fetch_add(1, Ordering::Relaxed);
+        Pair {
+            value: run,
+            check: !run,
+            live: &LIVE,
+        }
+    });
+    let resets = AtomicU64::new(0);
+    let threads = threads();
+    storm(|index| {
+        let mut last = 0;
+        for step in 0..64u64 {
+            match (index as u64 + step) % 8 {
+                0 => {
+                    cell.reset();
+                    resets.fetch_add(1, Ordering::Relaxed);
+                }
+                1 => cell.mutate(|pair| {
+                    pair.value = pair.value.wrapping_add(1);
+                    pair.check = !pair.value;
+                }),
+                _ => {
+                    let generation = cell.read_with_gen(|pair, generation| {
+                        assert_eq!(pair.check, !pair.value);
+                        generation
+                    });
+                    assert!(generation >= last, "generations went backwards");
+                    last = generation;
+                }
+            }
+            // A reset drops the old value after unlocking, so each
+            // thread may be dropping one while the cell holds another.
+            assert!(LIVE.load(Ordering::Relaxed) <= threads + 1);
+        }
+    });
+    assert!(RUNS.load(Ordering::Relaxed) <= resets.load(Ordering::Relaxed) + 1);
+    drop(cell);
+    assert_eq!(LIVE.load(Ordering::Relaxed), 0);
+}
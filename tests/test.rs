@@ -1,4 +1,5 @@
 #![no_std]
+#![cfg_attr(feature = "nightly", feature(type_alias_impl_trait))]
 use spincell::SpinCell;
 
 #[test]
@@ -35,3 +36,2560 @@ mod droptest {
         assert_eq!(COUNTER.load(Ordering::Acquire), 1);
     }
 }
+
+#[cfg(test)]
+mod poisontest {
+    extern crate std;
+
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use spincell::SpinCell;
+
+    #[test]
+    fn test_panicking_initializer_poisons() {
+        let cell: SpinCell<u32, _> = SpinCell::new(|| panic!("init failed"));
+        assert!(catch_unwind(AssertUnwindSafe(|| *cell)).is_err());
+        // The second access must not run the (consumed) initializer again.
+        assert!(catch_unwind(AssertUnwindSafe(|| *cell)).is_err());
+    }
+
+    #[test]
+    fn test_poisoned_cell_validates_when_unwinding() {
+        let cell: SpinCell<u32, _> = SpinCell::new(|| panic!("init failed"));
+        assert!(catch_unwind(AssertUnwindSafe(|| *cell)).is_err());
+        assert_eq!(SpinCell::debug_validate(&cell), Ok(()));
+    }
+}
+
+#[test]
+fn test_initializer_shares_value_storage() {
+    let table = [7u8; 256];
+    let cell = SpinCell::new(move || table);
+    // One state byte plus a single 256-byte slot, not two of them.
+    assert_eq!(core::mem::size_of_val(&cell), 257);
+    assert_eq!(cell[255], 7);
+}
+
+#[test]
+fn test_cache_padded_cells_do_not_share_lines() {
+    use spincell::CachePadded;
+
+    static CELLS: [CachePadded<SpinCell<u32>>; 2] = [
+        CachePadded::new(SpinCell::new(|| 1)),
+        CachePadded::new(SpinCell::new(|| 2)),
+    ];
+    let align = core::mem::align_of::<CachePadded<SpinCell<u32>>>();
+    assert!(align >= 64);
+    let distance = &CELLS[1] as *const _ as usize - &CELLS[0] as *const _ as usize;
+    assert!(distance >= align);
+    assert_eq!(**CELLS[0] + **CELLS[1], 3);
+}
+
+#[test]
+fn test_debug_validate_accepts_healthy_cells() {
+    use spincell::{SpinMutCell, SpinResetCell};
+
+    let cell = SpinCell::new(|| 1u32);
+    assert_eq!(SpinCell::debug_validate(&cell), Ok(()));
+    assert_eq!(*cell, 1);
+    assert_eq!(SpinCell::debug_validate(&cell), Ok(()));
+    assert_eq!(
+        SpinCell::debug_validate(&SpinCell::<u32>::eager(|| 1)),
+        Ok(())
+    );
+
+    let reset = SpinResetCell::new(|| 1u32);
+    reset.read(|_| assert_eq!(reset.debug_validate(), Ok(())));
+    reset.reset();
+    assert_eq!(reset.debug_validate(), Ok(()));
+
+    let mutable = SpinMutCell::new(|| 1u32);
+    mutable.mutate(|n| *n += 1);
+    assert_eq!(mutable.debug_validate(), Ok(()));
+}
+
+#[test]
+fn test_debug_validate_reports_overwritten_state() {
+    let mut cell: SpinCell<u32, fn() -> u32> = SpinCell::new(|| 1);
+    let state = (&mut cell as *mut SpinCell<u32, fn() -> u32>).cast::<u8>();
+    // The state byte is at offset 0 of the repr(C) cell.
+    unsafe { state.write(0xa5) };
+    assert_eq!(
+        SpinCell::debug_validate(&cell),
+        Err("the state byte holds no known state")
+    );
+    unsafe { state.write(0) };
+    assert_eq!(SpinCell::debug_validate(&cell), Ok(()));
+    assert_eq!(*cell, 1);
+}
+
+// Miri can call neither mprotect nor sysconf from the tests below, nor
+// resolve the linker-defined section bounds.
+#[cfg(all(target_os = "linux", not(miri)))]
+mod fastpathtest {
+    extern crate std;
+
+    use core::ffi::{c_int, c_long, c_void};
+    use std::alloc::{alloc, dealloc, Layout};
+
+    use spincell::SpinCell;
+
+    const PROT_READ: c_int = 1;
+    const PROT_WRITE: c_int = 2;
+    const SC_PAGESIZE: c_int = 30;
+
+    extern "C" {
+        fn mprotect(addr: *mut c_void, len: usize, prot: c_int) -> c_int;
+        fn sysconf(name: c_int) -> c_long;
+    }
+
+    #[test]
+    fn test_initialized_read_does_not_write() {
+        let page_size = unsafe { sysconf(SC_PAGESIZE) } as usize;
+        let layout = Layout::from_size_align(page_size, page_size).unwrap();
+        let page = unsafe { alloc(layout) };
+        assert!(!page.is_null());
+        let ptr = page as *mut SpinCell<u64>;
+        unsafe { ptr.write(SpinCell::new(|| 42)) };
+        let cell = unsafe { &*ptr };
+        assert_eq!(**cell, 42);
+
+        // Any store or read-modify-write on the now read-only page faults.
+        assert_eq!(unsafe { mprotect(page.cast(), page_size, PROT_READ) }, 0);
+        for _ in 0..1000 {
+            assert_eq!(**cell, 42);
+        }
+        assert_eq!(SpinCell::try_initialize(cell), Err(()));
+
+        unsafe {
+            let prot = PROT_READ | PROT_WRITE;
+            assert_eq!(mprotect(page.cast(), page_size, prot), 0);
+            ptr.drop_in_place();
+            dealloc(page, layout);
+        }
+    }
+}
+
+#[test]
+fn test_fn_cell_table() {
+    use spincell::SpinFnCell;
+
+    fn one() -> u32 {
+        1
+    }
+    fn two() -> u32 {
+        2
+    }
+
+    static TABLE: [SpinFnCell<u32>; 2] = [SpinFnCell::new(one), SpinFnCell::new(two)];
+    assert_eq!(*TABLE[0] + *TABLE[1], 3);
+}
+
+#[test]
+fn test_zero_sized_value_is_a_state_byte() {
+    fn mark() {}
+
+    let once = SpinCell::new(mark);
+    assert_eq!(core::mem::size_of_val(&once), 1);
+    *once;
+    assert_eq!(SpinCell::try_initialize(&once), Err(()));
+    // With a function pointer initializer only the pointer and the padded
+    // state byte remain.
+    assert_eq!(
+        core::mem::size_of::<SpinCell<()>>(),
+        2 * core::mem::size_of::<fn()>()
+    );
+}
+
+#[cfg(test)]
+mod racetest {
+    extern crate std;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+
+    use spincell::{SpinCell, SpinMutCell};
+
+    #[test]
+    fn test_racing_threads_initialize_once() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let cell = SpinCell::new(|| {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            // Keep the lock held long enough for the others to back off.
+            thread::yield_now();
+            7u32
+        });
+        let barrier = Barrier::new(8);
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    barrier.wait();
+                    assert_eq!(*cell, 7);
+                });
+            }
+        });
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_init_task_on_another_thread() {
+        static CELL: SpinCell<u32> = SpinCell::new(|| 21);
+        assert_eq!(SpinCell::get(&CELL), None);
+        thread::spawn(SpinCell::init_task(&CELL)).join().unwrap();
+        assert_eq!(SpinCell::get(&CELL), Some(&21));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_background_init() {
+        static CELL: SpinCell<u32> = SpinCell::new(|| {
+            thread::yield_now();
+            22
+        });
+        let handle = SpinCell::background_init(&CELL);
+        assert_eq!(*CELL, 22);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_mutate_is_exclusive() {
+        let cell = SpinMutCell::new(|| 0u64);
+        let barrier = Barrier::new(4);
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    barrier.wait();
+                    for _ in 0..100 {
+                        // A non-atomic read-modify-write split across a
+                        // yield would lose updates without the lock.
+                        cell.mutate(|n| {
+                            let seen = *n;
+                            thread::yield_now();
+                            *n = seen + 1;
+                        });
+                    }
+                });
+            }
+        });
+        assert_eq!(cell.read(|n| *n), 400);
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_boxed_cell_stores_a_pointer() {
+    use spincell::BoxedSpinCell;
+
+    static TABLE: BoxedSpinCell<[u64; 4096]> = BoxedSpinCell::new(|| [9; 4096]);
+    assert!(core::mem::size_of_val(&TABLE) < 64);
+    assert_eq!(TABLE[4095], 9);
+    assert_eq!(BoxedSpinCell::try_initialize(&TABLE), Err(()));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_leak() {
+    extern crate alloc;
+
+    use alloc::boxed::Box;
+    use spincell::BoxedSpinCell;
+
+    let boxed = BoxedSpinCell::new(|| [7u32; 16]);
+    let table: &'static [u32; 16] = BoxedSpinCell::leak(boxed);
+    assert_eq!(table[15], 7);
+
+    // An already initialized cell keeps its value.
+    let cell: Box<SpinCell<u32>> = Box::new(SpinCell::new(|| 1));
+    assert_eq!(**cell, 1);
+    let value: &'static u32 = SpinCell::leak(cell);
+    assert_eq!(*value, 1);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_dyn_cell() {
+    extern crate alloc;
+
+    use alloc::boxed::Box;
+    use spincell::SpinDynCell;
+
+    trait Shape: Send + Sync {
+        fn area(&self) -> u32;
+    }
+
+    struct Square(u32);
+
+    impl Shape for Square {
+        fn area(&self) -> u32 {
+            self.0 * self.0
+        }
+    }
+
+    static SHAPE: SpinDynCell<dyn Shape> = SpinDynCell::new(|| Box::new(Square(3)));
+    assert_eq!(SHAPE.area(), 9);
+    assert_eq!(SpinDynCell::try_initialize(&SHAPE), Err(()));
+
+    static NAME: SpinDynCell<str> = SpinDynCell::new(|| "dynamic".into());
+    assert_eq!(&*NAME, "dynamic");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_shared_cyclic() {
+    extern crate alloc;
+
+    use alloc::sync::{Arc, Weak};
+    use spincell::SharedCyclic;
+
+    struct Node {
+        this: Weak<SpinCell<Node, SharedCyclic<Node>>>,
+    }
+
+    let node = SpinCell::new_shared_cyclic(|this| Node { this: this.clone() });
+    let clone = node.clone();
+    assert!(!SpinCell::is_initialized(&clone));
+    let this = node.this.upgrade().unwrap();
+    assert!(Arc::ptr_eq(&this, &clone));
+    assert_eq!(Arc::strong_count(&node), 3);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_secret_cell() {
+    extern crate std;
+
+    use spincell::SecretSpinCell;
+    use std::string::String;
+
+    let secret = SecretSpinCell::new(|| String::from("hunter2"));
+    assert!(!secret.is_initialized());
+    assert!(!secret.is_locked());
+    assert_eq!(secret.with(|s| s.len()), 7);
+    assert!(secret.is_initialized());
+    // Whether locking succeeds depends on RLIMIT_MEMLOCK; the value is kept
+    // either way.
+    let _ = secret.is_locked();
+    assert!(secret.with(|s| s.starts_with("hunter")));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_secret_cell_runs_observer() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use spincell::{Observed, SecretSpinCell};
+
+    static OBSERVED: AtomicUsize = AtomicUsize::new(0);
+    fn record(key: &[u8; 16]) {
+        OBSERVED.store(key.as_ptr() as usize, Ordering::Relaxed);
+    }
+    let secret = SecretSpinCell::new(Observed::new(|| [7u8; 16], record));
+    let address = secret.with(|key| key.as_ptr() as usize);
+    assert_eq!(OBSERVED.load(Ordering::Relaxed), address);
+}
+
+#[cfg(all(target_os = "linux", not(miri)))]
+mod linksectiontest {
+    spincell::spin_static! {
+        /// Lands in the test section.
+        #[link_section = "spincell_test_uninit"]
+        static TABLE: [u8; 512] = [3; 512];
+
+        // Configured out as a whole, so the undefined initializer is never
+        // compiled.
+        #[cfg(any())]
+        static MISSING: u8 = undefined();
+    }
+
+    extern "C" {
+        static __start_spincell_test_uninit: u8;
+        static __stop_spincell_test_uninit: u8;
+    }
+
+    #[test]
+    fn test_value_slot_lands_in_section() {
+        assert_eq!(TABLE[511], 3);
+        let value = &*TABLE as *const [u8; 512] as usize;
+        let cell = &TABLE as *const _ as usize;
+        let (start, stop) = unsafe {
+            (
+                &__start_spincell_test_uninit as *const u8 as usize,
+                &__stop_spincell_test_uninit as *const u8 as usize,
+            )
+        };
+        assert!(start <= value && value + 512 <= stop);
+        assert!(!(start..stop).contains(&cell));
+    }
+}
+
+#[test]
+fn test_repr_c_layout() {
+    use core::mem::{align_of, size_of};
+    use spincell::state;
+
+    // An uninitialized cell holds UNINIT at offset 0 and the initializer in
+    // the slot; once initialized it holds READY and the value.
+    let cell: SpinCell<u32> = SpinCell::new(|| 0xdead_beef);
+    let base = &cell as *const SpinCell<u32> as *const u8;
+    assert_eq!(SpinCell::<u32>::VALUE_OFFSET, align_of::<fn() -> u32>());
+    assert_eq!(unsafe { *base }, state::UNINIT);
+    assert_eq!(*cell, 0xdead_beef);
+    assert_eq!(unsafe { *base }, state::READY);
+    let value = unsafe { *(base.add(SpinCell::<u32>::VALUE_OFFSET) as *const u32) };
+    assert_eq!(value, 0xdead_beef);
+
+    assert_eq!(SpinCell::<u8, ()>::VALUE_OFFSET, 1);
+    assert_eq!(size_of::<SpinCell<u8, ()>>(), 2);
+
+    // A value smaller than the initializer pays for the difference too.
+    let word = size_of::<fn()>();
+    assert_eq!(SpinCell::<[u8; 3]>::OVERHEAD, 2 * word - 3);
+}
+
+mod ffitest {
+    use spincell::SpinCell;
+
+    static COUNTER_BASE: SpinCell<u64> = SpinCell::new(|| 1000);
+
+    spincell::spincell_ffi! {
+        COUNTER_BASE: u64 {
+            get: spincell_test_base_get,
+            is_initialized: spincell_test_base_is_initialized,
+            force_init: spincell_test_base_force_init,
+        }
+    }
+
+    #[test]
+    fn test_ffi_accessors() {
+        assert!(!spincell_test_base_is_initialized());
+        spincell_test_base_force_init();
+        assert!(spincell_test_base_is_initialized());
+        assert_eq!(unsafe { *spincell_test_base_get() }, 1000);
+        assert!(core::ptr::eq(spincell_test_base_get(), &*COUNTER_BASE));
+    }
+}
+
+#[test]
+fn test_deref_ptr() {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    let cell = SpinCell::new(|| AtomicU32::new(5));
+    let ptr = SpinCell::deref_ptr(&cell);
+    assert!(SpinCell::is_initialized(&cell));
+    assert!(core::ptr::eq(ptr.as_ptr(), &*cell));
+    // Interior mutability through the raw pointer is visible to borrowers.
+    unsafe { ptr.as_ref() }.store(6, Ordering::Relaxed);
+    assert_eq!(cell.load(Ordering::Relaxed), 6);
+}
+
+#[test]
+fn test_init_in_place() {
+    use core::mem::MaybeUninit;
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    static CALLED: AtomicBool = AtomicBool::new(false);
+    let cell: SpinCell<[u32; 4096]> = SpinCell::new(|| {
+        CALLED.store(true, Ordering::Relaxed);
+        [0; 4096]
+    });
+    let fill = |slot: &mut MaybeUninit<[u32; 4096]>| {
+        let first = slot.as_mut_ptr().cast::<u32>();
+        for i in 0..4096 {
+            unsafe { first.add(i).write(i as u32) };
+        }
+    };
+    assert_eq!(unsafe { SpinCell::init_in_place(&cell, fill) }, Ok(()));
+    assert_eq!(unsafe { SpinCell::init_in_place(&cell, fill) }, Err(()));
+    assert_eq!(cell[4095], 4095);
+    assert!(!CALLED.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_init_guard_commit_and_abort() {
+    let cell: SpinCell<[u8; 4]> = SpinCell::new(|| [1; 4]);
+
+    // Abandoned transactions leave the cell uninitialized.
+    {
+        let mut guard = SpinCell::begin_init(&cell).unwrap();
+        guard.slot().write([9; 4]);
+    }
+    assert!(!SpinCell::is_initialized(&cell));
+
+    let mut guard = SpinCell::begin_init(&cell).unwrap();
+    let first = guard.slot().as_mut_ptr().cast::<u8>();
+    for i in 0..4 {
+        unsafe { first.add(i).write(i as u8) };
+    }
+    assert_eq!(unsafe { guard.commit() }, &[0, 1, 2, 3]);
+    assert!(SpinCell::begin_init(&cell).is_none());
+    assert_eq!(*cell, [0, 1, 2, 3]);
+}
+
+#[test]
+fn test_init_guard_abort_keeps_initializer() {
+    let cell: SpinCell<u32> = SpinCell::new(|| 11);
+    drop(SpinCell::begin_init(&cell));
+    assert_eq!(*cell, 11);
+
+    let fresh: SpinCell<u32> = SpinCell::new(|| 11);
+    assert_eq!(SpinCell::begin_init(&fresh).unwrap().write(12), &12);
+    assert_eq!(*fresh, 12);
+}
+
+#[test]
+fn test_raw_slot_commit() {
+    let cell: SpinCell<[u16; 8]> = SpinCell::new(|| [0; 8]);
+    let slot = unsafe { SpinCell::uninit_slot_ptr(&cell) };
+    assert!(!slot.is_null());
+    // Stand-in for a peripheral filling the buffer.
+    unsafe {
+        slot.write([0xabcd; 8]);
+        SpinCell::assume_init_commit(&cell);
+    }
+    assert_eq!(*cell, [0xabcd; 8]);
+    assert!(unsafe { SpinCell::uninit_slot_ptr(&cell) }.is_null());
+}
+
+#[test]
+fn test_from_value() {
+    static READY_CELL: SpinCell<[u8; 3]> = SpinCell::from_value([1, 2, 3]);
+    assert!(SpinCell::is_initialized(&READY_CELL));
+    assert_eq!(SpinCell::try_initialize(&READY_CELL), Err(()));
+    assert_eq!(*READY_CELL, [1, 2, 3]);
+
+    // Dropping a pre-initialized cell drops the value, not an initializer.
+    let owned: SpinCell<&str, fn() -> &'static str> = SpinCell::from_value("value");
+    assert_eq!(*owned, "value");
+}
+
+#[test]
+fn test_eager_init_all() {
+    static EAGER_CELL: SpinCell<u32> = SpinCell::eager(|| 5);
+    static LAZY_CELL: SpinCell<u32> = SpinCell::new(|| 6);
+    assert!(!SpinCell::is_initialized(&EAGER_CELL));
+    spincell::init_all(&[&EAGER_CELL, &LAZY_CELL]);
+    assert!(SpinCell::is_initialized(&EAGER_CELL));
+    assert!(SpinCell::is_initialized(&LAZY_CELL));
+    assert_eq!(*EAGER_CELL + *LAZY_CELL, 11);
+    // Running the phase again is harmless.
+    spincell::init_all(&[&EAGER_CELL]);
+    assert_eq!(*EAGER_CELL, 5);
+}
+
+mod eager_std {
+    extern crate std;
+
+    use spincell::SpinCell;
+
+    #[test]
+    fn test_eager_access_before_init_all_panics() {
+        let cell: SpinCell<u32> = SpinCell::eager(|| 1);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| *cell));
+        assert!(result.is_err());
+        // The cell is still usable once the init phase gets to it.
+        spincell::init_all(&[&cell]);
+        assert_eq!(*cell, 1);
+    }
+
+    #[test]
+    fn test_sealed_cell_never_initializes() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        let token = Rc::new(());
+        let held = token.clone();
+        let cell = SpinCell::new(move || Rc::strong_count(&held));
+        assert!(SpinCell::seal(&cell));
+        // The initializer is dropped right away, without running.
+        assert_eq!(Rc::strong_count(&token), 1);
+        assert!(SpinCell::seal(&cell));
+        assert!(SpinCell::is_sealed(&cell));
+        assert_eq!(SpinCell::get(&cell), None);
+        assert!(catch_unwind(AssertUnwindSafe(|| *cell)).is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| SpinCell::set(&cell, 0))).is_err());
+        assert_eq!(SpinCell::debug_validate(&cell), Ok(()));
+    }
+
+    #[test]
+    fn test_sealed_cell_clone_and_into_inner_panic() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let message = "SpinCell accessed after it was sealed uninitialized";
+        let cell = SpinCell::new(|| 4u32);
+        assert!(SpinCell::seal(&cell));
+        let err = catch_unwind(AssertUnwindSafe(|| drop(cell.clone()))).unwrap_err();
+        assert_eq!(err.downcast_ref::<&str>(), Some(&message));
+        let err =
+            catch_unwind(AssertUnwindSafe(|| SpinCell::into_inner(cell).is_ok())).unwrap_err();
+        assert_eq!(err.downcast_ref::<&str>(), Some(&message));
+    }
+
+    #[test]
+    fn test_seal_waits_for_initialization_in_progress() {
+        use std::sync::Barrier;
+
+        let started = Barrier::new(2);
+        let cell = SpinCell::new(|| {
+            started.wait();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            3u32
+        });
+        std::thread::scope(|s| {
+            s.spawn(|| assert_eq!(*cell, 3));
+            started.wait();
+            assert!(!SpinCell::seal(&cell));
+        });
+        assert!(!SpinCell::is_sealed(&cell));
+        assert_eq!(*cell, 3);
+
+        let eager: SpinCell<u32> = SpinCell::eager(|| 1);
+        assert!(SpinCell::seal(&eager));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            spincell::init_all(&[&eager]);
+        }));
+        assert!(result.is_err());
+    }
+}
+
+#[test]
+fn test_swap() {
+    use spincell::SpinMutCell;
+
+    let cell: SpinMutCell<[u8; 2]> = SpinMutCell::new(|| [1, 1]);
+    assert_eq!(cell.swap([2, 2]), [1, 1]);
+    assert_eq!(cell.swap([3, 3]), [2, 2]);
+    assert_eq!(cell.read(|value| *value), [3, 3]);
+}
+
+#[test]
+fn test_replace_and_compare_swap() {
+    use spincell::SpinMutCell;
+
+    // Replacing an uninitialized cell never runs the initializer.
+    let fresh: SpinMutCell<u32> = SpinMutCell::new(|| panic!("initializer ran"));
+    assert_eq!(fresh.replace(4), None);
+    assert_eq!(fresh.replace(5), Some(4));
+
+    let cell: SpinMutCell<u32> = SpinMutCell::new(|| 1);
+    assert_eq!(cell.read(|n| *n), 1);
+    assert_eq!(cell.replace(2), Some(1));
+    assert_eq!(cell.compare_swap(|n| *n == 1, 3), Err(3));
+    assert_eq!(cell.compare_swap(|n| *n == 2, 3), Ok(2));
+    assert_eq!(cell.read(|n| *n), 3);
+}
+
+#[test]
+fn test_reset_reruns_initializer() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use spincell::SpinResetCell;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let cell = SpinResetCell::new(|| CALLS.fetch_add(1, Ordering::Relaxed));
+    assert!(!cell.is_initialized());
+    cell.reset();
+    assert_eq!(cell.read(|n| *n), 0);
+    assert_eq!(cell.read(|n| *n), 0);
+    cell.reset();
+    assert!(!cell.is_initialized());
+    assert_eq!(cell.read(|n| *n), 1);
+    cell.mutate(|n| *n = 10);
+    assert_eq!(cell.read(|n| *n), 10);
+    assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_ttl_expiry() {
+    use core::cell::Cell;
+    use spincell::SpinTtlCell;
+
+    let now = Cell::new(1000u64);
+    let builds = Cell::new(0u32);
+    let cell = SpinTtlCell::new(
+        10,
+        || now.get(),
+        || {
+            builds.set(builds.get() + 1);
+            builds.get()
+        },
+    );
+    assert_eq!(cell.read(|v| *v), 1);
+    now.set(1009);
+    assert_eq!(cell.read(|v| *v), 1);
+    now.set(1010);
+    assert_eq!(cell.read(|v| *v), 2);
+    // The age is measured from the rebuild, not from the first build.
+    now.set(1019);
+    assert_eq!(cell.read(|v| *v), 2);
+    cell.reset();
+    assert_eq!(cell.read(|v| *v), 3);
+
+    // Readings that wrap around still measure the elapsed time.
+    now.set(u64::MAX - 2);
+    assert_eq!(cell.read(|v| *v), 4);
+    now.set(5);
+    assert_eq!(cell.read(|v| *v), 4);
+    now.set(7);
+    assert_eq!(cell.read(|v| *v), 5);
+}
+
+#[test]
+fn test_generation() {
+    use spincell::{SpinMutCell, SpinResetCell};
+
+    let mutable: SpinMutCell<u32> = SpinMutCell::new(|| 0);
+    assert_eq!(mutable.generation(), 0);
+    assert_eq!(
+        mutable.read_with_gen(|v, generation| (*v, generation)),
+        (0, 1)
+    );
+    mutable.swap(1);
+    mutable.mutate(|v| *v += 1);
+    assert_eq!(mutable.compare_swap(|v| *v == 0, 5), Err(5));
+    assert_eq!(mutable.generation(), 3);
+    assert_eq!(mutable.compare_swap(|v| *v == 2, 5), Ok(2));
+    assert_eq!(
+        mutable.read_with_gen(|v, generation| (*v, generation)),
+        (5, 4)
+    );
+
+    let resettable: SpinResetCell<u32> = SpinResetCell::new(|| 7);
+    assert_eq!(resettable.generation(), 0);
+    assert_eq!(resettable.read_with_gen(|_, generation| generation), 1);
+    resettable.reset();
+    assert_eq!(resettable.generation(), 1);
+    resettable.mutate(|v| *v += 1);
+    assert_eq!(
+        resettable.read_with_gen(|v, generation| (*v, generation)),
+        (7 + 1, 3)
+    );
+}
+
+#[test]
+fn test_two_phase() {
+    use spincell::SpinTwoPhaseCell;
+
+    let cell: SpinTwoPhaseCell<u32, [u32; 2]> = SpinTwoPhaseCell::new();
+    assert!(cell.basic().is_none());
+    assert!(cell.full().is_none());
+
+    let basic = cell.init_stage1(3).ok().unwrap();
+    assert_eq!(*basic, 3);
+    assert!(basic.try_full().is_none());
+    assert_eq!(cell.init_stage1(4).err(), Some(4));
+
+    let full = basic.finalize(|n| [*n, n * 2]);
+    assert_eq!(*full, [3, 6]);
+    assert_eq!(*full.basic(), 3);
+
+    // A second finalize keeps the first full value.
+    let again = cell.basic().unwrap().finalize(|_| [0, 0]);
+    assert_eq!(*again, [3, 6]);
+}
+
+#[test]
+fn test_new_cyclic() {
+    use core::ptr::NonNull;
+
+    struct Ring {
+        id: u32,
+        next: NonNull<Ring>,
+    }
+
+    let ring = SpinCell::new_cyclic(|this| Ring {
+        id: 9,
+        next: this.as_ptr(),
+    });
+    let next = unsafe { ring.next.as_ref() };
+    assert!(core::ptr::eq(next, &*ring));
+    assert_eq!(next.id, 9);
+}
+
+#[test]
+fn test_initialize_with() {
+    let cell: SpinCell<u32> = SpinCell::new(|| panic!("initializer ran"));
+    assert_eq!(SpinCell::initialize_with(&cell, 1), Ok(()));
+    assert_eq!(SpinCell::initialize_with(&cell, 2), Err(2));
+    assert_eq!(*cell, 1);
+}
+
+#[test]
+fn test_replace_initializer() {
+    use spincell::SpinFnCell;
+
+    let cell: SpinFnCell<u32> = SpinFnCell::new(|| 1);
+    let old = SpinFnCell::replace_initializer(&cell, || 2).ok().unwrap();
+    assert_eq!(old(), 1);
+    assert!(!SpinCell::is_initialized(&cell));
+    assert_eq!(*cell, 2);
+    assert!(SpinFnCell::replace_initializer(&cell, || 3).is_err());
+    assert_eq!(*cell, 2);
+}
+
+#[test]
+fn test_into_initializer() {
+    let cell: SpinCell<u32> = SpinCell::new(|| 4);
+    let init_func = SpinCell::into_initializer(cell).ok().unwrap();
+    assert_eq!(init_func(), 4);
+
+    let used: SpinCell<u32> = SpinCell::new(|| 5);
+    assert_eq!(*used, 5);
+    let used = SpinCell::into_initializer(used).err().unwrap();
+    assert_eq!(*used, 5);
+}
+
+#[test]
+fn test_lazy_transform() {
+    use spincell::LazyTransform;
+
+    fn sum(values: [u32; 4]) -> u32 {
+        values.iter().sum()
+    }
+
+    static TOTAL: LazyTransform<[u32; 4], u32> = LazyTransform::new_transform([1, 2, 3, 4], sum);
+    assert_eq!(*TOTAL, 10);
+
+    let owned = SpinCell::new_transform([5u8; 3], |bytes| bytes.len());
+    assert_eq!(*owned, 3);
+}
+
+#[test]
+fn test_lazy_parsed() {
+    use spincell::LazyParsed;
+
+    static TABLE: [u8; 6] = [0, 1, 0, 2, 0, 3];
+
+    fn words(bytes: &'static [u8]) -> [u16; 3] {
+        let mut words = [0; 3];
+        for (word, pair) in words.iter_mut().zip(bytes.chunks_exact(2)) {
+            *word = u16::from_be_bytes([pair[0], pair[1]]);
+        }
+        words
+    }
+
+    static WORDS: LazyParsed<[u16; 3]> = LazyParsed::new_parsed(&TABLE, words);
+    assert!(!SpinCell::is_initialized(&WORDS));
+    assert_eq!(*WORDS, [1, 2, 3]);
+}
+
+#[test]
+fn test_clone() {
+    #[derive(Clone)]
+    struct Config {
+        limit: SpinCell<u32>,
+    }
+
+    let config = Config {
+        limit: SpinCell::new(|| 10),
+    };
+    let fresh = config.clone();
+    assert!(!SpinCell::is_initialized(&fresh.limit));
+    assert_eq!(*config.limit, 10);
+
+    let copy = config.clone();
+    assert!(SpinCell::is_initialized(&copy.limit));
+    assert_eq!(*copy.limit, 10);
+    assert_eq!(*fresh.limit, 10);
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn test_drop_zeroizes_value() {
+    use core::mem::MaybeUninit;
+
+    type Secret = SpinCell<[u8; 16]>;
+    let mut cell = MaybeUninit::new(Secret::new(|| [0xa5; 16]));
+    assert_eq!(unsafe { cell.assume_init_ref() }[15], 0xa5);
+    unsafe { cell.assume_init_drop() };
+    let value = unsafe { cell.as_ptr().cast::<u8>().add(Secret::VALUE_OFFSET) };
+    let bytes = unsafe { core::slice::from_raw_parts(value, 16) };
+    assert_eq!(bytes, [0; 16]);
+}
+
+#[cfg(feature = "global_alloc")]
+#[test]
+fn test_global_alloc_passthrough() {
+    use core::alloc::{GlobalAlloc, Layout};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counting {
+        base: usize,
+        live: AtomicUsize,
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            self.live.fetch_add(1, Ordering::Relaxed);
+            (self.base + layout.size()) as *mut u8
+        }
+
+        unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
+            self.live.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    static HEAP: SpinCell<Counting> = SpinCell::new(|| Counting {
+        base: 0x1000,
+        live: AtomicUsize::new(0),
+    });
+    let layout = Layout::new::<u64>();
+    assert!(!SpinCell::is_initialized(&HEAP));
+    let ptr = unsafe { HEAP.alloc(layout) };
+    assert_eq!(ptr as usize, 0x1008);
+    assert_eq!(HEAP.live.load(Ordering::Relaxed), 1);
+    unsafe { HEAP.dealloc(ptr, layout) };
+    assert_eq!(HEAP.live.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn test_std_parity() {
+    let mut lazy = SpinCell::new(|| 1u32);
+    *SpinCell::force_mut(&mut lazy) += 1;
+    assert_eq!(*SpinCell::force(&lazy), 2);
+    assert_eq!(SpinCell::into_inner(lazy).ok(), Some(2));
+
+    let lazy = SpinCell::new(|| 5u32);
+    assert_eq!(SpinCell::into_inner(lazy).err().map(|init| init()), Some(5));
+
+    let mut once: spincell::SpinOnceCell<u32> = SpinCell::empty();
+    assert_eq!(SpinCell::get_mut(&mut once), None);
+    assert_eq!(SpinCell::set(&once, 3), Ok(()));
+    assert_eq!(SpinCell::set(&once, 4), Err(4));
+    assert_eq!(*SpinCell::wait(&once), 3);
+    assert_eq!(SpinCell::take(&mut once), Some(3));
+    assert_eq!(*SpinCell::get_or_init(&once, || 6), 6);
+}
+
+#[test]
+fn test_force_pinned() {
+    use core::pin::Pin;
+
+    static CELL: SpinCell<u32> = SpinCell::new(|| 9);
+    let pinned = SpinCell::force_pinned(Pin::static_ref(&CELL));
+    assert_eq!(*pinned, 9);
+    #[allow(deprecated)]
+    let old = Pin::static_ref(&CELL).deref_pinned();
+    assert!(core::ptr::eq(pinned.get_ref(), old.get_ref()));
+}
+
+#[test]
+fn test_try_insert() {
+    let once: spincell::SpinOnceCell<u32> = SpinCell::empty();
+    assert_eq!(SpinCell::try_insert(&once, 1), Ok(&1));
+    assert_eq!(SpinCell::try_insert(&once, 2), Err((&1, 2)));
+
+    let lazy = SpinCell::new(|| 3u32);
+    assert_eq!(*lazy, 3);
+    assert_eq!(SpinCell::try_insert(&lazy, 4), Err((&3, 4)));
+    let lazy = SpinCell::new(|| 3u32);
+    assert_eq!(SpinCell::try_insert(&lazy, 4), Ok(&4));
+    assert_eq!(*lazy, 4);
+}
+
+#[test]
+fn test_into_iterator() {
+    let cell = SpinCell::new(|| [1u8, 2, 3]);
+    assert!(!SpinCell::is_initialized(&cell));
+    let doubled = (&cell).into_iter().map(|n| n * 2);
+    assert!(SpinCell::is_initialized(&cell));
+    assert!(doubled.eq([2, 4, 6]));
+    assert_eq!(cell.iter().count(), 3);
+}
+
+#[test]
+fn test_singleton_put_take() {
+    use spincell::SpinSingleton;
+
+    static DMA: SpinSingleton<[u8; 2]> = SpinSingleton::empty();
+    assert!(DMA.take().is_none());
+    assert_eq!(DMA.put([1, 2]), Ok(()));
+    assert_eq!(DMA.put([3, 4]), Err([3, 4]));
+    assert!(!DMA.is_taken());
+    assert_eq!(DMA.take(), Some([1, 2]));
+    assert!(DMA.is_taken());
+    assert_eq!(DMA.take(), None);
+    assert_eq!(DMA.put([5, 6]), Err([5, 6]));
+}
+
+#[test]
+fn test_bytes_cell_checks() {
+    use spincell::SpinBytesCell;
+
+    #[repr(C, align(8))]
+    struct Blob([u8; 9]);
+
+    static BLOB: Blob = Blob([0xa5, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+    static WRONG_SIZE: SpinBytesCell<u64> = SpinBytesCell::new(&BLOB.0);
+    static PREFIX: SpinBytesCell<u32> = SpinBytesCell::new(BLOB.0.split_at(4).0);
+    static MISALIGNED: SpinBytesCell<u32> = SpinBytesCell::new(BLOB.0.split_at(1).1.split_at(4).0);
+    static TAGGED: SpinBytesCell<[u8; 9]> = SpinBytesCell::with_check(&BLOB.0, |b| b[0] == 0xa5);
+    static REJECTED: SpinBytesCell<[u8; 9]> = SpinBytesCell::with_check(&BLOB.0, |b| b[8] == 0);
+
+    assert_eq!(WRONG_SIZE.view(), None);
+    assert_eq!(
+        PREFIX.view().map(|w| w.to_le_bytes()),
+        Some([0xa5, 0, 0, 0])
+    );
+    assert_eq!(MISALIGNED.view(), None);
+    assert_eq!(TAGGED.view().map(|b| b[8]), Some(1));
+    assert_eq!(REJECTED.view(), None);
+    assert_eq!(REJECTED.view(), None);
+}
+
+#[test]
+fn test_registry() {
+    use spincell::SpinRegistry;
+
+    let registry: SpinRegistry<&str, 2> = SpinRegistry::default();
+    assert!(registry.is_empty());
+    assert_eq!(registry.insert("net", "eth0"), Ok(&"eth0"));
+    assert_eq!(registry.insert("net", "eth1"), Err("eth1"));
+    assert_eq!(registry.insert("fs", "fat"), Ok(&"fat"));
+    assert_eq!(registry.insert("log", "uart"), Err("uart"));
+    assert_eq!(registry.get("fs"), Some(&"fat"));
+    assert_eq!(registry.get("log"), None);
+    assert_eq!(registry.len(), 2);
+    assert!(registry.iter().map(|(key, _)| key).eq(["net", "fs"]));
+}
+
+#[test]
+fn test_result_helpers() {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static PROBES: AtomicU32 = AtomicU32::new(0);
+    let ok: SpinCell<Result<u8, ()>, _> = SpinCell::new(|| {
+        PROBES.fetch_add(1, Ordering::Relaxed);
+        Ok(1)
+    });
+    assert_eq!(SpinCell::get_ok(&ok), Some(&1));
+    assert_eq!(SpinCell::get_err(&ok), None);
+    assert_eq!(SpinCell::as_result(&ok), Ok(&1));
+    assert_eq!(PROBES.load(Ordering::Relaxed), 1);
+
+    let failed: SpinCell<Result<(), u8>, _> = SpinCell::new(|| Err(5));
+    assert_eq!(SpinCell::get_err(&failed), Some(&5));
+    assert_eq!(SpinCell::get_ok(&failed), None);
+}
+
+#[test]
+fn test_observer_runs_once_after_publish() {
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use spincell::ObservedSpinCell;
+
+    static SEEN: AtomicU32 = AtomicU32::new(0);
+
+    fn observe(value: &u32) {
+        // The value is published, so the cell can be read from here.
+        assert!(SpinCell::is_initialized(&CELL));
+        SEEN.fetch_add(*value + *CELL, Ordering::Relaxed);
+    }
+
+    static CELL: ObservedSpinCell<u32> = ObservedSpinCell::new_observed(|| 3, observe);
+    assert_eq!(SEEN.load(Ordering::Relaxed), 0);
+    assert_eq!(*CELL, 3);
+    assert_eq!(*CELL, 3);
+    assert_eq!(SEEN.load(Ordering::Relaxed), 6);
+}
+
+#[test]
+fn test_named_initializer() {
+    use spincell::Initializer;
+
+    struct Zero;
+
+    impl Initializer<u8> for Zero {
+        fn init(self) -> u8 {
+            0
+        }
+    }
+
+    // Only the state byte and the value are stored.
+    static COUNTER: SpinCell<u8, Zero> = SpinCell::new(Zero);
+    assert_eq!(core::mem::size_of_val(&COUNTER), 2);
+    assert_eq!(*COUNTER, 0);
+}
+
+#[test]
+fn test_map_and_zip() {
+    use spincell::{LazyMap, LazyZip};
+
+    static BASE: SpinCell<u32> = SpinCell::new(|| 3);
+    static SQUARE: LazyMap<u32, u32> = LazyMap::new(&BASE, |n| n * n);
+    static BOTH: LazyZip<u32, u32> = LazyZip::new(&BASE, &SQUARE);
+    static SUM: LazyMap<(&u32, &u32), u32> = LazyMap::new(&BOTH, |&(a, b)| a + b);
+
+    assert!(!SpinCell::is_initialized(&BASE));
+    assert_eq!(*SUM, 12);
+    assert!(SpinCell::is_initialized(&BASE));
+    assert!(core::ptr::eq(BOTH.0, &*BASE));
+}
+
+mod grouptest {
+    extern crate std;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use spincell::{GroupMember, SpinCell, SpinCellGroup};
+
+    #[test]
+    fn test_group_initializes_all() {
+        let a: SpinCell<u32> = SpinCell::new(|| 1);
+        let b: SpinCell<u32> = SpinCell::new(|| 2);
+        assert_eq!(*b, 2);
+        SpinCellGroup::new(&[&a, &b]).init();
+        assert!(SpinCell::is_initialized(&a));
+        assert_eq!(*a + *b, 3);
+    }
+
+    #[test]
+    fn test_group_rolls_back() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counted;
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let first = SpinCell::new(|| Counted);
+        let failing: SpinCell<Counted> = SpinCell::new(|| panic!("device missing"));
+        let last = SpinCell::new(|| Counted);
+        let members: [&(dyn GroupMember + Sync); 3] = [&first, &failing, &last];
+        let group = SpinCellGroup::new(&members);
+        assert!(catch_unwind(AssertUnwindSafe(|| group.init())).is_err());
+
+        // The value built before the failure was dropped again.
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+        assert!(!SpinCell::is_initialized(&first));
+        assert!(catch_unwind(AssertUnwindSafe(|| SpinCell::try_initialize(&first))).is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| SpinCell::try_initialize(&failing))).is_err());
+        // The initializer that never ran is still there.
+        assert_eq!(SpinCell::try_initialize(&last), Ok(()));
+    }
+
+    #[test]
+    fn test_group_size_is_limited() {
+        use std::vec::Vec;
+
+        let cells: Vec<SpinCell<u32>> = (0..=SpinCellGroup::MAX_MEMBERS)
+            .map(|_| SpinCell::new((|| 1) as fn() -> u32))
+            .collect();
+        let members: Vec<&(dyn GroupMember + Sync)> = cells.iter().map(|c| c as _).collect();
+        SpinCellGroup::new(&members[1..]).init();
+        assert!(cells[1..].iter().all(SpinCell::is_initialized));
+        assert!(catch_unwind(AssertUnwindSafe(|| SpinCellGroup::new(&members))).is_err());
+    }
+}
+
+mod boottest {
+    extern crate std;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use spincell::boot::{self, BootEntry};
+    use spincell::{LazyInit, SpinCell};
+
+    static STEP: AtomicUsize = AtomicUsize::new(0);
+
+    fn step() -> usize {
+        STEP.fetch_add(1, Ordering::Relaxed)
+    }
+
+    static FIRST: SpinCell<usize> = SpinCell::new(step);
+    static SECOND: SpinCell<usize> = SpinCell::new(step);
+    static THIRD: SpinCell<usize> = SpinCell::new(step);
+
+    static FIRST_ENTRY: BootEntry = BootEntry::new(&FIRST, &[]);
+    static SECOND_ENTRY: BootEntry = BootEntry::new(&SECOND, &[&FIRST_ENTRY]);
+    static THIRD_ENTRY: BootEntry = BootEntry::new(&THIRD, &[&SECOND_ENTRY, &FIRST_ENTRY]);
+
+    #[test]
+    fn test_boot_order() {
+        boot::register(&THIRD_ENTRY);
+        boot::register(&SECOND_ENTRY);
+        boot::register(&THIRD_ENTRY);
+        boot::init_all();
+        assert!(SpinCell::is_initialized(&THIRD));
+        assert!(*FIRST < *SECOND && *SECOND < *THIRD);
+    }
+
+    spincell::register! {
+        static REGISTERED: SpinCell<u32> = SpinCell::new(|| 42);
+    }
+
+    #[test]
+    fn test_init_registered() {
+        boot::init_registered();
+        assert!(SpinCell::is_initialized(&REGISTERED));
+    }
+
+    static PING: SpinCell<u8> = SpinCell::new(|| 0);
+    static PONG: SpinCell<u8> = SpinCell::new(|| 0);
+    static PING_ENTRY: BootEntry = BootEntry::new(&PING, &[&PONG_ENTRY]);
+    static PONG_ENTRY: BootEntry = BootEntry::new(&PONG, &[&PING_ENTRY]);
+
+    #[test]
+    fn test_boot_cycle_panics() {
+        assert!(catch_unwind(AssertUnwindSafe(|| PING_ENTRY.init())).is_err());
+        assert!(!SpinCell::is_initialized(&PING));
+        assert!(!SpinCell::is_initialized(&PONG));
+    }
+
+    // Fails the first time it is initialized.
+    struct Flaky(AtomicUsize);
+
+    impl LazyInit for Flaky {
+        fn init(&self) {
+            if self.0.fetch_add(1, Ordering::Relaxed) == 0 {
+                panic!("not ready yet");
+            }
+        }
+    }
+
+    static FLAKY: Flaky = Flaky(AtomicUsize::new(0));
+    static AFTER_FLAKY: SpinCell<u8> = SpinCell::new(|| 1);
+    static FLAKY_ENTRY: BootEntry = BootEntry::new(&FLAKY, &[]);
+    static AFTER_FLAKY_ENTRY: BootEntry = BootEntry::new(&AFTER_FLAKY, &[&FLAKY_ENTRY]);
+
+    #[test]
+    fn test_boot_retries_after_panic() {
+        assert!(catch_unwind(AssertUnwindSafe(|| AFTER_FLAKY_ENTRY.init())).is_err());
+        assert!(!SpinCell::is_initialized(&AFTER_FLAKY));
+        AFTER_FLAKY_ENTRY.init();
+        assert!(SpinCell::is_initialized(&AFTER_FLAKY));
+        assert_eq!(FLAKY.0.load(Ordering::Relaxed), 2);
+    }
+}
+
+mod shutdowntest {
+    extern crate std;
+
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    static DROPPED: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+    struct Named(&'static str);
+
+    impl Drop for Named {
+        fn drop(&mut self) {
+            DROPPED.lock().unwrap().push(self.0);
+        }
+    }
+
+    spincell::shutdown_static! {
+        static DRIVER: Named = Named("driver");
+        static BUS: Named = {
+            let _ = &*DRIVER;
+            Named("bus")
+        };
+        static UNUSED: Named = Named("unused");
+    }
+
+    #[test]
+    fn test_run_destructors_in_reverse_order() {
+        assert_eq!(BUS.0, "bus");
+        unsafe { spincell::shutdown::run_destructors() };
+        // BUS finished initializing after DRIVER, so it is dropped first.
+        assert_eq!(*DROPPED.lock().unwrap(), ["bus", "driver"]);
+        assert!(catch_unwind(AssertUnwindSafe(|| BUS.0)).is_err());
+        assert!(!spincell::SpinCell::is_initialized(&UNUSED));
+        #[cfg(feature = "testing")]
+        {
+            use spincell::testing::{CellId, Counts};
+            let counts = CellId::of(&DRIVER).counts();
+            assert_eq!(
+                counts,
+                Counts {
+                    inits: 1,
+                    drops: 1,
+                    resets: 0
+                }
+            );
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynctest {
+    extern crate std;
+
+    use core::future::Future;
+    use core::pin::pin;
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use core::task::{Context, Poll, Waker};
+    use spincell::{AsyncSpinCell, SpinCell};
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_get_async_initializes() {
+        static CELL: SpinCell<u32> = SpinCell::new(|| 5);
+        let mut cx = Context::from_waker(Waker::noop());
+        let future = pin!(SpinCell::get_async(&CELL));
+        assert_eq!(future.poll(&mut cx), Poll::Ready(&5));
+    }
+
+    #[test]
+    fn test_get_async_waits_for_other_thread() {
+        static STARTED: AtomicBool = AtomicBool::new(false);
+        static RELEASE: AtomicBool = AtomicBool::new(false);
+        static CELL: SpinCell<u32> = SpinCell::new(|| {
+            STARTED.store(true, Ordering::Release);
+            while !RELEASE.load(Ordering::Acquire) {
+                std::thread::yield_now();
+            }
+            7
+        });
+
+        let initializer = std::thread::spawn(|| *CELL);
+        while !STARTED.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(SpinCell::get_async(&CELL));
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+        RELEASE.store(true, Ordering::Release);
+        assert_eq!(initializer.join().unwrap(), 7);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(future.poll(&mut cx), Poll::Ready(&7));
+    }
+
+    // Completes on the second poll.
+    struct Handshake(AtomicBool);
+
+    impl Future for Handshake {
+        type Output = u32;
+
+        fn poll(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+            if self.0.swap(true, Ordering::Relaxed) {
+                Poll::Ready(9)
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    fn test_async_cell_shares_result() {
+        static CELL: AsyncSpinCell<u32, Handshake> =
+            AsyncSpinCell::new(Handshake(AtomicBool::new(false)));
+        let cell = core::pin::Pin::static_ref(&CELL);
+
+        let driver_wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let driver_waker = Waker::from(driver_wakes.clone());
+        let waiter_wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waiter_waker = Waker::from(waiter_wakes.clone());
+
+        let mut driver = pin!(cell.get());
+        let mut waiter = pin!(cell.get());
+        let mut cx = Context::from_waker(&driver_waker);
+        assert_eq!(driver.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(driver_wakes.0.load(Ordering::SeqCst), 1);
+        let mut waiter_cx = Context::from_waker(&waiter_waker);
+        assert_eq!(waiter.as_mut().poll(&mut waiter_cx), Poll::Pending);
+        assert_eq!(CELL.try_get(), None);
+
+        assert_eq!(driver.poll(&mut cx), Poll::Ready(&9));
+        assert_eq!(waiter_wakes.0.load(Ordering::SeqCst), 1);
+        assert_eq!(waiter.poll(&mut waiter_cx), Poll::Ready(&9));
+    }
+
+    #[test]
+    fn test_async_cell_handover() {
+        let cell = pin!(AsyncSpinCell::new(Handshake(AtomicBool::new(false))));
+        let mut cx = Context::from_waker(Waker::noop());
+        {
+            let first = pin!(cell.as_ref().get());
+            assert_eq!(first.poll(&mut cx), Poll::Pending);
+        }
+        // The first awaiter gave up; the next one finishes the same future.
+        let second = pin!(cell.as_ref().get());
+        assert_eq!(second.poll(&mut cx), Poll::Ready(&9));
+        assert!(cell.is_initialized());
+    }
+
+    #[test]
+    fn test_initialized_does_not_initialize() {
+        static CELL: SpinCell<u32> = SpinCell::new(|| 11);
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut watch = pin!(SpinCell::initialized(&CELL));
+        assert_eq!(watch.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(SpinCell::poll_ready(&CELL), Poll::Pending);
+        assert!(!SpinCell::is_initialized(&CELL));
+
+        assert_eq!(*CELL, 11);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(watch.poll(&mut cx), Poll::Ready(&11));
+        assert_eq!(SpinCell::poll_ready(&CELL), Poll::Ready(&11));
+    }
+}
+
+mod compattest {
+    extern crate std;
+
+    use spincell::compat::once_cell::sync::{Lazy, OnceCell};
+    use std::panic::catch_unwind;
+
+    #[test]
+    fn test_lazy_into_value() {
+        let lazy: Lazy<u32, _> = Lazy::new(|| 3);
+        let init = Lazy::into_value(lazy).err().unwrap();
+        assert_eq!(init(), 3);
+
+        let lazy: Lazy<u32> = Lazy::new(|| 4);
+        assert_eq!(Lazy::get(&lazy), None);
+        assert_eq!(*Lazy::force(&lazy), 4);
+        assert_eq!(Lazy::into_value(lazy).ok(), Some(4));
+    }
+
+    #[test]
+    fn test_once_cell_panic_leaves_cell_empty() {
+        static CELL: OnceCell<u32> = OnceCell::new();
+        assert!(catch_unwind(|| CELL.get_or_init(|| panic!("no value"))).is_err());
+        assert_eq!(CELL.get(), None);
+        assert_eq!(*CELL.get_or_init(|| 6), 6);
+        assert_eq!(CELL.set(7), Err(7));
+    }
+
+    #[test]
+    fn test_once_cell_take() {
+        let mut cell = OnceCell::from(8u32);
+        *cell.get_mut().unwrap() += 1;
+        assert_eq!(cell.take(), Some(9));
+        assert_eq!(cell.get(), None);
+        assert_eq!(cell.into_inner(), None);
+    }
+}
+
+mod lazystatictest {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    spincell::spin_lazy_static! {
+        static ref BASE: u32 = {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            40
+        };
+        pub(crate) static ref ANSWER: u32 = *BASE + 2;
+    }
+
+    spincell::spincell! {
+        static DOUBLED: u32 = *ANSWER * 2;
+    }
+
+    #[test]
+    fn test_spincell_macro() {
+        assert_eq!(*DOUBLED, 84);
+        assert!(spincell::SpinCell::is_initialized(&ANSWER));
+    }
+
+    static OPENED: AtomicU32 = AtomicU32::new(0);
+
+    struct Window {
+        width: u32,
+        title: &'static str,
+        _unused: u8,
+    }
+
+    fn open_window() -> Window {
+        OPENED.fetch_add(1, Ordering::Relaxed);
+        Window {
+            width: 640,
+            title: "main",
+            _unused: 0,
+        }
+    }
+
+    spincell::spincell_fields! {
+        let window: Window = open_window();
+        static WIDTH: u32 = window.width;
+        pub(crate) static TITLE: &'static str = window.title;
+    }
+
+    #[test]
+    fn test_spincell_fields() {
+        assert_eq!(*TITLE, "main");
+        assert_eq!(*WIDTH, 640);
+        assert_eq!(*TITLE, "main");
+        assert_eq!(OPENED.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_spin_lazy_static() {
+        assert_eq!(*ANSWER, 42);
+        assert_eq!(*BASE, 40);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    }
+}
+
+mod buildertest {
+    extern crate std;
+
+    use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use spincell::{ConfiguredSpinCell, Initializer, Spin, SpinCell};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::string::String;
+
+    #[test]
+    fn test_defaults() {
+        let cell = SpinCell::builder(|| 5u32).build();
+        assert_eq!(ConfiguredSpinCell::get(&cell), None);
+        assert_eq!(*cell, 5);
+        assert!(ConfiguredSpinCell::is_initialized(&cell));
+        assert_eq!(ConfiguredSpinCell::name(&cell), None);
+        assert_eq!(ConfiguredSpinCell::stats(&cell), None);
+    }
+
+    #[test]
+    fn test_no_poisoning_retries() {
+        static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+        fn flaky() -> u32 {
+            if ATTEMPTS.fetch_add(1, Ordering::Relaxed) == 0 {
+                panic!("first attempt fails");
+            }
+            9
+        }
+        let cell = SpinCell::builder(flaky as fn() -> u32)
+            .no_poisoning()
+            .build();
+        assert!(catch_unwind(AssertUnwindSafe(|| *cell)).is_err());
+        assert!(!ConfiguredSpinCell::is_initialized(&cell));
+        assert_eq!(*cell, 9);
+        assert_eq!(ATTEMPTS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_no_poisoning_clone_panic_unlocks() {
+        static CLONES: AtomicU32 = AtomicU32::new(0);
+
+        // Fails to clone the first time.
+        struct FlakyClone;
+
+        impl Clone for FlakyClone {
+            fn clone(&self) -> FlakyClone {
+                if CLONES.fetch_add(1, Ordering::Relaxed) == 0 {
+                    panic!("first clone fails");
+                }
+                FlakyClone
+            }
+        }
+
+        impl Initializer<u32> for FlakyClone {
+            fn init(self) -> u32 {
+                3
+            }
+        }
+
+        let cell = SpinCell::builder(FlakyClone).no_poisoning().build();
+        assert!(catch_unwind(AssertUnwindSafe(|| *cell)).is_err());
+        assert!(!ConfiguredSpinCell::is_initialized(&cell));
+        assert_eq!(*cell, 3);
+        assert_eq!(CLONES.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_poisoning_without_clone() {
+        struct Once;
+
+        impl Initializer<u32> for Once {
+            fn init(self) -> u32 {
+                4
+            }
+        }
+
+        let cell = SpinCell::builder(Once).poisoning().build();
+        assert_eq!(*cell, 4);
+    }
+
+    #[test]
+    fn test_poisoned_panic_names_cell() {
+        let cell: ConfiguredSpinCell<u32, _> = SpinCell::builder(|| panic!("init failed"))
+            .name("NET_CFG")
+            .build();
+        assert!(catch_unwind(AssertUnwindSafe(|| *cell)).is_err());
+        let message = catch_unwind(AssertUnwindSafe(|| *cell)).unwrap_err();
+        let message = message.downcast_ref::<String>().unwrap();
+        assert_eq!(message, "SpinCell NET_CFG has previously been poisoned");
+    }
+
+    #[test]
+    fn test_stats_count_waiters() {
+        static STARTED: AtomicBool = AtomicBool::new(false);
+        static RELEASE: AtomicBool = AtomicBool::new(false);
+        static CELL: ConfiguredSpinCell<u32, fn() -> u32, Spin> =
+            SpinCell::builder(slow as fn() -> u32)
+                .strategy(Spin)
+                .stats(true)
+                .build();
+        fn slow() -> u32 {
+            STARTED.store(true, Ordering::Release);
+            while !RELEASE.load(Ordering::Acquire) {
+                std::thread::yield_now();
+            }
+            3
+        }
+
+        let initializer = std::thread::spawn(|| *CELL);
+        while !STARTED.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+        let waiter = std::thread::spawn(|| *CELL);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        RELEASE.store(true, Ordering::Release);
+        assert_eq!(initializer.join().unwrap(), 3);
+        assert_eq!(waiter.join().unwrap(), 3);
+
+        let stats = ConfiguredSpinCell::stats(&CELL).unwrap();
+        assert_eq!(stats.contended, 1);
+        assert!(stats.wait_rounds > 0);
+    }
+}
+
+// Stands in for the FreeRTOS kernel, counting the calls the crate makes.
+#[cfg(feature = "freertos")]
+mod freertostest {
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use spincell::freertos::{CriticalSpinCell, Yield};
+    use spincell::{Observed, SpinCell, WaitStrategy};
+
+    static DELAYS: AtomicU32 = AtomicU32::new(0);
+    static NESTING: AtomicU32 = AtomicU32::new(0);
+
+    #[no_mangle]
+    extern "C" fn vTaskDelay(ticks: u32) {
+        assert_eq!(ticks, 0);
+        DELAYS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[no_mangle]
+    extern "C" fn vPortEnterCritical() {
+        NESTING.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[no_mangle]
+    extern "C" fn vPortExitCritical() {
+        NESTING.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_yield_after_spinning() {
+        let mut strategy = Yield::new();
+        for _ in 0..8 {
+            strategy.wait();
+        }
+        assert_eq!(DELAYS.load(Ordering::Relaxed), 0);
+        strategy.wait();
+        strategy.wait();
+        assert_eq!(DELAYS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_critical_initializer() {
+        fn setup() -> u32 {
+            assert_eq!(NESTING.load(Ordering::Relaxed), 1);
+            7
+        }
+        static CLOCK: CriticalSpinCell<u32> = SpinCell::new_critical(setup);
+        assert_eq!(*CLOCK, 7);
+        assert_eq!(NESTING.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_critical_runs_observer() {
+        static OBSERVED: AtomicU32 = AtomicU32::new(0);
+        fn record(value: &u32) {
+            OBSERVED.store(*value, Ordering::Relaxed);
+        }
+        type Setup = Observed<u32, fn() -> u32>;
+        static TIMER: CriticalSpinCell<u32, Setup> =
+            SpinCell::new_critical(Observed::new(|| 11, record));
+        assert_eq!(*TIMER, 11);
+        assert_eq!(OBSERVED.load(Ordering::Relaxed), 11);
+        assert_eq!(NESTING.load(Ordering::Relaxed), 0);
+    }
+}
+
+// Property tests over random sequences of calls, checked against a model of
+// what the cell should hold. The sequences come from a fixed-seed xorshift
+// generator so that failures reproduce; the failing seed is in the message.
+#[cfg(test)]
+mod propertytest {
+    use core::cell::Cell;
+
+    use spincell::{SpinCell, SpinOnceCell, SpinResetCell};
+
+    // Fewer cases under Miri, which runs each one thousands of times slower.
+    const CASES: u64 = if cfg!(miri) { 8 } else { 256 };
+    const STEPS: usize = 64;
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn new(seed: u64) -> Rng {
+            Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+        }
+
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    // Counts the values created and dropped over a whole case.
+    #[derive(Default)]
+    struct Counts {
+        created: Cell<usize>,
+        dropped: Cell<usize>,
+    }
+
+    struct Tracked<'a> {
+        value: u64,
+        counts: &'a Counts,
+    }
+
+    impl<'a> Tracked<'a> {
+        fn new(value: u64, counts: &'a Counts) -> Tracked<'a> {
+            counts.created.set(counts.created.get() + 1);
+            Tracked { value, counts }
+        }
+    }
+
+    impl Drop for Tracked<'_> {
+        fn drop(&mut self) {
+            self.counts.dropped.set(self.counts.dropped.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_once_cell_matches_model() {
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let counts = Counts::default();
+            {
+                let mut cell: SpinOnceCell<Tracked> = SpinOnceCell::empty();
+                let mut model: Option<u64> = None;
+                for _ in 0..STEPS {
+                    let value = rng.next();
+                    match rng.below(4) {
+                        0 => match SpinCell::set(&cell, Tracked::new(value, &counts)) {
+                            Ok(()) => {
+                                assert!(model.is_none(), "seed {seed}");
+                                model = Some(value);
+                            }
+                            Err(rejected) => {
+                                assert_eq!(rejected.value, value, "seed {seed}");
+                                assert!(model.is_some(), "seed {seed}");
+                            }
+                        },
+                        1 => {
+                            let got = SpinCell::get_or_init(&cell, || Tracked::new(value, &counts));
+                            assert_eq!(got.value, *model.get_or_insert(value), "seed {seed}");
+                        }
+                        2 => {
+                            let taken = SpinCell::take(&mut cell).map(|t| t.value);
+                            assert_eq!(taken, model.take(), "seed {seed}");
+                        }
+                        _ => {}
+                    }
+                    assert_eq!(SpinCell::get(&cell).map(|t| t.value), model, "seed {seed}");
+                    let live = counts.created.get() - counts.dropped.get();
+                    assert_eq!(live, usize::from(model.is_some()), "seed {seed}");
+                }
+            }
+            assert_eq!(counts.created.get(), counts.dropped.get(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_lazy_cell_initializes_at_most_once() {
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let runs = Cell::new(0);
+            let cell = SpinCell::new(|| {
+                runs.set(runs.get() + 1);
+                seed
+            });
+            let mut forced = false;
+            for _ in 0..STEPS {
+                match rng.below(4) {
+                    0 => {
+                        assert_eq!(*cell, seed, "seed {seed}");
+                        forced = true;
+                    }
+                    1 => forced |= SpinCell::try_initialize(&cell).is_ok(),
+                    2 => assert_eq!(SpinCell::get(&cell).is_some(), forced, "seed {seed}"),
+                    _ => {
+                        // Another value offered by a different caller is
+                        // only taken if nothing initialized the cell first.
+                        let offered = SpinCell::initialize_with(&cell, !seed).is_ok();
+                        assert_eq!(offered, !forced, "seed {seed}");
+                        if offered {
+                            assert_eq!(*cell, !seed, "seed {seed}");
+                            break;
+                        }
+                    }
+                }
+                assert_eq!(runs.get(), usize::from(forced), "seed {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_reset_cell_matches_model() {
+        for seed in 0..CASES {
+            let mut rng = Rng::new(seed);
+            let counts = Counts::default();
+            let runs = Cell::new(0u64);
+            {
+                let cell = SpinResetCell::new(|| {
+                    runs.set(runs.get() + 1);
+                    Tracked::new(runs.get(), &counts)
+                });
+                let mut model: Option<u64> = None;
+                let mut generation = 0;
+                for _ in 0..STEPS {
+                    match rng.below(4) {
+                        0 => {
+                            let value = cell.read(|t| t.value);
+                            let expected = *model.get_or_insert_with(|| {
+                                generation += 1;
+                                runs.get()
+                            });
+                            assert_eq!(value, expected, "seed {seed}");
+                        }
+                        1 => {
+                            let value = rng.next();
+                            cell.mutate(|t| t.value = value);
+                            if model.is_none() {
+                                generation += 1;
+                            }
+                            generation += 1;
+                            model = Some(value);
+                        }
+                        2 => {
+                            cell.reset();
+                            model = None;
+                        }
+                        _ => assert_eq!(cell.generation(), generation, "seed {seed}"),
+                    }
+                    assert_eq!(cell.is_initialized(), model.is_some(), "seed {seed}");
+                    let live = counts.created.get() - counts.dropped.get();
+                    assert_eq!(live, usize::from(model.is_some()), "seed {seed}");
+                }
+            }
+            assert_eq!(counts.created.get(), counts.dropped.get(), "seed {seed}");
+        }
+    }
+}
+
+// The unsafe paths again, with values and initializers that own heap
+// memory: under Miri (`cargo +nightly miri test`) any double drop, leak,
+// use of a consumed initializer or read of uninitialized storage fails the
+// test. They run as ordinary tests too.
+#[cfg(test)]
+mod miritest {
+    extern crate std;
+
+    use core::mem::MaybeUninit;
+    use std::boxed::Box;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::string::{String, ToString};
+    use std::thread;
+    use std::vec::Vec;
+
+    use spincell::{SpinCell, SpinMutCell, SpinOnceCell, SpinResetCell};
+
+    #[test]
+    fn test_slot_switches_from_initializer_to_value() {
+        let prefix = String::from("id-");
+        let cell = SpinCell::new(move || prefix + "7");
+        assert_eq!(SpinCell::get(&cell), None);
+        assert_eq!(*cell, "id-7");
+        assert_eq!(SpinCell::into_inner(cell).ok().as_deref(), Some("id-7"));
+
+        let suffix = String::from("8");
+        let cell = SpinCell::new(move || Box::new(suffix));
+        let init_func = SpinCell::into_inner(cell).unwrap_err();
+        assert_eq!(*init_func(), "8");
+    }
+
+    #[test]
+    fn test_drop_uninitialized_initialized_and_poisoned() {
+        let captured = Box::new([7u8; 32]);
+        drop(SpinCell::new(move || captured.to_vec()));
+
+        let cell = SpinCell::new(|| Vec::from([String::from("a"), String::from("b")]));
+        assert_eq!(cell.len(), 2);
+        drop(cell);
+
+        // The panic consumes the initializer, so the drop of the poisoned
+        // cell must not touch the slot again.
+        let owned = String::from("consumed");
+        let cell = SpinCell::new(move || -> String {
+            drop(owned);
+            panic!("init failed")
+        });
+        assert!(catch_unwind(AssertUnwindSafe(|| cell.len())).is_err());
+        drop(cell);
+    }
+
+    #[test]
+    fn test_in_place_and_raw_slot_writes() {
+        let cell: SpinCell<[String; 2]> = SpinCell::new(|| [String::new(), String::new()]);
+        let fill = |slot: &mut MaybeUninit<[String; 2]>| {
+            slot.write([String::from("x"), String::from("y")]);
+        };
+        assert_eq!(unsafe { SpinCell::init_in_place(&cell, fill) }, Ok(()));
+        assert_eq!(cell[1], "y");
+
+        let cell: SpinCell<Box<u64>> = SpinCell::new(|| Box::new(0));
+        let slot = unsafe { SpinCell::uninit_slot_ptr(&cell) };
+        unsafe {
+            slot.write(Box::new(9));
+            SpinCell::assume_init_commit(&cell);
+        }
+        assert_eq!(**cell, 9);
+    }
+
+    #[test]
+    fn test_guard_restores_heap_initializer() {
+        let cell = SpinCell::new(Box::new(|| String::from("first")) as Box<dyn FnOnce() -> String>);
+        drop(SpinCell::begin_init(&cell));
+        let previous = SpinCell::replace_initializer(&cell, Box::new(|| String::from("second")));
+        assert_eq!(previous.ok().unwrap()(), "first");
+        assert_eq!(*cell, "second");
+        assert_eq!(
+            SpinCell::initialize_with(&cell, "late".to_string()).unwrap_err(),
+            "late"
+        );
+    }
+
+    #[test]
+    fn test_take_and_refill() {
+        let mut cell: SpinOnceCell<Box<str>> = SpinOnceCell::empty();
+        for round in 0..3 {
+            assert_eq!(SpinCell::set(&cell, round.to_string().into()), Ok(()));
+            assert!(SpinCell::set(&cell, "again".into()).is_err());
+            assert_eq!(
+                SpinCell::take(&mut cell).as_deref(),
+                Some(&*round.to_string())
+            );
+        }
+        assert_eq!(SpinCell::take(&mut cell), None);
+    }
+
+    #[test]
+    fn test_reset_and_swap() {
+        let cell = SpinResetCell::new(|| Vec::from([String::from("seed")]));
+        cell.mutate(|v| v.push(String::from("more")));
+        cell.reset();
+        assert_eq!(cell.read(|v| v.len()), 1);
+
+        let cell = SpinMutCell::new(|| String::from("old"));
+        assert_eq!(cell.swap(String::from("new")), "old");
+        assert_eq!(cell.replace(String::from("newer")).as_deref(), Some("new"));
+        assert_eq!(cell.read(|s| s.clone()), "newer");
+    }
+
+    #[test]
+    fn test_threads_see_published_value() {
+        let cell = SpinCell::new(|| (0..16u64).collect::<Vec<_>>());
+        thread::scope(|s| {
+            for _ in 0..3 {
+                s.spawn(|| assert_eq!(cell.iter().sum::<u64>(), 120));
+            }
+        });
+    }
+}
+
+#[cfg(feature = "testing")]
+mod testingtest {
+    use spincell::testing::ManualClock;
+    use spincell::{SpinResetCell, SpinTtlCell};
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(u64::MAX - 1);
+        assert_eq!(clock.advance(3), 1);
+        clock.set(40);
+        assert_eq!(clock.now(), 40);
+        assert_eq!(ManualClock::default().now(), 0);
+    }
+
+    // Walks a TTL cell across the edges of its lifetime: the last tick
+    // before expiry, the expiry tick itself, mutations, resets, and clock
+    // readings that wrap around.
+    #[test]
+    fn test_ttl_edges() {
+        static CLOCK: ManualClock = ManualClock::new(500);
+        let cell = SpinTtlCell::new(8, &CLOCK, || CLOCK.now());
+        assert_eq!(cell.read(|v| *v), 500);
+        assert_eq!(cell.generation(), 1);
+
+        CLOCK.set(507);
+        assert_eq!(cell.read(|v| *v), 500);
+        // A mutation changes the value, not its age.
+        cell.mutate(|v| *v += 1);
+        CLOCK.set(508);
+        assert_eq!(cell.read(|v| *v), 508);
+        assert_eq!(cell.generation(), 3);
+
+        // Looking at the generation does not rebuild an expired value.
+        CLOCK.advance(100);
+        assert_eq!(cell.generation(), 3);
+        assert_eq!(cell.read(|v| *v), 608);
+
+        // After a reset the age starts over from the rebuild.
+        CLOCK.advance(7);
+        cell.reset();
+        assert_eq!(cell.read(|v| *v), 615);
+        CLOCK.advance(7);
+        assert_eq!(cell.read(|v| *v), 615);
+
+        CLOCK.set(u64::MAX - 3);
+        assert_eq!(cell.read(|v| *v), u64::MAX - 3);
+        assert_eq!(CLOCK.advance(7), 3);
+        assert_eq!(cell.read(|v| *v), u64::MAX - 3);
+        CLOCK.advance(1);
+        assert_eq!(cell.read(|v| *v), 4);
+    }
+
+    #[test]
+    fn test_counts() {
+        extern crate std;
+
+        use spincell::testing::{CellId, Counts};
+        use spincell::{SpinCell, SpinOnceCell};
+        use std::string::String;
+
+        static CONFIG: SpinCell<u32> = SpinCell::new(|| 3);
+        let config = CellId::of(&CONFIG);
+        assert_eq!(config.counts(), Counts::default());
+        assert_eq!(*CONFIG + *CONFIG, 6);
+        assert_eq!(config.counts().inits, 1);
+
+        let id = {
+            let cell = SpinCell::new(|| String::from("dropped"));
+            assert_eq!(cell.len(), 7);
+            CellId::of(&cell)
+        };
+        let counts = id.counts();
+        assert_eq!((counts.inits, counts.drops, counts.resets), (1, 1, 0));
+        id.clear();
+        assert_eq!(id.counts(), Counts::default());
+
+        let mut once: SpinOnceCell<u8> = SpinOnceCell::empty();
+        assert_eq!(SpinCell::set(&once, 1), Ok(()));
+        assert_eq!(SpinCell::take(&mut once), Some(1));
+        assert_eq!(CellId::of(&once).counts().resets, 1);
+
+        let id = {
+            let cell = SpinResetCell::new(|| 0u8);
+            cell.reset();
+            cell.read(|_| {});
+            cell.reset();
+            cell.read(|_| {});
+            let id = CellId::of(&cell);
+            let counts = id.counts();
+            assert_eq!((counts.inits, counts.drops, counts.resets), (2, 1, 1));
+            id
+        };
+        assert_eq!(id.counts().drops, 2);
+
+        static CLOCK: ManualClock = ManualClock::new(0);
+        let ttl = SpinTtlCell::new(5, &CLOCK, || 0u8);
+        ttl.read(|_| {});
+        CLOCK.advance(5);
+        ttl.read(|_| {});
+        let counts = CellId::of(&ttl).counts();
+        assert_eq!((counts.inits, counts.drops, counts.resets), (2, 1, 0));
+    }
+
+    #[test]
+    fn test_counts_of_values_written_in() {
+        use spincell::testing::CellId;
+        use spincell::SpinCell;
+
+        let cell = SpinCell::new(|| 0u32);
+        unsafe {
+            SpinCell::init_in_place(&cell, |slot| {
+                slot.write(1);
+            })
+        }
+        .unwrap();
+        assert_eq!(CellId::of(&cell).counts().inits, 1);
+
+        let cell = SpinCell::new(|| 0u32);
+        SpinCell::begin_init(&cell).unwrap().write(2);
+        let once: spincell::SpinOnceCell<u32> = SpinCell::empty();
+        assert_eq!(SpinCell::set(&once, 3), Ok(()));
+        assert_eq!(CellId::of(&cell).counts().inits, 1);
+        assert_eq!(CellId::of(&once).counts().inits, 1);
+    }
+
+    // The id of `cell` with its counts cleared, since an earlier test may
+    // have counted a cell at the same address.
+    fn fresh_id<C: spincell::testing::Instrumented>(cell: &C) -> spincell::testing::CellId {
+        let id = spincell::testing::CellId::of(cell);
+        id.clear();
+        id
+    }
+
+    #[test]
+    fn test_counts_of_group_members() {
+        extern crate std;
+
+        use spincell::testing::Counts;
+        use spincell::{GroupMember, SpinCell, SpinCellGroup};
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let first = SpinCell::new(|| 1u32);
+        let second = SpinCell::new(|| 2u32);
+        let ids = [fresh_id(&first), fresh_id(&second)];
+        SpinCellGroup::new(&[&first, &second]).init();
+        assert!(ids.iter().all(|id| id.counts().inits == 1));
+
+        let built = SpinCell::new(|| 1u32);
+        let failing: SpinCell<u32> = SpinCell::new(|| panic!("device missing"));
+        let ids = [fresh_id(&built), fresh_id(&failing)];
+        let members: [&(dyn GroupMember + Sync); 2] = [&built, &failing];
+        let group = SpinCellGroup::new(&members);
+        assert!(catch_unwind(AssertUnwindSafe(|| group.init())).is_err());
+        let built = Counts {
+            inits: 1,
+            drops: 1,
+            resets: 0,
+        };
+        assert_eq!(ids[0].counts(), built);
+        assert_eq!(ids[1].counts().inits, 1);
+    }
+
+    #[test]
+    fn test_counts_of_mut_cell_replace() {
+        use spincell::testing::Counts;
+        use spincell::SpinMutCell;
+
+        let id = {
+            let cell = SpinMutCell::new(|| 0u32);
+            let id = fresh_id(&cell);
+            assert_eq!(cell.replace(4), None);
+            assert_eq!(cell.replace(5), Some(4));
+            id
+        };
+        assert_eq!(
+            id.counts(),
+            Counts {
+                inits: 1,
+                drops: 1,
+                resets: 0
+            }
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_counts_of_async_cell() {
+        use core::future::Future;
+        use core::pin::pin;
+        use core::task::{Context, Poll, Waker};
+        use spincell::AsyncSpinCell;
+
+        let cell = pin!(AsyncSpinCell::new(core::future::ready(3u32)));
+        let id = fresh_id(&*cell);
+        let mut cx = Context::from_waker(Waker::noop());
+        let get = pin!(cell.as_ref().get());
+        assert_eq!(get.poll(&mut cx), Poll::Ready(&3));
+        assert_eq!(id.counts().inits, 1);
+    }
+
+    #[test]
+    fn test_counts_entries_released_on_drop() {
+        extern crate std;
+
+        use spincell::testing::CellId;
+        use spincell::SpinCell;
+        use std::boxed::Box;
+        use std::string::String;
+
+        // Far more cells than the table has room for, each with events but
+        // without an id, and each dropped before the next is created. The
+        // spacers, cells without events, keep the freed memory from being
+        // reused, so every cell counted lives at an address of its own.
+        let mut spacers = std::vec::Vec::new();
+        for _ in 0..1000 {
+            let cell = Box::new(SpinCell::new(|| String::from("temporary")));
+            assert_eq!(cell.len(), 9);
+            drop(cell);
+            spacers.push(Box::new(SpinCell::new(String::new)));
+        }
+
+        let cell = SpinCell::new(|| 5u8);
+        assert_eq!(*cell, 5);
+        assert_eq!(CellId::of(&cell).counts().inits, 1);
+    }
+
+    #[test]
+    fn test_mock_outcomes() {
+        extern crate std;
+
+        use spincell::testing::{poisoned, Mock, MockInit};
+        use spincell::SpinCell;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let mock = Mock::new();
+        let failing: SpinCell<Result<u8, &str>, _> = SpinCell::new(mock.failing("offline"));
+        assert_eq!(SpinCell::get_err(&failing), Some(&"offline"));
+        assert_eq!(SpinCell::get_ok(&failing), None);
+
+        let panicking: SpinCell<u8, _> = SpinCell::new(mock.panicking());
+        assert!(catch_unwind(AssertUnwindSafe(|| *panicking)).is_err());
+        assert!(catch_unwind(AssertUnwindSafe(|| *panicking)).is_err());
+        assert_eq!(mock.calls(), 2);
+
+        // An unused initializer is never counted.
+        drop(SpinCell::new(mock.returning(1u8)));
+        assert_eq!(mock.calls(), 2);
+
+        let cell: SpinCell<u8, MockInit<u8>> = poisoned(mock.returning(1));
+        assert!(!SpinCell::is_initialized(&cell));
+        assert!(catch_unwind(AssertUnwindSafe(|| *cell)).is_err());
+        assert_eq!(mock.calls(), 2);
+    }
+
+    #[test]
+    fn test_drop_tracker() {
+        use spincell::testing::DropCounter;
+
+        let counter = DropCounter::new();
+        {
+            let cell = SpinResetCell::new(|| counter.track(0u32));
+            cell.mutate(|v| **v += 1);
+            cell.reset();
+            assert_eq!(counter.live(), 0);
+            assert_eq!(cell.read(|v| **v), 0);
+            assert_eq!(counter.live(), 1);
+        }
+        assert_eq!((counter.created(), counter.dropped()), (2, 2));
+    }
+
+    #[test]
+    fn test_ttl_zero_rebuilds_every_access() {
+        static CLOCK: ManualClock = ManualClock::new(0);
+        let builds = core::cell::Cell::new(0);
+        let cell = SpinTtlCell::new(0, &CLOCK, || {
+            builds.set(builds.get() + 1);
+            builds.get()
+        });
+        assert_eq!(cell.read(|v| *v), 1);
+        assert_eq!(cell.read(|v| *v), 2);
+        assert_eq!(cell.mutate(|v| *v), 3);
+    }
+}
+
+#[cfg(feature = "test-interleave")]
+#[cfg(test)]
+mod interleavetest {
+    extern crate std;
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use spincell::interleave::{set_hook, YieldPoint};
+    use spincell::SpinCell;
+    use std::sync::Mutex;
+    use std::thread;
+
+    // The hook is global, so the tests take turns installing it.
+    static SERIAL: Mutex<()> = Mutex::new(());
+    static TARGET: AtomicUsize = AtomicUsize::new(0);
+    static POINT: AtomicUsize = AtomicUsize::new(0);
+    static HELD: AtomicBool = AtomicBool::new(false);
+    static RELEASE: AtomicBool = AtomicBool::new(false);
+
+    fn hold(point: YieldPoint, state: usize) {
+        if state == TARGET.load(Ordering::SeqCst) && point as usize == POINT.load(Ordering::SeqCst)
+        {
+            HELD.store(true, Ordering::SeqCst);
+            while !RELEASE.load(Ordering::SeqCst) {
+                thread::yield_now();
+            }
+        }
+    }
+
+    // Runs `access` on another thread, holds it at `point` of `cell` and
+    // calls `check` while it is held.
+    fn held_at<C: Sync>(
+        point: YieldPoint,
+        cell: &C,
+        access: impl FnOnce() + Send,
+        check: impl FnOnce(),
+    ) {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        TARGET.store(cell as *const C as usize, Ordering::SeqCst);
+        POINT.store(point as usize, Ordering::SeqCst);
+        HELD.store(false, Ordering::SeqCst);
+        RELEASE.store(false, Ordering::SeqCst);
+        set_hook(Some(hold));
+        thread::scope(|s| {
+            let holder = s.spawn(access);
+            while !HELD.load(Ordering::SeqCst) {
+                thread::yield_now();
+            }
+            check();
+            RELEASE.store(true, Ordering::SeqCst);
+            let _ = holder.join();
+        });
+        set_hook(None);
+    }
+
+    #[test]
+    fn test_locked_cell_is_not_initialized() {
+        let cell = SpinCell::new(|| 7u32);
+        held_at(
+            YieldPoint::Locked,
+            &cell,
+            || assert_eq!(*cell, 7),
+            || {
+                assert!(!SpinCell::is_initialized(&cell));
+                assert_eq!(SpinCell::get(&cell), None);
+            },
+        );
+        assert_eq!(SpinCell::get(&cell), Some(&7));
+    }
+
+    #[test]
+    fn test_written_value_is_hidden_until_published() {
+        let cell = SpinCell::new(|| 7u32);
+        held_at(
+            YieldPoint::BeforePublish,
+            &cell,
+            || assert_eq!(*cell, 7),
+            || assert_eq!(SpinCell::get(&cell), None),
+        );
+        assert_eq!(SpinCell::get(&cell), Some(&7));
+    }
+
+    #[test]
+    fn test_waiter_sees_value_of_held_initializer() {
+        let runs = AtomicUsize::new(0);
+        let cell = SpinCell::new(|| {
+            runs.fetch_add(1, Ordering::SeqCst);
+            7u32
+        });
+        thread::scope(|s| {
+            held_at(
+                YieldPoint::Locked,
+                &cell,
+                || assert_eq!(*cell, 7),
+                || {
+                    s.spawn(|| assert_eq!(*cell, 7));
+                    assert_eq!(runs.load(Ordering::SeqCst), 0);
+                },
+            );
+        });
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_poisoning_unlocks_through_hook() {
+        let cell: SpinCell<u32> = SpinCell::new(|| panic!("init failed"));
+        held_at(
+            YieldPoint::BeforeUnlock,
+            &cell,
+            || {
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| *cell));
+            },
+            || assert!(!SpinCell::is_initialized(&cell)),
+        );
+        assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| *cell)).is_err());
+    }
+}
+
+#[cfg(feature = "spurious-cas")]
+#[cfg(test)]
+mod spuriouscastest {
+    extern crate std;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use spincell::{SpinCell, SpinMutCell, SpinResetCell};
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn test_every_lock_path_retries() {
+        for round in 0..1000u32 {
+            let cell = SpinCell::new(|| round);
+            assert_eq!(*cell, round);
+
+            let counter = SpinMutCell::new(|| 0u32);
+            for _ in 0..4 {
+                counter.mutate(|n| *n += 1);
+            }
+            assert_eq!(counter.read(|n| *n), 4);
+
+            let reset = SpinResetCell::new(|| round);
+            reset.reset();
+            assert_eq!(reset.read(|n| *n), round);
+        }
+    }
+
+    #[test]
+    fn test_racing_threads_initialize_once() {
+        for round in 0..100 {
+            let runs = AtomicUsize::new(0);
+            let cell = SpinCell::new(|| {
+                runs.fetch_add(1, Ordering::Relaxed);
+                round
+            });
+            let barrier = Barrier::new(4);
+            thread::scope(|s| {
+                for _ in 0..4 {
+                    s.spawn(|| {
+                        barrier.wait();
+                        assert_eq!(*cell, round);
+                    });
+                }
+            });
+            assert_eq!(runs.load(Ordering::Relaxed), 1);
+        }
+    }
+}
+
+#[cfg(feature = "nightly")]
+#[cfg(test)]
+mod taittest {
+    use core::mem::size_of;
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use spincell::SpinCell;
+
+    static RUNS: AtomicU32 = AtomicU32::new(0);
+
+    spincell::spincell_tait! {
+        static BASE: SpinCell<u32, BaseInit> = {
+            RUNS.fetch_add(1, Ordering::Relaxed);
+            20
+        };
+        /// Documented statics keep their attributes.
+        pub static DERIVED: SpinCell<[u32; 2], DerivedInit> = [*BASE, *BASE + 1];
+    }
+
+    #[test]
+    fn test_tait_statics_initialize_once() {
+        assert_eq!(*DERIVED, [20, 21]);
+        assert_eq!(*BASE, 20);
+        assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_tait_initializers_take_no_space() {
+        assert_eq!(size_of::<BaseInit>(), 0);
+        assert_eq!(size_of::<DerivedInit>(), 0);
+        assert_eq!(
+            size_of::<SpinCell<u32, BaseInit>>(),
+            size_of::<SpinCell<u32, ()>>()
+        );
+        assert!(size_of::<SpinCell<u32, BaseInit>>() < size_of::<SpinCell<u32>>());
+    }
+}
+
+#[cfg(test)]
+mod boundedtest {
+    extern crate std;
+
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::thread;
+
+    use spincell::BoundedSpinCell;
+
+    #[test]
+    fn test_bounded_initializes_once() {
+        let cell: BoundedSpinCell<u32, _, 0> = BoundedSpinCell::new(|| 9u32);
+        assert_eq!(BoundedSpinCell::get(&cell), None);
+        assert_eq!(BoundedSpinCell::try_force(&cell), Some(&9));
+        assert!(BoundedSpinCell::is_initialized(&cell));
+        assert_eq!(*cell, 9);
+    }
+
+    #[test]
+    fn test_bounded_gives_up_while_locked() {
+        static STARTED: AtomicBool = AtomicBool::new(false);
+        static RELEASE: AtomicBool = AtomicBool::new(false);
+        fn overrun() -> ! {
+            panic!("overrun hook")
+        }
+        let cell: BoundedSpinCell<u32, _, 64> = BoundedSpinCell::with_overrun(
+            || {
+                STARTED.store(true, Ordering::Release);
+                while !RELEASE.load(Ordering::Acquire) {
+                    thread::yield_now();
+                }
+                5u32
+            },
+            overrun,
+        );
+        thread::scope(|s| {
+            let initializer = s.spawn(|| *cell);
+            while !STARTED.load(Ordering::Acquire) {
+                thread::yield_now();
+            }
+            assert_eq!(BoundedSpinCell::try_force(&cell), None);
+            let err = catch_unwind(AssertUnwindSafe(|| *cell)).unwrap_err();
+            assert_eq!(err.downcast_ref::<&str>(), Some(&"overrun hook"));
+            RELEASE.store(true, Ordering::Release);
+            assert_eq!(initializer.join().unwrap(), 5);
+        });
+        assert_eq!(BoundedSpinCell::try_force(&cell), Some(&5));
+    }
+
+    #[test]
+    fn test_bounded_default_overrun_panics() {
+        static STARTED: AtomicBool = AtomicBool::new(false);
+        static RELEASE: AtomicBool = AtomicBool::new(false);
+        let cell: BoundedSpinCell<u32, _, 0> = BoundedSpinCell::new(|| {
+            STARTED.store(true, Ordering::Release);
+            while !RELEASE.load(Ordering::Acquire) {
+                thread::yield_now();
+            }
+            1u32
+        });
+        thread::scope(|s| {
+            let initializer = s.spawn(|| *cell);
+            while !STARTED.load(Ordering::Acquire) {
+                thread::yield_now();
+            }
+            let err = catch_unwind(AssertUnwindSafe(|| *cell)).unwrap_err();
+            let message = err.downcast_ref::<std::string::String>().unwrap();
+            assert_eq!(message, "BoundedSpinCell still locked after 0 spins");
+            RELEASE.store(true, Ordering::Release);
+            assert_eq!(initializer.join().unwrap(), 1);
+        });
+        assert_eq!(*cell, 1);
+    }
+}